@@ -2,41 +2,18 @@ use pregel_rs::graph_frame::GraphFrame;
 use pschema_rs::backends::ntriples::NTriples;
 use pschema_rs::backends::Backend;
 use pschema_rs::pschema::PSchema;
-use pschema_rs::shape::shex::NodeConstraint;
+use pschema_rs::shape;
 use pschema_rs::shape::shex::Shape;
-use pschema_rs::shape::shex::{ShapeAnd, ShapeReference, TripleConstraint};
 
 fn main() -> Result<(), String> {
     // Define validation rules
-    let start: Shape<&str> = ShapeAnd::new(
-        "Person",
-        vec![
-            TripleConstraint::new(
-                "Date",
-                "<http://example.org/dateOfBirth>",
-                NodeConstraint::Any,
-            )
-            .into(),
-            ShapeReference::new(
-                "Place",
-                "<http://example.org/placeOfBirth>",
-                TripleConstraint::new(
-                    "Country",
-                    "<http://example.org/country>",
-                    NodeConstraint::Any,
-                )
-                .into(),
-            )
-            .into(),
-            TripleConstraint::new(
-                "Organization",
-                "<http://example.org/employer>",
-                NodeConstraint::Any,
-            )
-            .into(),
-        ],
-    )
-    .into();
+    let start: Shape<&str> = shape!(and "Person" {
+        "Date": "<http://example.org/dateOfBirth>" => *,
+        ref "Place": "<http://example.org/placeOfBirth>" => {
+            "Country": "<http://example.org/country>" => *
+        },
+        "Organization": "<http://example.org/employer>" => *,
+    });
 
     // Load Wikidata entities
     let edges = NTriples::import("./examples/paper/paper.nt")?;
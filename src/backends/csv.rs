@@ -0,0 +1,141 @@
+use std::fs::File;
+
+use polars::prelude::*;
+use pregel_rs::pregel::Column;
+
+use super::Backend;
+use crate::utils::check::check_string_cache;
+
+pub struct Csv;
+
+/// The `Csv` block defines a Rust module that contains `import` and `export`.
+/// It is meant for debugging and for sharing subsets with spreadsheets, so the
+/// files have a header with, at least, the `subject`, `predicate` and `object`
+/// columns.
+impl Backend for Csv {
+    /// This function reads the edges stored in a CSV file. Any column other than
+    /// `subject`, `predicate` and `object` is ignored, and the three of them are
+    /// cast to `Categorical`, as `NTriples::import` does.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The path to the CSV file.
+    ///
+    /// Returns:
+    ///
+    /// This function returns a `Result<DataFrame, String>`, where the `String`
+    /// is an error message in case the file cannot be read or any of the
+    /// required columns is missing.
+    fn import(path: &str) -> Result<DataFrame, String> {
+        check_string_cache().map_err(|error| error.to_string())?;
+
+        let edges = match CsvReadOptions::default()
+            .with_has_header(true)
+            .try_into_reader_with_file_path(Some(path.into()))
+            .and_then(|reader| reader.finish())
+        {
+            Ok(edges) => edges,
+            Err(error) => return Err(format!("Error reading the CSV file: {}", error)),
+        };
+
+        let dtype = DataType::Categorical(None, CategoricalOrdering::Lexical);
+        let columns = [Column::Subject, Column::Predicate, Column::Object];
+        if let Some(column) = columns
+            .iter()
+            .find(|column| edges.column(column.as_ref()).is_err())
+        {
+            return Err(format!("The CSV file has no {} column", column.as_ref()));
+        }
+        match edges
+            .lazy()
+            .select(
+                columns
+                    .iter()
+                    .map(|column| col(column.as_ref()).cast(dtype.clone()))
+                    .collect::<Vec<_>>(),
+            )
+            .collect()
+        {
+            Ok(edges) => Ok(edges),
+            Err(_) => Err(String::from("Error casting the edges")),
+        }
+    }
+
+    fn export(path: &str, df: &mut DataFrame) -> Result<(), String> {
+        let mut file = match File::create(path) {
+            Ok(file) => file,
+            Err(_) => return Err(String::from("Error creating the CSV file")),
+        };
+
+        let mut edges = match df
+            .clone()
+            .lazy()
+            .select([
+                col(Column::Subject.as_ref()).cast(DataType::String),
+                col(Column::Predicate.as_ref()).cast(DataType::String),
+                col(Column::Object.as_ref()).cast(DataType::String),
+            ])
+            .collect()
+        {
+            Ok(edges) => edges,
+            Err(_) => {
+                return Err(String::from(
+                    "The subject, predicate and object columns are required",
+                ))
+            }
+        };
+
+        match CsvWriter::new(&mut file)
+            .include_header(true)
+            .finish(&mut edges)
+        {
+            Ok(_) => Ok(()),
+            Err(_) => Err(String::from("Error writing to the CSV file")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pregel_rs::pregel::Column;
+
+    use super::Csv;
+    use crate::backends::Backend;
+
+    #[test]
+    fn extra_columns_test() -> Result<(), String> {
+        let path = std::env::temp_dir().join("pschema-extra-columns.csv");
+        let content = concat!(
+            "subject,predicate,object,comment\n",
+            "<http://example.org/alan>,<http://example.org/type>,<http://example.org/Person>,ok\n",
+        );
+        if std::fs::write(&path, content).is_err() {
+            return Err(String::from("Error writing the CSV file"));
+        }
+
+        let mut edges = Csv::import(path.to_str().unwrap())?;
+        let exported = std::env::temp_dir().join("pschema-extra-columns-exported.csv");
+        Csv::export(exported.to_str().unwrap(), &mut edges)?;
+
+        match Csv::import(exported.to_str().unwrap()) {
+            Ok(actual) if actual.get_column_names().len() == 3 && actual.equals(&edges) => Ok(()),
+            Ok(actual) => Err(format!("Unexpected edges: {:?}", actual)),
+            Err(error) => Err(error),
+        }
+    }
+
+    #[test]
+    fn missing_column_test() -> Result<(), String> {
+        let path = std::env::temp_dir().join("pschema-missing-column.csv");
+        let content = "subject,object\n<http://example.org/alan>,<http://example.org/Person>\n";
+        if std::fs::write(&path, content).is_err() {
+            return Err(String::from("Error writing the CSV file"));
+        }
+
+        match Csv::import(path.to_str().unwrap()) {
+            Ok(_) => Err(String::from("The predicate column should be missing")),
+            Err(error) if error.contains(Column::Predicate.as_ref()) => Ok(()),
+            Err(error) => Err(error),
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use duckdb::arrow::array::{Array, UInt32Array};
 use duckdb::arrow::record_batch::RecordBatch;
-use duckdb::Connection;
+use duckdb::{params, Connection};
 use polars::frame::DataFrame;
 use polars::prelude::NamedFrom;
 use polars::series::Series;
@@ -8,18 +8,33 @@ use pregel_rs::pregel::Column;
 use rayon::iter::ParallelIterator;
 use rayon::prelude::IntoParallelIterator;
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use strum::IntoEnumIterator;
 use wikidata_rs::dtype::DataType;
 use wikidata_rs::id::Id;
 
 use super::Backend;
 
+/// The `DuckDB` backend reads the databases produced by `wd2duckdb`, whose ids
+/// are stored as `UINTEGER`, so the imported columns hold `u32` ids. Databases
+/// with ids beyond `u32::MAX` cannot be imported by this backend; such graphs
+/// can still be validated by building the `GraphFrame` out of `u64` columns
+/// and a `Shape<u64>`.
 pub struct DuckDB;
 
 /// The `DuckDB` block defines a Rust module that contains `import` and `export`.
 impl Backend for DuckDB {
     /// This function retrieves data from a DuckDB database and returns it as a
-    /// DataFrame.
+    /// DataFrame. Databases compressed with zstd, whose path ends in `.zst`, are
+    /// decompressed into a temporary file beforehand. Literals are not stored
+    /// by their lexical form, but collapsed into the id of their datatype,
+    /// which is used as their object. Besides the
+    /// `subject`, `predicate` and `object` columns, a `dtype` column holding the
+    /// id of the datatype of the object is produced, being `Entity` for the
+    /// edges among entities. Note that, as a consequence, all the literals of
+    /// the same datatype share the same object, so a `TripleConstraint` on a
+    /// concrete literal value cannot tell them apart; see
+    /// `PSchema::collapsed_literal_constraints` for detecting such constraints.
     ///
     /// Arguments:
     ///
@@ -32,27 +47,381 @@ impl Backend for DuckDB {
     /// the `String` is an error message in case any error occurs during the
     /// execution of the function.
     fn import(path: &str) -> Result<DataFrame, String> {
+        Self::import_with_mapping(path, &ColumnMapping::default())
+    }
+
+    /// This function stores the edges of a `DataFrame` in the `edge` table of a
+    /// DuckDB database, whose columns are `src_id`, `property_id` and `dst_id`.
+    /// The database is created in case it does not exist. If it already has an
+    /// `edge` table, the edges are appended to it, so several subsets can be
+    /// gathered in the same database; an error is returned if that table has
+    /// any other layout. The edges are inserted through an appender within a
+    /// single transaction, so either all of them are stored or none is.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The path to the DuckDB database file.
+    /// * `df`: The `DataFrame` whose `subject`, `predicate` and `object` columns,
+    /// which must hold `UInt32` ids, are to be stored.
+    ///
+    /// Returns:
+    ///
+    /// This function returns a `Result<(), String>`, where the `String` is an
+    /// error message in case any error occurs while storing the edges.
+    fn export(path: &str, df: &mut DataFrame) -> Result<(), String> {
+        let column = |column: Column| match df.column(column.as_ref()) {
+            Ok(series) => match series.as_materialized_series().u32() {
+                Ok(ids) => Ok(ids.clone()),
+                Err(_) => Err(format!(
+                    "The {} column must hold UInt32 ids",
+                    column.as_ref()
+                )),
+            },
+            Err(_) => Err(format!("The {} column is missing", column.as_ref())),
+        };
+        let subjects = column(Column::Subject)?;
+        let predicates = column(Column::Predicate)?;
+        let objects = column(Column::Object)?;
+
+        let mut connection = match Connection::open(Path::new(path)) {
+            Ok(connection) => connection,
+            Err(_) => return Err(String::from("Cannot connect to the database")),
+        };
+        let transaction = match connection.transaction() {
+            Ok(transaction) => transaction,
+            Err(error) => return Err(format!("Cannot start the transaction {}", error)),
+        };
+        if let Err(error) = transaction.execute_batch(
+            "CREATE TABLE IF NOT EXISTS edge (src_id UINTEGER, property_id UINTEGER, dst_id UINTEGER)",
+        ) {
+            return Err(format!("Cannot create the edge table {}", error));
+        }
+        // An existing table must have the very same layout, so no edges are appended to the wrong
+        // columns of an unrelated table.
+        let layout = transaction
+            .prepare(
+                "SELECT column_name, data_type FROM information_schema.columns \
+                 WHERE table_name = 'edge' ORDER BY ordinal_position",
+            )
+            .and_then(|mut statement| {
+                statement
+                    .query_map([], |row| {
+                        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+                    })?
+                    .collect::<Result<Vec<_>, _>>()
+            });
+        match layout {
+            Ok(layout)
+                if layout
+                    .iter()
+                    .map(|(name, dtype)| (name.as_str(), dtype.as_str()))
+                    .eq([
+                        ("src_id", "UINTEGER"),
+                        ("property_id", "UINTEGER"),
+                        ("dst_id", "UINTEGER"),
+                    ]) => {}
+            Ok(layout) => return Err(format!("The edge table has another layout: {:?}", layout)),
+            Err(error) => {
+                return Err(format!(
+                    "Cannot read the layout of the edge table {}",
+                    error
+                ))
+            }
+        }
+        {
+            let mut appender = match transaction.appender("edge") {
+                Ok(appender) => appender,
+                Err(error) => return Err(format!("Cannot append to the edge table {}", error)),
+            };
+            for (i, ((subject, predicate), object)) in subjects
+                .into_iter()
+                .zip(predicates.into_iter())
+                .zip(objects.into_iter())
+                .enumerate()
+            {
+                let (subject, predicate, object) = match (subject, predicate, object) {
+                    (Some(subject), Some(predicate), Some(object)) => (subject, predicate, object),
+                    _ => return Err(format!("The {}th row has NULL values", i)),
+                };
+                if appender
+                    .append_row(params![subject, predicate, object])
+                    .is_err()
+                {
+                    return Err(format!("Error storing the {}th row", i));
+                }
+            }
+            if appender.flush().is_err() {
+                return Err(String::from("Error storing the edges"));
+            }
+        }
+        match transaction.commit() {
+            Ok(_) => Ok(()),
+            Err(error) => Err(format!("Cannot commit the transaction {}", error)),
+        }
+    }
+}
+
+/// The `ColumnMapping` struct describes which columns of the DuckDB tables are
+/// projected into the `subject`, `predicate` and `object` columns. In case a
+/// position is made of several columns, they are combined into a single
+/// `UINTEGER` key through a dictionary assigning dense codes to the distinct
+/// combinations of values, ordered by the values, so distinct combinations
+/// never share a key. The subjects and the objects share the same dictionary,
+/// so they must be made of the same number of columns whenever any of them is
+/// composite.
+///
+/// Properties:
+///
+/// * `subject`: the columns identifying the source of the edge.
+/// * `predicate`: the columns identifying the property of the edge.
+/// * `object`: the columns identifying the destination of the edge. Only used
+/// for the entity table, as literals are collapsed into their datatype.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnMapping {
+    subject: Vec<String>,
+    predicate: Vec<String>,
+    object: Vec<String>,
+}
+
+impl ColumnMapping {
+    pub fn new(subject: &[&str], predicate: &[&str], object: &[&str]) -> Self {
+        let to_vec = |columns: &[&str]| columns.iter().map(|c| c.to_string()).collect();
+        Self {
+            subject: to_vec(subject),
+            predicate: to_vec(predicate),
+            object: to_vec(object),
+        }
+    }
+
+    /// This function builds the SQL expression that projects the provided
+    /// columns into a single `UINTEGER` key, together with the join it needs,
+    /// if any. A single column is cast, so the import fails instead of silently
+    /// truncating ids beyond `u32::MAX`, while composite keys are looked up in
+    /// the provided dictionary, joined under the provided alias.
+    ///
+    /// Arguments:
+    ///
+    /// * `columns`: The columns to be projected.
+    /// * `dictionary`: The name of the dictionary holding the composite keys.
+    /// * `alias`: The alias the dictionary is joined under.
+    ///
+    /// Returns:
+    ///
+    /// A tuple with the expression selecting the key and the join clause,
+    /// which is empty for single columns.
+    fn key(columns: &[String], dictionary: &str, alias: &str) -> (String, String) {
+        match columns {
+            [column] => (format!("CAST({} AS UINTEGER)", column), String::new()),
+            columns => (
+                format!("{}.pschema_code", alias),
+                format!(
+                    " JOIN {} AS {} ON {}.pschema_key = row({})",
+                    dictionary,
+                    alias,
+                    alias,
+                    columns.join(", ")
+                ),
+            ),
+        }
+    }
+
+    /// This function builds the common table expression of a dictionary,
+    /// which assigns dense `UINTEGER` codes, starting from zero, to the
+    /// distinct combinations of values of the provided columns of the tables.
+    ///
+    /// Arguments:
+    ///
+    /// * `name`: The name of the dictionary.
+    /// * `sources`: The tables and the columns the combinations are read from.
+    fn dictionary(name: &str, sources: &[(&str, &[String])]) -> String {
+        let keys = sources
+            .iter()
+            .map(|(table, columns)| {
+                format!(
+                    "SELECT row({}) AS pschema_key FROM {}",
+                    columns.join(", "),
+                    table
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" UNION ");
+        format!(
+            "{} AS (SELECT pschema_key, CAST(DENSE_RANK() OVER (ORDER BY pschema_key) - 1 AS UINTEGER) AS pschema_code FROM ({}))",
+            name, keys
+        )
+    }
+}
+
+/// The default mapping corresponds to the layout produced by `wd2duckdb`,
+/// where edges are keyed by `src_id`, `property_id` and `dst_id`.
+impl Default for ColumnMapping {
+    fn default() -> Self {
+        Self::new(&["src_id"], &["property_id"], &["dst_id"])
+    }
+}
+
+impl DuckDB {
+    /// This function retrieves data from a DuckDB database using the provided
+    /// `ColumnMapping` to project the tables into the standard three columns.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The path to the DuckDB database file.
+    /// * `mapping`: The columns to be projected into each position of the edge.
+    ///
+    /// Returns:
+    ///
+    /// This function returns a `Result<DataFrame, String>` as `import` does.
+    pub fn import_with_mapping(path: &str, mapping: &ColumnMapping) -> Result<DataFrame, String> {
+        Self::query(path, mapping, &DataType::iter().collect::<Vec<_>>(), false)
+    }
+
+    /// This function retrieves data from a DuckDB database as `import` does,
+    /// adding a `unit` column holding the id of the unit of the quantities, so
+    /// they can be validated through `NodeConstraint::QuantityUnit`. The unit is
+    /// read from the `unit_id` column of the `quantity` table, while the rest
+    /// of the edges are given a unit of 0.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The path to the DuckDB database file.
+    ///
+    /// Returns:
+    ///
+    /// This function returns a `Result<DataFrame, String>` as `import` does.
+    pub fn import_with_units(path: &str) -> Result<DataFrame, String> {
+        Self::query(
+            path,
+            &ColumnMapping::default(),
+            &DataType::iter().collect::<Vec<_>>(),
+            true,
+        )
+    }
+
+    /// This function retrieves data from a DuckDB database reading only the
+    /// tables of the requested datatypes. Hence, users interested only in the
+    /// relationships among entities do not pay the cost of reading the literals.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The path to the DuckDB database file.
+    /// * `dtypes`: The datatypes whose tables are to be read.
+    ///
+    /// Returns:
+    ///
+    /// This function returns a `Result<DataFrame, String>` as `import` does.
+    pub fn import_types(path: &str, dtypes: &[DataType]) -> Result<DataFrame, String> {
+        Self::query(path, &ColumnMapping::default(), dtypes, false)
+    }
+
+    fn query(
+        path: &str,
+        mapping: &ColumnMapping,
+        dtypes: &[DataType],
+        units: bool,
+    ) -> Result<DataFrame, String> {
+        if dtypes.is_empty() {
+            return Err(String::from("At least one datatype must be provided"));
+        }
+
+        let composite = |columns: &[String]| columns.len() > 1;
+        if (composite(&mapping.subject) || composite(&mapping.object))
+            && mapping.subject.len() != mapping.object.len()
+        {
+            return Err(String::from(
+                "The subject and the object must be made of the same number of columns",
+            ));
+        }
+
+        let (subject, subject_join) = ColumnMapping::key(&mapping.subject, "vertices", "subjects");
+        let (predicate, predicate_join) =
+            ColumnMapping::key(&mapping.predicate, "predicates", "predicates");
+        let (object, object_join) = ColumnMapping::key(&mapping.object, "vertices", "objects");
+
+        // The composite keys are looked up in dictionaries built out of every table to be read, so
+        // the same combination of values is given the same code wherever it appears.
+        let mut dictionaries = Vec::new();
+        if composite(&mapping.subject) {
+            let mut sources = dtypes
+                .iter()
+                .map(|dtype| (dtype.as_ref(), mapping.subject.as_slice()))
+                .collect::<Vec<_>>();
+            if let Some(entity) = dtypes
+                .iter()
+                .find(|dtype| matches!(dtype, DataType::Entity))
+            {
+                sources.push((entity.as_ref(), mapping.object.as_slice()));
+            }
+            dictionaries.push(ColumnMapping::dictionary("vertices", &sources));
+        }
+        if composite(&mapping.predicate) {
+            let sources = dtypes
+                .iter()
+                .map(|dtype| (dtype.as_ref(), mapping.predicate.as_slice()))
+                .collect::<Vec<_>>();
+            dictionaries.push(ColumnMapping::dictionary("predicates", &sources));
+        }
+
+        let dtype_id = |dtype: &DataType| u32::from(Id::DataType(dtype.clone()));
+
+        let unit = |dtype: &DataType| match (units, dtype) {
+            (true, DataType::Quantity) => String::from(", CAST(unit_id AS UINTEGER)"),
+            (true, _) => String::from(", CAST(0 AS UINTEGER)"),
+            (false, _) => String::new(),
+        };
+
         let format = |id: DataType| {
             format!(
-                "SELECT src_id, property_id, CAST({:} AS UINTEGER) FROM {:}",
-                u32::from(Id::DataType(id.clone())),
-                id.as_ref()
+                "SELECT {}, {}, CAST({:} AS UINTEGER), CAST({:} AS UINTEGER){} FROM {:}{}{}",
+                subject,
+                predicate,
+                dtype_id(&id),
+                dtype_id(&id),
+                unit(&id),
+                id.as_ref(),
+                subject_join,
+                predicate_join
             )
         };
 
-        let stmt = DataType::iter()
+        let stmt = dtypes
+            .iter()
+            .cloned()
             .map(|dtype| match dtype {
                 DataType::Quantity => format(DataType::Quantity),
                 DataType::Coordinate => format(DataType::Coordinate),
                 DataType::String => format(DataType::String),
                 DataType::DateTime => format(DataType::DateTime),
                 DataType::Entity => format!(
-                    "SELECT src_id, property_id, dst_id FROM {:}",
-                    dtype.as_ref()
+                    "SELECT {}, {}, {}, CAST({:} AS UINTEGER){} FROM {:}{}{}{}",
+                    subject,
+                    predicate,
+                    object,
+                    dtype_id(&dtype),
+                    unit(&dtype),
+                    dtype.as_ref(),
+                    subject_join,
+                    predicate_join,
+                    object_join
                 ),
             })
             .collect::<Vec<String>>()
             .join(" UNION ");
+        let stmt = match dictionaries.is_empty() {
+            true => stmt,
+            false => format!("WITH {} {}", dictionaries.join(", "), stmt),
+        };
+
+        // DuckDB needs a path to the database, so compressed ones are decompressed into a temporary
+        // file, which is removed once the edges have been read.
+        let decompressed = match path.ends_with(".zst") {
+            true => Some(Decompressed::new(path)?),
+            false => None,
+        };
+        let path = match &decompressed {
+            Some(decompressed) => decompressed.path(),
+            None => path,
+        };
 
         let connection: Connection = match Path::new(path).try_exists() {
             Ok(true) => match Connection::open(Path::new(path)) {
@@ -69,52 +438,427 @@ impl Backend for DuckDB {
 
         let batches: Vec<RecordBatch> = match statement.query_arrow([]) {
             Ok(arrow) => arrow.collect(),
-            Err(_) => return Err(String::from("Error executing the Arrow query")),
+            Err(error) => return Err(format!("Error executing the Arrow query: {}", error)),
         };
 
+        let mut columns = vec![
+            Column::Subject,
+            Column::Predicate,
+            Column::Object,
+            Column::Custom("dtype"),
+        ];
+        if units {
+            columns.push(Column::Custom("unit"));
+        }
+
         Ok(batches
             .into_par_iter()
             .map(|batch| {
-                match DataFrame::new(vec![
-                    Series::new(
-                        Column::Subject.as_ptr(),
-                        batch
-                            .column(0)
-                            .as_any()
-                            .downcast_ref::<UInt32Array>()
-                            .unwrap()
-                            .values(),
-                    )
-                    .into(),
-                    Series::new(
-                        Column::Predicate.as_ptr(),
-                        batch
-                            .column(1)
-                            .as_any()
-                            .downcast_ref::<UInt32Array>()
-                            .unwrap()
-                            .values(),
-                    )
-                    .into(),
-                    Series::new(
-                        Column::Object.as_ptr(),
-                        batch
-                            .column(2)
-                            .as_any()
-                            .downcast_ref::<UInt32Array>()
-                            .unwrap()
-                            .values(),
-                    )
-                    .into(),
-                ]) {
+                match DataFrame::new(
+                    columns
+                        .iter()
+                        .enumerate()
+                        .map(|(i, column)| {
+                            Series::new(
+                                column.as_ptr(),
+                                batch
+                                    .column(i)
+                                    .as_any()
+                                    .downcast_ref::<UInt32Array>()
+                                    .unwrap()
+                                    .values(),
+                            )
+                            .into()
+                        })
+                        .collect(),
+                ) {
                     Ok(tmp_dataframe) => tmp_dataframe,
                     Err(_) => DataFrame::empty(),
                 }
             })
             .reduce(DataFrame::empty, |acc, e| acc.vstack(&e).unwrap()))
     }
+}
+
+/// The `Decompressed` struct holds the temporary copy of a zstd-compressed
+/// database, which is removed when the struct is dropped.
+struct Decompressed {
+    path: std::path::PathBuf,
+}
+
+/// The number of databases decompressed so far by this process, which tells
+/// apart the temporary copies of databases sharing the same file name.
+static DECOMPRESSED: AtomicUsize = AtomicUsize::new(0);
+
+impl Decompressed {
+    fn new(path: &str) -> Result<Self, String> {
+        let name = match Path::new(path).file_stem() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => return Err(String::from("Make sure you provide an existing path")),
+        };
+        let decompressed = Self {
+            path: std::env::temp_dir().join(format!(
+                "{}-{}-{}",
+                std::process::id(),
+                DECOMPRESSED.fetch_add(1, Ordering::Relaxed),
+                name
+            )),
+        };
+        let source = match std::fs::File::open(path) {
+            Ok(source) => source,
+            Err(_) => return Err(String::from("Make sure you provide an existing path")),
+        };
+        let destination = match std::fs::File::create(&decompressed.path) {
+            Ok(destination) => destination,
+            Err(_) => return Err(String::from("Cannot create the decompressed database")),
+        };
+        match zstd::stream::copy_decode(source, destination) {
+            Ok(_) => Ok(decompressed),
+            Err(error) => Err(format!("Cannot decompress the database: {}", error)),
+        }
+    }
+
+    fn path(&self) -> &str {
+        self.path.to_str().unwrap_or_default()
+    }
+}
+
+impl Drop for Decompressed {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use duckdb::Connection;
+    use pregel_rs::graph_frame::GraphFrame;
+    use pregel_rs::pregel::Column;
+    use strum::IntoEnumIterator;
+    use wikidata_rs::dtype::DataType;
+
+    use super::{ColumnMapping, Decompressed, DuckDB};
+    use crate::backends::Backend;
+    use crate::pschema::PSchema;
+    use crate::shape::shex::{NodeConstraint, Shape, TripleConstraint};
+    use crate::test_support::assert_conforms;
+    use crate::utils::examples::{paper_graph, paper_schema};
+    use wikidata_rs::id::Id;
+
+    fn create_database(name: &str, rows: &[(DataType, &str)]) -> Result<PathBuf, String> {
+        let path = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_file(&path);
+
+        let connection = match Connection::open(&path) {
+            Ok(connection) => connection,
+            Err(_) => return Err(String::from("Cannot connect to the database")),
+        };
+        for dtype in DataType::iter() {
+            let stmt = format!(
+                "CREATE TABLE {} (src_id UINTEGER, src_type UINTEGER, property_id UINTEGER, dst_id UINTEGER, dst_type UINTEGER)",
+                dtype.as_ref()
+            );
+            if connection.execute_batch(&stmt).is_err() {
+                return Err(String::from("Error creating the tables"));
+            }
+        }
+        for (dtype, values) in rows {
+            let stmt = format!("INSERT INTO {} VALUES {}", dtype.as_ref(), values);
+            if connection.execute_batch(&stmt).is_err() {
+                return Err(String::from("Error inserting the edges"));
+            }
+        }
+        Ok(path)
+    }
+
+    #[test]
+    fn composite_key_test() -> Result<(), String> {
+        let path = create_database(
+            "pschema-composite-key.duckdb",
+            &[(
+                DataType::Entity,
+                "(1, 0, 31, 5, 0), (1, 1, 31, 5, 0), (5, 0, 17, 1, 0)",
+            )],
+        )?;
+
+        let mapping = ColumnMapping::new(
+            &["src_id", "src_type"],
+            &["property_id"],
+            &["dst_id", "dst_type"],
+        );
+        let edges = DuckDB::import_with_mapping(path.to_str().unwrap(), &mapping)?;
+
+        let codes = |column: Column| match edges.column(column.as_ref()) {
+            Ok(column) => column
+                .u32()
+                .unwrap()
+                .into_no_null_iter()
+                .collect::<Vec<_>>(),
+            Err(_) => Vec::new(),
+        };
+        let (subjects, objects) = (codes(Column::Subject), codes(Column::Object));
+        let mut vertices = subjects.iter().chain(objects.iter()).collect::<Vec<_>>();
+        vertices.sort();
+        vertices.dedup();
+        // The codes are dense and the same combination is given the same code as subject and object.
+        match (edges.height(), vertices.as_slice()) {
+            (3, [0, 1, 2]) if objects.iter().all(|object| subjects.contains(object)) => Ok(()),
+            _ => Err(format!("Unexpected edges: {:?}", edges)),
+        }
+    }
+
+    #[test]
+    fn id_overflow_test() -> Result<(), String> {
+        let path = create_database("pschema-id-overflow.duckdb", &[])?;
+        let connection = match Connection::open(&path) {
+            Ok(connection) => connection,
+            Err(_) => return Err(String::from("Cannot connect to the database")),
+        };
+        let stmt = format!(
+            "ALTER TABLE {0} ALTER src_id TYPE UBIGINT; INSERT INTO {0} VALUES (5000000000, 0, 31, 5, 0)",
+            DataType::Entity.as_ref()
+        );
+        if connection.execute_batch(&stmt).is_err() {
+            return Err(String::from("Error inserting the edges"));
+        }
+        drop(connection);
+
+        match DuckDB::import(path.to_str().unwrap()) {
+            Ok(edges) => Err(format!("The ids were truncated: {:?}", edges)),
+            Err(_) => Ok(()),
+        }
+    }
+
+    #[test]
+    fn compressed_test() -> Result<(), String> {
+        let path = create_database(
+            "pschema-compressed.duckdb",
+            &[(DataType::Entity, "(1, 0, 31, 5, 0), (2, 0, 31, 5, 0)")],
+        )?;
+        let compressed = std::env::temp_dir().join("pschema-compressed.duckdb.zst");
+        let written = std::fs::File::open(&path).and_then(|source| {
+            std::fs::File::create(&compressed)
+                .and_then(|destination| zstd::stream::copy_encode(source, destination, 0))
+        });
+        if written.is_err() {
+            return Err(String::from("Error compressing the database"));
+        }
+
+        match DuckDB::import(compressed.to_str().unwrap()) {
+            Ok(edges) if edges.height() == 2 => Ok(()),
+            Ok(edges) => Err(format!("Unexpected edges: {:?}", edges)),
+            Err(error) => Err(error),
+        }
+    }
+
+    #[test]
+    fn decompressed_name_test() -> Result<(), String> {
+        let path = create_database(
+            "pschema-decompressed-name.duckdb",
+            &[(DataType::Entity, "(1, 0, 31, 5, 0)")],
+        )?;
+        let compressed = std::env::temp_dir().join("pschema-decompressed-name.duckdb.zst");
+        let written = std::fs::File::open(&path).and_then(|source| {
+            std::fs::File::create(&compressed)
+                .and_then(|destination| zstd::stream::copy_encode(source, destination, 0))
+        });
+        if written.is_err() {
+            return Err(String::from("Error compressing the database"));
+        }
+
+        let first = Decompressed::new(compressed.to_str().unwrap())?;
+        let second = Decompressed::new(compressed.to_str().unwrap())?;
+        match first.path() != second.path() {
+            true => Ok(()),
+            false => Err(format!("Both copies share the path {}", first.path())),
+        }
+    }
+
+    #[test]
+    fn import_types_test() -> Result<(), String> {
+        let path = create_database(
+            "pschema-import-types.duckdb",
+            &[
+                (DataType::Entity, "(1, 0, 31, 5, 0), (2, 0, 31, 5, 0)"),
+                (DataType::Quantity, "(1, 0, 1082, 0, 0)"),
+            ],
+        )?;
+        let edges = DuckDB::import_types(path.to_str().unwrap(), &[DataType::Entity])?;
+        match edges.height() {
+            2 => Ok(()),
+            _ => Err(format!("Unexpected edges: {:?}", edges)),
+        }
+    }
+
+    #[test]
+    fn literal_dtype_test() -> Result<(), String> {
+        let path = create_database(
+            "pschema-literal-dtype.duckdb",
+            &[
+                (DataType::Entity, "(1, 0, 31, 5, 0)"),
+                (DataType::DateTime, "(1, 0, 569, 0, 0), (2, 0, 569, 0, 0)"),
+            ],
+        )?;
+        let edges = DuckDB::import(path.to_str().unwrap())?;
+        let date_time = u32::from(Id::DataType(DataType::DateTime));
+        let dtypes = match edges.column(Column::Custom("dtype").as_ref()) {
+            Ok(dtypes) => dtypes.n_unique().unwrap_or_default(),
+            Err(_) => return Err(String::from("The dtype column is missing")),
+        };
+
+        let graph = match GraphFrame::from_edges(edges) {
+            Ok(graph) => graph,
+            Err(_) => return Err(String::from("Error creating the GraphFrame from edges")),
+        };
+        let schema: Shape<u32> =
+            TripleConstraint::new("BirthDate", 569, NodeConstraint::Value(date_time)).into();
+        match PSchema::new(schema).validate(graph) {
+            Ok(subset) if subset.height() == 3 && dtypes == 2 => Ok(()),
+            Ok(subset) => Err(format!("Unexpected subset: {:?}", subset)),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    #[test]
+    fn datatype_test() -> Result<(), String> {
+        let path = create_database(
+            "pschema-datatype.duckdb",
+            &[
+                (DataType::Entity, "(1, 0, 31, 5, 0)"),
+                (DataType::DateTime, "(1, 0, 569, 0, 0)"),
+                (DataType::String, "(2, 0, 569, 0, 0)"),
+            ],
+        )?;
+        let graph = || -> Result<GraphFrame, String> {
+            let edges = DuckDB::import(path.to_str().unwrap())?;
+            GraphFrame::from_edges(edges).map_err(|error| error.to_string())
+        };
+        // Only the first subject has a `DateTime` as the value of the property.
+        let date_time = u32::from(Id::DataType(DataType::DateTime));
+        let schema = TripleConstraint::new("BirthDate", 569, NodeConstraint::Datatype(date_time));
+        assert_conforms(graph()?, schema.into(), &[("1", &["BirthDate"])])?;
+        // The objects of the edges among entities are their ids, so only the `dtype` column tells
+        // them apart from the literals.
+        let entity = u32::from(Id::DataType(DataType::Entity));
+        let schema = TripleConstraint::new("Entity", 31, NodeConstraint::Datatype(entity));
+        assert_conforms(graph()?, schema.into(), &[("1", &["Entity"])])
+    }
+
+    #[test]
+    fn quantity_unit_test() -> Result<(), String> {
+        let path = create_database(
+            "pschema-quantity-unit.duckdb",
+            &[(DataType::Entity, "(3, 0, 31, 5, 0)")],
+        )?;
+        // Both quantities are masses, but only the first one is measured in daltons.
+        let (dalton, kilogram) = (483261u32, 11570u32);
+        match Connection::open(&path).and_then(|connection| {
+            connection.execute_batch(&format!(
+                "ALTER TABLE quantity ADD COLUMN unit_id UINTEGER; \
+                 INSERT INTO quantity VALUES (1, 0, 2067, 0, 0, {}), (2, 0, 2067, 0, 0, {})",
+                dalton, kilogram
+            ))
+        }) {
+            Ok(_) => (),
+            Err(_) => return Err(String::from("Error inserting the quantities")),
+        }
+        let graph = match GraphFrame::from_edges(DuckDB::import_with_units(path.to_str().unwrap())?)
+        {
+            Ok(graph) => graph,
+            Err(_) => return Err(String::from("Error creating the GraphFrame from edges")),
+        };
+        let schema: Shape<u32> =
+            TripleConstraint::new("MassInDaltons", 2067, NodeConstraint::QuantityUnit(dalton))
+                .into();
+        match PSchema::new(schema).validate(graph) {
+            Ok(subset) if subset.height() == 1 => match subset.column(Column::Subject.as_ref()) {
+                Ok(subjects)
+                    if subjects
+                        .as_materialized_series()
+                        .u32()
+                        .ok()
+                        .and_then(|subjects| subjects.get(0))
+                        == Some(1) =>
+                {
+                    Ok(())
+                }
+                _ => Err(format!("Unexpected subset: {:?}", subset)),
+            },
+            Ok(subset) => Err(format!("Unexpected subset: {:?}", subset)),
+            Err(error) => Err(error.to_string()),
+        }
+    }
 
-    fn export(_path: &str, _df: &mut DataFrame) -> Result<(), String> {
-        todo!()
+    #[test]
+    fn export_test() -> Result<(), String> {
+        let path = std::env::temp_dir().join("pschema-export.duckdb");
+        let _ = std::fs::remove_file(&path);
+        let mut subset = match PSchema::new(paper_schema()).validate(paper_graph()?) {
+            Ok(subset) => subset,
+            Err(error) => return Err(error.to_string()),
+        };
+        // Exporting twice appends the edges to the existing table.
+        DuckDB::export(path.to_str().unwrap(), &mut subset)?;
+        DuckDB::export(path.to_str().unwrap(), &mut subset)?;
+
+        let connection = match Connection::open(&path) {
+            Ok(connection) => connection,
+            Err(_) => return Err(String::from("Cannot connect to the database")),
+        };
+        match connection.query_row("SELECT COUNT(*) FROM edge", [], |row| {
+            row.get::<_, usize>(0)
+        }) {
+            Ok(count) if count == 2 * subset.height() => Ok(()),
+            Ok(count) => Err(format!("Unexpected number of edges: {}", count)),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    #[test]
+    fn export_layout_test() -> Result<(), String> {
+        let path = std::env::temp_dir().join("pschema-export-layout.duckdb");
+        let _ = std::fs::remove_file(&path);
+        match Connection::open(&path).and_then(|connection| {
+            connection.execute_batch("CREATE TABLE edge (src_id UINTEGER, dst_id UINTEGER)")
+        }) {
+            Ok(_) => (),
+            Err(error) => return Err(error.to_string()),
+        }
+        let mut subset = match PSchema::new(paper_schema()).validate(paper_graph()?) {
+            Ok(subset) => subset,
+            Err(error) => return Err(error.to_string()),
+        };
+        match DuckDB::export(path.to_str().unwrap(), &mut subset) {
+            Ok(_) => Err(String::from("The edges should not have been appended")),
+            Err(_) => Ok(()),
+        }
+    }
+
+    #[test]
+    fn collapsed_literal_test() -> Result<(), String> {
+        let path = create_database(
+            "pschema-collapsed-literal.duckdb",
+            &[(
+                DataType::Quantity,
+                "(1, 0, 1082, 42, 0), (2, 0, 1082, 7, 0)",
+            )],
+        )?;
+        let graph = match GraphFrame::from_edges(DuckDB::import(path.to_str().unwrap())?) {
+            Ok(graph) => graph,
+            Err(_) => return Err(String::from("Error creating the GraphFrame from edges")),
+        };
+        let schema: Shape<u32> = TripleConstraint::new(
+            "Population",
+            1082,
+            NodeConstraint::Value(u32::from(Id::DataType(DataType::Quantity))),
+        )
+        .into();
+        match PSchema::new(schema).collapsed_literal_constraints(&graph) {
+            Ok(labels) if labels == vec!["Population"] => Ok(()),
+            Ok(labels) => Err(format!("Unexpected warnings: {:?}", labels)),
+            Err(error) => Err(error.to_string()),
+        }
     }
 }
@@ -0,0 +1,114 @@
+use polars::prelude::*;
+use pregel_rs::graph_frame::GraphFrame;
+use pregel_rs::pregel::Column;
+
+use crate::utils::check::check_string_cache;
+
+pub struct Memory;
+
+/// The `Memory` block builds the edges straight from triples held in memory,
+/// so graphs can be constructed programmatically, e.g. for tests or when pschema
+/// is embedded in a larger service, without touching the filesystem. The terms
+/// are stored as provided, so IRIs are expected to be written as the N-Triples
+/// backend stores them, that is, enclosed in angle brackets.
+impl Memory {
+    /// This function builds the edges out of the provided triples, casting the
+    /// `subject`, `predicate` and `object` columns to `Categorical`, as
+    /// `NTriples::import` does.
+    ///
+    /// Arguments:
+    ///
+    /// * `triples`: The subject, predicate and object of each of the triples.
+    ///
+    /// Returns:
+    ///
+    /// This function returns a `Result<DataFrame, String>`, where the `String`
+    /// is an error message in case the edges cannot be built.
+    pub fn from_triples<I, S>(triples: I) -> Result<DataFrame, String>
+    where
+        I: IntoIterator<Item = (S, S, S)>,
+        S: AsRef<str>,
+    {
+        check_string_cache().map_err(|error| error.to_string())?;
+
+        let mut columns = [Vec::new(), Vec::new(), Vec::new()];
+        for (subject, predicate, object) in triples {
+            columns[0].push(subject.as_ref().to_string());
+            columns[1].push(predicate.as_ref().to_string());
+            columns[2].push(object.as_ref().to_string());
+        }
+        let [subjects, predicates, objects] = columns;
+
+        let dtype = DataType::Categorical(None, CategoricalOrdering::Lexical);
+        let column = |column: Column, values: Vec<String>| {
+            Series::new(column.as_ptr(), values)
+                .cast(&dtype)
+                .map(polars::prelude::Column::from)
+        };
+        match (
+            column(Column::Subject, subjects),
+            column(Column::Predicate, predicates),
+            column(Column::Object, objects),
+        ) {
+            (Ok(subjects), Ok(predicates), Ok(objects)) => {
+                match DataFrame::new(vec![subjects, predicates, objects]) {
+                    Ok(edges) => Ok(edges),
+                    Err(_) => Err(String::from("Error creating the edges DataFrame")),
+                }
+            }
+            _ => Err(String::from("Error casting the edges")),
+        }
+    }
+
+    /// This function builds a `GraphFrame` out of the provided triples, as
+    /// described in `from_triples`.
+    pub fn graph<I, S>(triples: I) -> Result<GraphFrame, String>
+    where
+        I: IntoIterator<Item = (S, S, S)>,
+        S: AsRef<str>,
+    {
+        match GraphFrame::from_edges(Self::from_triples(triples)?) {
+            Ok(graph) => Ok(graph),
+            Err(_) => Err(String::from("Error creating the GraphFrame from edges")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Memory;
+    use crate::shape::shex::{NodeConstraint, Shape, TripleConstraint};
+    use crate::test_support::assert_conforms;
+
+    #[test]
+    fn from_triples_test() -> Result<(), String> {
+        let graph = Memory::graph([
+            (
+                "<http://example.org/alan>",
+                "<http://example.org/type>",
+                "<http://example.org/Person>",
+            ),
+            (
+                "<http://example.org/bob>",
+                "<http://example.org/type>",
+                "<http://example.org/Robot>",
+            ),
+        ])?;
+        let schema: Shape<&str> = TripleConstraint::new(
+            "Person",
+            "<http://example.org/type>",
+            NodeConstraint::Value("<http://example.org/Person>"),
+        )
+        .into();
+        assert_conforms(graph, schema, &[("<http://example.org/alan>", &["Person"])])
+    }
+
+    #[test]
+    fn owned_triples_test() -> Result<(), String> {
+        let triples = vec![(String::from("a"), String::from("b"), String::from("c"))];
+        match Memory::from_triples(triples)?.shape() {
+            (1, 3) => Ok(()),
+            shape => Err(format!("Unexpected shape: {:?}", shape)),
+        }
+    }
+}
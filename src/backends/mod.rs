@@ -1,15 +1,317 @@
-use polars::prelude::DataFrame;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
+use polars::prelude::*;
+use pregel_rs::graph_frame::GraphFrame;
+use pregel_rs::pregel::Column;
+
+/// `pub mod csv;` is creating a public module named `csv`. This module contains
+/// code related to reading and writing the edges as CSV files.
+pub mod csv;
 /// `pub mod duckdb_dump;` is creating a public module named `duckdb`. This
 /// module contains code related to dumping data from a DuckDB database.
 pub mod duckdb;
+/// `pub mod memory;` is creating a public module named `memory`. This module
+/// contains code related to building the edges from triples held in memory.
+pub mod memory;
 /// `pub mod duckdb_dump;` is creating a public module named `parquet`. This
 /// module contains code related to dumping data from a Parquet file.
 pub mod parquet;
 
 pub mod ntriples;
+/// `pub mod sparql;` is creating a public module named `sparql`. This module
+/// contains code related to loading the edges from a remote SPARQL endpoint.
+pub mod sparql;
+
+/// The `Format` enum lists the formats a validated subset can be serialized to
+/// in memory.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    NTriples,
+    Parquet,
+}
 
 pub trait Backend {
     fn import(path: &str) -> Result<DataFrame, String>;
     fn export(path: &str, df: &mut DataFrame) -> Result<(), String>;
+
+    /// This function imports the edges from any `Read` implementor, such as the
+    /// standard input or the body of an HTTP response, so no file is needed. By
+    /// default, the contents are spooled into a temporary file, which is then
+    /// imported with `import`; backends able to parse a stream override it.
+    ///
+    /// Arguments:
+    ///
+    /// * `reader`: The source of the serialized edges.
+    ///
+    /// Returns:
+    ///
+    /// This function returns a `Result<DataFrame, String>` as `import` does.
+    fn import_reader<R: Read>(mut reader: R) -> Result<DataFrame, String> {
+        let spooled = Spooled::new();
+        let mut file = match File::create(&spooled.path) {
+            Ok(file) => file,
+            Err(_) => return Err(String::from("Error creating the temporary file")),
+        };
+        if std::io::copy(&mut reader, &mut file).is_err() {
+            return Err(String::from("Error reading the edges"));
+        }
+        drop(file);
+        Self::import(spooled.path())
+    }
+
+    /// This function exports the edges into any `Write` implementor, such as
+    /// the standard output or an in-memory buffer. By default, the edges are
+    /// exported with `export` into a temporary file, which is then copied into
+    /// the writer; backends able to serialize into a stream override it.
+    ///
+    /// Arguments:
+    ///
+    /// * `writer`: The destination of the serialized edges.
+    /// * `df`: The `DataFrame` containing the edges to be serialized.
+    ///
+    /// Returns:
+    ///
+    /// This function returns a `Result<(), String>` as `export` does.
+    fn export_writer<W: Write>(mut writer: W, df: &mut DataFrame) -> Result<(), String> {
+        let spooled = Spooled::new();
+        Self::export(spooled.path(), df)?;
+        let mut file = match File::open(&spooled.path) {
+            Ok(file) => file,
+            Err(_) => return Err(String::from("Error opening the temporary file")),
+        };
+        match std::io::copy(&mut file, &mut writer).and_then(|_| writer.flush()) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(String::from("Error writing the edges")),
+        }
+    }
+
+    /// This function exports the edges of a `GraphFrame`, so there is no need to
+    /// extract them before calling `export`.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The path to the file where the edges are to be exported.
+    /// * `graph`: The `GraphFrame` whose edges are to be exported.
+    ///
+    /// Returns:
+    ///
+    /// This function returns a `Result<(), String>` as `export` does.
+    fn export_graph(path: &str, graph: &GraphFrame) -> Result<(), String> {
+        Self::export(path, &mut graph.edges.clone())
+    }
+}
+
+/// The `Spooled` struct holds the path of the temporary file the default
+/// implementations of `import_reader` and `export_writer` go through, which is
+/// removed when the struct is dropped.
+struct Spooled {
+    path: PathBuf,
+}
+
+impl Spooled {
+    fn new() -> Self {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let name = format!(
+            "pschema-spooled-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        Self {
+            path: std::env::temp_dir().join(name),
+        }
+    }
+
+    fn path(&self) -> &str {
+        self.path.to_str().unwrap_or_default()
+    }
+}
+
+impl Drop for Spooled {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// This function serializes a validated subset into an in-memory buffer, so it
+/// can be sent over the network without creating temporary files.
+///
+/// Arguments:
+///
+/// * `df`: The `DataFrame` resulting from the validation.
+/// * `format`: The `Format` the subset is to be serialized to.
+///
+/// Returns:
+///
+/// This function returns a `Result<Vec<u8>, String>` containing the serialized
+/// subset or an error message in case the serialization fails.
+pub fn export_to_bytes(df: &mut DataFrame, format: Format) -> Result<Vec<u8>, String> {
+    let mut buffer = Vec::new();
+    match format {
+        Format::NTriples => ntriples::NTriples::export_writer(&mut buffer, df)?,
+        Format::Parquet => parquet::Parquet::export_writer(&mut buffer, df)?,
+    }
+    Ok(buffer)
+}
+
+/// This function writes the distinct subjects of a validated subset to a file,
+/// one per line. IRIs are written without the surrounding angle brackets, so
+/// this is the simplest way of getting the conforming nodes when the full
+/// triples are not needed.
+///
+/// Arguments:
+///
+/// * `path`: The path to the file where the subjects are to be written.
+/// * `df`: The `DataFrame` resulting from the validation.
+///
+/// Returns:
+///
+/// This function returns a `Result<(), String>`, where the `String` is an error
+/// message in case any error occurs while writing the file.
+pub fn export_subject_list(path: &str, df: &DataFrame) -> Result<(), String> {
+    let subjects = match df
+        .clone()
+        .lazy()
+        .select([col(Column::Subject.as_ref()).cast(DataType::String)])
+        .unique_stable(None, UniqueKeepStrategy::First)
+        .collect()
+    {
+        Ok(subjects) => subjects,
+        Err(_) => return Err(String::from("Error retrieving the subjects")),
+    };
+
+    let subjects = match subjects.column(Column::Subject.as_ref()) {
+        Ok(subjects) => subjects.as_materialized_series().clone(),
+        Err(_) => return Err(String::from("Error retrieving the subjects")),
+    };
+
+    let mut writer = BufWriter::new(match File::create(path) {
+        Ok(file) => file,
+        Err(_) => return Err(String::from("Error creating the subject list file")),
+    });
+
+    for subject in subjects.str().unwrap().into_iter().flatten() {
+        let subject = subject
+            .strip_prefix('<')
+            .and_then(|subject| subject.strip_suffix('>'))
+            .unwrap_or(subject);
+        if writeln!(writer, "{}", subject).is_err() {
+            return Err(String::from("Error writing to the subject list file"));
+        }
+    }
+
+    match writer.flush() {
+        Ok(_) => Ok(()),
+        Err(_) => Err(String::from("Error storing the results to the file")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::Cursor;
+
+    use polars::prelude::*;
+
+    use crate::backends::ntriples::NTriples;
+    use crate::backends::parquet::Parquet;
+    use crate::backends::{export_subject_list, export_to_bytes, Backend, Format};
+    use crate::pschema::PSchema;
+    use crate::utils::examples::Value::*;
+    use crate::utils::examples::*;
+
+    #[test]
+    fn reader_writer_test() -> Result<(), String> {
+        let content = concat!(
+            "<http://example.org/alan> <http://example.org/knows> <http://example.org/bob> .\n",
+            "<http://example.org/bob> <http://example.org/knows> <http://example.org/alan> .\n",
+        );
+        // The N-Triples backend streams the edges, while the Parquet one goes through a file.
+        let mut edges = NTriples::import_reader(Cursor::new(content))?;
+        let mut parquet = Vec::new();
+        Parquet::export_writer(&mut parquet, &mut edges)?;
+        let mut edges = Parquet::import_reader(Cursor::new(parquet))?;
+        let mut exported = Vec::new();
+        NTriples::export_writer(&mut exported, &mut edges)?;
+        match String::from_utf8_lossy(&exported) == content {
+            true => Ok(()),
+            false => Err(format!(
+                "Unexpected export: {}",
+                String::from_utf8_lossy(&exported)
+            )),
+        }
+    }
+
+    #[test]
+    fn subject_list_test() -> Result<(), String> {
+        let graph = paper_graph()?;
+        let subset = match PSchema::new(simple_schema()).validate(graph) {
+            Ok(subset) => subset,
+            Err(error) => return Err(error.to_string()),
+        };
+
+        let path = std::env::temp_dir().join("pschema-subject-list.txt");
+        export_subject_list(path.to_str().unwrap(), &subset)?;
+
+        let mut actual = match fs::read_to_string(&path) {
+            Ok(content) => content.lines().map(String::from).collect::<Vec<_>>(),
+            Err(_) => return Err(String::from("Error reading the subject list file")),
+        };
+        actual.sort();
+
+        let mut expected = [TimBernersLee, VintCerf]
+            .iter()
+            .map(|value| value.id().to_string())
+            .collect::<Vec<_>>();
+        expected.sort();
+
+        match actual == expected {
+            true => Ok(()),
+            false => Err(format!("Unexpected subjects: {:?}", actual)),
+        }
+    }
+
+    #[test]
+    fn bytes_test() -> Result<(), String> {
+        let mut subset = match PSchema::new(paper_schema()).validate(paper_graph()?) {
+            Ok(subset) => subset,
+            Err(error) => return Err(error.to_string()),
+        };
+
+        let bytes = export_to_bytes(&mut subset, Format::Parquet)?;
+        match ParquetReader::new(Cursor::new(bytes)).finish() {
+            Ok(actual) if actual.height() == subset.height() => Ok(()),
+            Ok(_) => Err(String::from("The DataFrames are not equals")),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    #[test]
+    fn export_graph_test() -> Result<(), String> {
+        let path = std::env::temp_dir().join("pschema-export-graph.nt");
+        let content = concat!(
+            "<http://example.org/alan> <http://example.org/knows> <http://example.org/bob> .\n",
+            "<http://example.org/bob> <http://example.org/knows> <http://example.org/carol> .\n",
+        );
+        if fs::write(&path, content).is_err() {
+            return Err(String::from("Error writing the N-Triples file"));
+        }
+        let graph = match pregel_rs::graph_frame::GraphFrame::from_edges(NTriples::import(
+            path.to_str().unwrap(),
+        )?) {
+            Ok(graph) => graph,
+            Err(_) => return Err(String::from("Error creating the GraphFrame from edges")),
+        };
+
+        let exported = std::env::temp_dir().join("pschema-export-graph-exported.nt");
+        NTriples::export_graph(exported.to_str().unwrap(), &graph)?;
+
+        match NTriples::import(exported.to_str().unwrap()) {
+            Ok(edges) if edges.height() == graph.edges.height() => Ok(()),
+            Ok(edges) => Err(format!("Unexpected edges: {:?}", edges)),
+            Err(error) => Err(error),
+        }
+    }
 }
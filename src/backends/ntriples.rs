@@ -1,72 +1,371 @@
-use std::io::BufWriter;
+use std::io::{BufRead, BufWriter, Read, Write};
+use std::sync::mpsc::SyncSender;
 use std::{fs::File, io::BufReader};
 
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use memmap2::Mmap;
+use oxiri::Iri;
 use polars::df;
-use polars::enable_string_cache;
 use polars::prelude::*;
 use pregel_rs::pregel::Column;
-use rio_api::formatter::TriplesFormatter;
-use rio_api::model::{Literal, NamedNode, Triple};
+use rio_api::formatter::{QuadsFormatter, TriplesFormatter};
+use rio_api::model::{BlankNode, GraphName, Literal, NamedNode, Quad, Term, Triple};
 use rio_api::parser::TriplesParser;
+use rio_turtle::NQuadsFormatter;
 use rio_turtle::NTriplesFormatter;
 use rio_turtle::NTriplesParser;
 use rio_turtle::TurtleError;
+use rio_turtle::TurtleParser;
 
 use super::Backend;
+use crate::utils::check::check_string_cache;
+use crate::utils::dictionary::Dictionary;
+
+/// `XSD_STRING` is the datatype of the simple literals, as stored in the `dtype`
+/// column added by `ImportOptions::with_datatypes`.
+const XSD_STRING: &str = "<http://www.w3.org/2001/XMLSchema#string>";
+/// `RDF_LANG_STRING` is the datatype of the language-tagged literals, as stored
+/// in the `dtype` column added by `ImportOptions::with_datatypes`.
+const RDF_LANG_STRING: &str = "<http://www.w3.org/1999/02/22-rdf-syntax-ns#langString>";
 
 pub struct NTriples;
 
-impl Backend for NTriples {
-    fn import(path: &str) -> Result<DataFrame, String> {
-        enable_string_cache();
+/// The `ImportOptions` struct gathers the options that tune how an N-Triples
+/// file is imported.
+///
+/// Properties:
+///
+/// * `ordering`: the ordering of the categorical columns.
+/// * `normalize_literals`: whether the lexical forms of the literals are to be
+/// normalized by trimming any leading BOM and collapsing the whitespace. It is
+/// disabled by default, so strict users keep the raw values.
+/// * `base_iri`: the IRI the relative IRIs are resolved against. When set, the
+/// file is parsed as Turtle, which N-Triples is a subset of, so documents with
+/// relative IRIs can be imported. Absolute IRIs are left untouched.
+/// * `normalize_datatypes`: whether the datatype IRIs of the typed literals are
+/// to be canonicalized, so literals differing only by the spelling of their
+/// datatype match the same constraints. See `canonical_datatype` for the
+/// mapping. It is disabled by default.
+/// * `preserve_order`: whether an `_order` column holding the position of each
+/// triple in the file is to be added. It is carried through the validation, so
+/// the subset and its export follow the order of the file.
+/// * `object_kinds`: whether an `object_kind` column telling whether each object
+/// is an `iri`, a `blank` node or a `literal` is to be added. It is carried
+/// through the validation, so the export emits each object as the same kind of
+/// term, even if the subset has been transformed in between.
+/// * `datatypes`: whether a `dtype` column holding the datatype IRI of each of
+/// the literal objects is to be added, so they can be validated through
+/// `NodeConstraint::Datatype`. Simple literals are given `xsd:string` and the
+/// language-tagged ones `rdf:langString`, whereas IRIs and blank nodes are
+/// given NULL. The IRIs are kept as written, so `normalize_datatypes` should be
+/// set as well when the dump spells them in several ways.
+/// * `gzip`: whether the file is gzip-compressed, such as the `.nt.gz` dumps of
+/// Wikidata and UniProt, so it is decompressed while being read. When unset, it
+/// is decided upon the extension of the file: only `.gz` files are decompressed.
+#[derive(Clone, Debug)]
+pub struct ImportOptions {
+    ordering: CategoricalOrdering,
+    normalize_literals: bool,
+    base_iri: Option<String>,
+    normalize_datatypes: bool,
+    preserve_order: bool,
+    object_kinds: bool,
+    datatypes: bool,
+    gzip: Option<bool>,
+}
 
-        let mut subjects = Vec::<String>::new();
-        let mut predicates = Vec::<String>::new();
-        let mut objects = Vec::<String>::new();
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            ordering: CategoricalOrdering::Lexical,
+            normalize_literals: false,
+            base_iri: None,
+            normalize_datatypes: false,
+            preserve_order: false,
+            object_kinds: false,
+            datatypes: false,
+            gzip: None,
+        }
+    }
+}
 
-        let reader = BufReader::new(match File::open(path) {
-            Ok(file) => file,
-            Err(_) => return Err(String::from("Cannot open the file")),
-        });
-        let mut parser = NTriplesParser::new(reader);
+impl ImportOptions {
+    pub fn with_ordering(mut self, ordering: CategoricalOrdering) -> Self {
+        self.ordering = ordering;
+        self
+    }
 
-        let mut on_triple = |triple: Triple| {
-            {
-                subjects.push(triple.subject.to_string());
-                predicates.push(triple.predicate.to_string());
-                objects.push(triple.object.to_string());
-            };
-            Ok(())
-        } as Result<(), TurtleError>;
+    pub fn with_normalized_literals(mut self) -> Self {
+        self.normalize_literals = true;
+        self
+    }
 
-        while !parser.is_end() {
-            if parser.parse_step(&mut on_triple).is_err() {
-                continue;
-            }
+    pub fn with_base_iri(mut self, base_iri: &str) -> Self {
+        self.base_iri = Some(base_iri.to_string());
+        self
+    }
+
+    pub fn with_normalized_datatypes(mut self) -> Self {
+        self.normalize_datatypes = true;
+        self
+    }
+
+    pub fn with_preserved_order(mut self) -> Self {
+        self.preserve_order = true;
+        self
+    }
+
+    pub fn with_object_kinds(mut self) -> Self {
+        self.object_kinds = true;
+        self
+    }
+
+    pub fn with_datatypes(mut self) -> Self {
+        self.datatypes = true;
+        self
+    }
+
+    pub fn with_gzip(mut self, gzip: bool) -> Self {
+        self.gzip = Some(gzip);
+        self
+    }
+}
+
+/// This function tells whether the file at the provided path is to be treated
+/// as gzip-compressed, which is decided upon its `.gz` extension.
+fn is_gzip(path: &str) -> bool {
+    std::path::Path::new(path)
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("gz"))
+}
+
+/// The `SampleSpec` enum defines which triples of a file are imported when only
+/// a sample of it is needed, e.g. for iterating quickly on a schema against a
+/// huge dump.
+///
+/// Variants:
+///
+/// * `First(n)`: the first `n` triples of the file. The file is read no further.
+/// * `Random(fraction)`: each triple is kept with probability `fraction`, so the
+/// whole file is read, but only the sampled triples are held in memory.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SampleSpec {
+    First(usize),
+    Random(f64),
+}
+
+/// The `ParseError` struct describes a malformed triple found while importing
+/// an N-Triples file.
+///
+/// Properties:
+///
+/// * `line`: the number of the line the error was found at, starting from 1,
+/// if known.
+/// * `message`: the description of the error.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    pub line: Option<u64>,
+    pub message: String,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{} at line {}", self.message, line),
+            None => write!(f, "{}", self.message),
         }
+    }
+}
 
-        match df![
-            Column::Subject.as_ref() => Series::new(Column::Subject.as_ptr(), subjects).cast(&DataType::Categorical(None, CategoricalOrdering::Lexical)).unwrap(),
-            Column::Predicate.as_ref() => Series::new(Column::Predicate.as_ptr(), predicates).cast(&DataType::Categorical(None, CategoricalOrdering::Lexical)).unwrap(),
-            Column::Object.as_ref() => Series::new(Column::Object.as_ptr(), objects).cast(&DataType::Categorical(None, CategoricalOrdering::Lexical)).unwrap(),
-        ] {
-            Ok(edges) => Ok(edges),
-            Err(_) => Err(String::from("Error creating the edges DataFrame")),
+/// The `ChannelWriter` struct sends each of the lines written to it through a
+/// channel, so the N-Triples formatter can be reused for streaming the triples.
+struct ChannelWriter {
+    sender: SyncSender<String>,
+    buffer: Vec<u8>,
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        while let Some(end) = self.buffer.iter().position(|byte| *byte == b'\n') {
+            let line = self.buffer.drain(..=end).collect::<Vec<_>>();
+            let line = String::from_utf8_lossy(&line[..end]).into_owned();
+            if self.sender.send(line).is_err() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::BrokenPipe,
+                    "The receiver hung up",
+                ));
+            }
         }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Backend for NTriples {
+    fn import(path: &str) -> Result<DataFrame, String> {
+        Self::import_with_ordering(path, CategoricalOrdering::Lexical)
     }
 
     fn export(path: &str, df: &mut DataFrame) -> Result<(), String> {
-        let file = File::create(path).unwrap();
-        let writer = BufWriter::new(file);
+        if is_gzip(path) {
+            return Self::export_gz(path, df);
+        }
+        let file = match File::create(path) {
+            Ok(file) => file,
+            Err(_) => return Err(String::from("Error creating the N-Triples file")),
+        };
+        Self::export_writer(BufWriter::new(file), df)
+    }
+
+    /// This function imports the triples from any `Read` implementor, which is
+    /// parsed as it is read, so no temporary file is needed. As no extension is
+    /// available, compressed streams must be decompressed by the caller.
+    fn import_reader<R: Read>(reader: R) -> Result<DataFrame, String> {
+        Self::read_from(
+            BufReader::new(reader),
+            &ImportOptions::default(),
+            None,
+            None,
+        )
+        .map(|(edges, _)| edges)
+    }
+
+    /// This function serializes the triples of a `DataFrame` as N-Triples into
+    /// any `Write` implementor, such as a file or an in-memory buffer.
+    ///
+    /// Arguments:
+    ///
+    /// * `writer`: The destination of the serialized triples.
+    /// * `df`: The `DataFrame` containing the triples to be serialized.
+    ///
+    /// Returns:
+    ///
+    /// This function returns a `Result<(), String>`, where the `String` is an
+    /// error message in case any error occurs while serializing the triples.
+    fn export_writer<W: Write>(writer: W, df: &mut DataFrame) -> Result<(), String> {
         let mut formatter = NTriplesFormatter::new(writer);
+        Self::for_each_triple(df, |triple| formatter.format(&triple))?;
+        match formatter.finish() {
+            Ok(_) => Ok(()),
+            Err(_) => Err(String::from("Error storing the results to the file")),
+        }
+    }
+}
+
+impl NTriples {
+    /// This function forwards to `Backend::export_writer`, so the callers that
+    /// do not have the `Backend` trait in scope can still serialize the triples
+    /// into any `Write` implementor.
+    pub fn export_writer<W: Write>(writer: W, df: &mut DataFrame) -> Result<(), String> {
+        <Self as Backend>::export_writer(writer, df)
+    }
+
+    /// This function exports the triples of a `DataFrame` as gzip-compressed
+    /// N-Triples, regardless of the extension of the file. Note that `export`
+    /// already compresses the files whose extension is `.gz`.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The path to the compressed N-Triples file.
+    /// * `df`: The `DataFrame` containing the triples to be serialized.
+    ///
+    /// Returns:
+    ///
+    /// This function returns a `Result<(), String>` as `export_writer` does.
+    pub fn export_gz(path: &str, df: &mut DataFrame) -> Result<(), String> {
+        let file = match File::create(path) {
+            Ok(file) => file,
+            Err(_) => return Err(String::from("Error creating the N-Triples file")),
+        };
+        let mut encoder = GzEncoder::new(BufWriter::new(file), Compression::default());
+        Self::export_writer(&mut encoder, df)?;
+        match encoder.finish().and_then(|mut writer| writer.flush()) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(String::from("Error compressing the N-Triples file")),
+        }
+    }
+
+    /// This function serializes the triples of a `DataFrame` as N-Quads into
+    /// any `Write` implementor, so the subset can be loaded into a named graph
+    /// of a quad store. Each line holds the provided graph IRI as its fourth
+    /// term; in case no IRI is provided, the triples belong to the default
+    /// graph, so they are written as in N-Triples.
+    ///
+    /// Arguments:
+    ///
+    /// * `writer`: The destination of the serialized quads.
+    /// * `df`: The `DataFrame` containing the triples to be serialized.
+    /// * `graph`: The IRI of the named graph, without angle brackets, if any.
+    ///
+    /// Returns:
+    ///
+    /// This function returns a `Result<(), String>` as `export_writer` does.
+    pub fn export_nquads_writer<W: Write>(
+        writer: W,
+        df: &mut DataFrame,
+        graph: Option<&str>,
+    ) -> Result<(), String> {
+        let mut formatter = NQuadsFormatter::new(writer);
+        let graph_name = graph.map(|iri| GraphName::NamedNode(NamedNode { iri }));
+        Self::for_each_triple(df, |triple| {
+            formatter.format(&Quad {
+                subject: triple.subject,
+                predicate: triple.predicate,
+                object: triple.object,
+                graph_name,
+            })
+        })?;
+        match formatter.finish() {
+            Ok(_) => Ok(()),
+            Err(_) => Err(String::from("Error storing the results to the file")),
+        }
+    }
 
+    /// This function exports the triples of a `DataFrame` as N-Quads into the
+    /// file at the provided path, as described in `export_nquads_writer`.
+    pub fn export_nquads(
+        path: &str,
+        df: &mut DataFrame,
+        graph: Option<&str>,
+    ) -> Result<(), String> {
+        let file = match File::create(path) {
+            Ok(file) => file,
+            Err(_) => return Err(String::from("Error creating the N-Quads file")),
+        };
+        Self::export_nquads_writer(BufWriter::new(file), df, graph)
+    }
+
+    /// This function builds the `rio` triple of each of the rows of a
+    /// `DataFrame` and hands it over to the provided function, which formats it.
+    /// In case the `DataFrame` has an `_order` column, as imported with
+    /// `ImportOptions::with_preserved_order`, the triples follow that order.
+    fn for_each_triple<F>(df: &DataFrame, mut format: F) -> Result<(), String>
+    where
+        F: FnMut(Triple) -> std::io::Result<()>,
+    {
+        let order = Column::Custom("_order");
+        let kind = Column::Custom("object_kind");
+        let mut df = df.clone().lazy();
+        let schema = df.collect_schema().map_err(|error| error.to_string())?;
+        if schema.contains(order.as_ref()) {
+            df = df.sort([order.as_ref()], SortMultipleOptions::default());
+        }
+        let kind = match schema.contains(kind.as_ref()) {
+            true => col(kind.as_ref()).cast(DataType::String),
+            false => lit(NULL).cast(DataType::String),
+        };
         let df = df
-            .clone()
-            .lazy()
             .select([
                 col(Column::Subject.as_ref()).cast(DataType::String),
                 col(Column::Predicate.as_ref()).cast(DataType::String),
                 col(Column::Object.as_ref()).cast(DataType::String),
+                kind.alias(Column::Custom("object_kind").as_ref()),
             ])
             .collect()
             .unwrap();
@@ -77,72 +376,1445 @@ impl Backend for NTriples {
                 Err(_) => return Err(format!("Error retrieving the {}th row", i)),
             };
 
-            if formatter
-                .format(&Triple {
-                    subject: match row.get(0) {
-                        Some(subject) => match subject {
-                            AnyValue::String(iri) => NamedNode {
-                                iri: &iri[1..iri.len() - 1],
-                            }
-                            .into(),
-                            _ => {
-                                return Err(format!("Cannot parse from non-string at {}th row", i))
-                            }
-                        },
-                        None => {
-                            return Err(format!("Error obtaining the subject of the {}th row", i))
+            if format(Triple {
+                subject: match row.get(0) {
+                    Some(subject) => match subject {
+                        AnyValue::String(id) if id.starts_with("_:") => {
+                            BlankNode { id: &id[2..] }.into()
+                        }
+                        AnyValue::String(iri) => NamedNode {
+                            iri: Self::strip_iri(iri, i)?,
                         }
+                        .into(),
+                        _ => return Err(format!("Cannot parse from non-string at {}th row", i)),
                     },
-                    predicate: match row.get(1) {
-                        Some(predicate) => match predicate {
-                            AnyValue::String(iri) => NamedNode {
-                                iri: &iri[1..iri.len() - 1],
-                            },
-                            _ => {
-                                return Err(format!("Cannot parse from non-string at {}th row", i))
-                            }
+                    None => return Err(format!("Error obtaining the subject of the {}th row", i)),
+                },
+                predicate: match row.get(1) {
+                    Some(predicate) => match predicate {
+                        AnyValue::String(iri) => NamedNode {
+                            iri: Self::strip_iri(iri, i)?,
                         },
-                        None => {
-                            return Err(format!("Error obtaining the predicate of the {}th row", i))
-                        }
+                        _ => return Err(format!("Cannot parse from non-string at {}th row", i)),
                     },
-                    object: match row.get(2) {
-                        Some(object) => match object {
-                            AnyValue::String(iri) => {
-                                if iri.contains("^^") {
-                                    let v: Vec<_> = iri.split("^^").collect();
-                                    Literal::Typed {
-                                        value: &v[0][1..v[0].len() - 1],
-                                        datatype: NamedNode {
-                                            iri: &v[1][1..v[1].len() - 1],
-                                        },
-                                    }
-                                    .into()
-                                } else {
-                                    NamedNode {
-                                        iri: &iri[1..iri.len() - 1],
+                    None => {
+                        return Err(format!("Error obtaining the predicate of the {}th row", i))
+                    }
+                },
+                object: match row.get(2) {
+                    Some(object) => match (object, row.get(3)) {
+                        // Literals known to be so are emitted as such, even if they lack the quotes.
+                        (AnyValue::String(value), Some(AnyValue::String("literal")))
+                            if !value.starts_with('"') =>
+                        {
+                            Literal::Simple { value: *value }.into()
+                        }
+                        (AnyValue::String(iri), _) if iri.starts_with('"') => {
+                            match Self::split_literal(iri, i)? {
+                                (value, "") => Literal::Simple { value }.into(),
+                                (value, suffix) => {
+                                    match (suffix.strip_prefix("^^"), suffix.strip_prefix('@')) {
+                                        (Some(datatype), _) => Literal::Typed {
+                                            value,
+                                            datatype: NamedNode {
+                                                iri: Self::strip_iri(datatype, i)?,
+                                            },
+                                        }
+                                        .into(),
+                                        (_, Some(language)) if Self::is_language_tag(language) => {
+                                            Literal::LanguageTaggedString { value, language }.into()
+                                        }
+                                        _ => {
+                                            return Err(format!(
+                                                "Unsupported literal {} at the {}th row",
+                                                iri, i
+                                            ))
+                                        }
                                     }
-                                    .into()
                                 }
                             }
-                            _ => {
-                                return Err(format!("Cannot parse from non-string at {}th row", i))
-                            }
-                        },
-                        None => {
-                            return Err(format!("Error obtaining the object of the {}th row", i))
                         }
+                        (AnyValue::String(id), _) if id.starts_with("_:") => {
+                            BlankNode { id: &id[2..] }.into()
+                        }
+                        (AnyValue::String(iri), _) => NamedNode {
+                            iri: Self::strip_iri(iri, i)?,
+                        }
+                        .into(),
+                        _ => return Err(format!("Cannot parse from non-string at {}th row", i)),
                     },
-                })
-                .is_err()
+                    None => return Err(format!("Error obtaining the object of the {}th row", i)),
+                },
+            })
+            .is_err()
             {
                 return Err(format!("Error parsing the {}th row", i));
             }
         }
+        Ok(())
+    }
 
-        match formatter.finish() {
-            Ok(_) => Ok(()),
-            Err(_) => Err(String::from("Error storing the results to the file")),
+    /// This function serializes the triples of a `DataFrame` as N-Triples and
+    /// sends each of them, without the trailing newline, to a bounded channel
+    /// as soon as it is formatted. As sending blocks while the channel is full,
+    /// the export proceeds at the pace of the receiver.
+    ///
+    /// Arguments:
+    ///
+    /// * `df`: The `DataFrame` containing the triples to be serialized.
+    /// * `sender`: The sending half of the bounded channel.
+    ///
+    /// Returns:
+    ///
+    /// This function returns a `Result<(), String>`, where the `String` is an
+    /// error message in case any triple cannot be serialized or the receiver
+    /// hung up.
+    pub fn export_channel(df: &mut DataFrame, sender: SyncSender<String>) -> Result<(), String> {
+        Self::export_writer(
+            ChannelWriter {
+                sender,
+                buffer: Vec::new(),
+            },
+            df,
+        )
+    }
+
+    /// This function imports an N-Triples file choosing the ordering of the
+    /// categorical columns. `CategoricalOrdering::Lexical` sorts categories by
+    /// their string value, whereas `CategoricalOrdering::Physical` keeps the
+    /// order in which they were inserted in the string cache. The latter is
+    /// faster, as no sorting is needed, but the codes of the categories are then
+    /// insertion-ordered. The validation only relies on equality, so both of
+    /// them produce the same results.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The path to the N-Triples file.
+    /// * `ordering`: The ordering of the categorical columns.
+    ///
+    /// Returns:
+    ///
+    /// This function returns a `Result<DataFrame, String>` as `import` does.
+    pub fn import_with_ordering(
+        path: &str,
+        ordering: CategoricalOrdering,
+    ) -> Result<DataFrame, String> {
+        Self::import_with_options(path, &ImportOptions::default().with_ordering(ordering))
+    }
+
+    /// This function imports an N-Triples file according to the provided
+    /// `ImportOptions`.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The path to the N-Triples file.
+    /// * `options`: The `ImportOptions` tuning the import.
+    ///
+    /// Returns:
+    ///
+    /// This function returns a `Result<DataFrame, String>` as `import` does.
+    pub fn import_with_options(path: &str, options: &ImportOptions) -> Result<DataFrame, String> {
+        Self::read(path, options, None, None).map(|(edges, _)| edges)
+    }
+
+    /// This function imports an N-Triples file collecting the malformed lines
+    /// instead of skipping them silently, so the quality of the data can be
+    /// audited. The import fails as soon as the number of malformed lines
+    /// exceeds the provided maximum.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The path to the N-Triples file.
+    /// * `max_errors`: The maximum number of malformed lines tolerated.
+    ///
+    /// Returns:
+    ///
+    /// This function returns a `Result<(DataFrame, Vec<ParseError>), String>`
+    /// with the edges of the well-formed triples and the errors found, or an
+    /// error message in case there are too many of them.
+    pub fn import_strict(
+        path: &str,
+        max_errors: usize,
+    ) -> Result<(DataFrame, Vec<ParseError>), String> {
+        Self::read(path, &ImportOptions::default(), None, Some(max_errors))
+    }
+
+    /// This function imports only a sample of an N-Triples file, as described by
+    /// the provided `SampleSpec`.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The path to the N-Triples file.
+    /// * `sample`: The `SampleSpec` defining which triples are imported.
+    ///
+    /// Returns:
+    ///
+    /// This function returns a `Result<DataFrame, String>` as `import` does.
+    pub fn import_sample(path: &str, sample: SampleSpec) -> Result<DataFrame, String> {
+        Self::read(path, &ImportOptions::default(), Some(sample), None).map(|(edges, _)| edges)
+    }
+
+    /// This function imports an N-Triples file by memory-mapping it, so the
+    /// parser reads the triples straight from the mapped pages instead of
+    /// copying them into intermediate buffers, which pays off for very large
+    /// local files. Compressed files are decompressed as in `import`.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The path to the N-Triples file.
+    ///
+    /// Returns:
+    ///
+    /// This function returns a `Result<DataFrame, String>` as `import` does.
+    pub fn import_mmap(path: &str) -> Result<DataFrame, String> {
+        let options = ImportOptions::default();
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(_) => return Err(String::from("Cannot open the file")),
+        };
+        // SAFETY: the mapping is only read while parsing, which finishes before it is dropped at
+        // the end of this function, and every parsed term is copied into an owned `String`, so no
+        // reference to the mapped memory outlives it. Modifying the file while it is being imported
+        // is undefined behavior, as it is for any memory-mapped file.
+        let mmap = match unsafe { Mmap::map(&file) } {
+            Ok(mmap) => mmap,
+            Err(_) => return Err(String::from("Cannot memory-map the file")),
+        };
+        let reader: Box<dyn BufRead> = match options.gzip.unwrap_or_else(|| is_gzip(path)) {
+            true => Box::new(BufReader::new(GzDecoder::new(&mmap[..]))),
+            false => Box::new(&mmap[..]),
+        };
+        Self::read_from(reader, &options, None, None).map(|(edges, _)| edges)
+    }
+
+    /// This function imports an N-Triples file interning the subjects, predicates
+    /// and objects into `u32` codes, so the resulting graph can be validated with
+    /// integer `TripleConstraint`s, which compare faster than the categorical
+    /// ones, as the graphs imported from DuckDB are. The terms are kept in their
+    /// N-Triples form, so IRIs are enclosed in angle brackets.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The path to the N-Triples file.
+    ///
+    /// Returns:
+    ///
+    /// This function returns a `Result<(DataFrame, Dictionary), String>` with
+    /// the interned edges and the `Dictionary` mapping the codes back to the
+    /// terms, which can be used for building the schema and for decoding the
+    /// results of the validation.
+    pub fn import_interned(path: &str) -> Result<(DataFrame, Dictionary), String> {
+        let edges = Self::import(path)?;
+        let mut dictionary = Dictionary::new();
+        let mut columns: Vec<polars::prelude::Column> = Vec::new();
+        for column in [Column::Subject, Column::Predicate, Column::Object] {
+            let terms = match edges
+                .column(column.as_ref())
+                .and_then(|terms| terms.cast(&DataType::String))
+            {
+                Ok(terms) => terms,
+                Err(_) => return Err(format!("Error retrieving the {} column", column.as_ref())),
+            };
+            let codes = terms
+                .str()
+                .unwrap()
+                .into_iter()
+                .map(|term| dictionary.intern(term.unwrap_or_default()))
+                .collect::<Result<Vec<_>, _>>()?;
+            columns.push(Series::new(column.as_ptr(), codes).into());
+        }
+        match DataFrame::new(columns) {
+            Ok(edges) => Ok((edges, dictionary)),
+            Err(_) => Err(String::from("Error creating the edges DataFrame")),
+        }
+    }
+
+    fn read(
+        path: &str,
+        options: &ImportOptions,
+        sample: Option<SampleSpec>,
+        max_errors: Option<usize>,
+    ) -> Result<(DataFrame, Vec<ParseError>), String> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(_) => return Err(String::from("Cannot open the file")),
+        };
+        let reader: Box<dyn BufRead> = match options.gzip.unwrap_or_else(|| is_gzip(path)) {
+            true => Box::new(BufReader::new(GzDecoder::new(file))),
+            false => Box::new(BufReader::new(file)),
+        };
+        Self::read_from(reader, options, sample, max_errors)
+    }
+
+    pub(crate) fn read_from<R: BufRead>(
+        reader: R,
+        options: &ImportOptions,
+        sample: Option<SampleSpec>,
+        max_errors: Option<usize>,
+    ) -> Result<(DataFrame, Vec<ParseError>), String> {
+        check_string_cache().map_err(|error| error.to_string())?;
+
+        let mut columns = [Vec::new(), Vec::new(), Vec::new()];
+        let mut errors = Vec::new();
+        match &options.base_iri {
+            Some(base_iri) => match Iri::parse(base_iri.to_owned()) {
+                Ok(base_iri) => Self::parse(
+                    TurtleParser::new(reader, Some(base_iri)),
+                    options,
+                    sample,
+                    &mut columns,
+                    (&mut errors, max_errors),
+                )?,
+                Err(_) => return Err(format!("Invalid base IRI {}", base_iri)),
+            },
+            None => Self::parse(
+                NTriplesParser::new(reader),
+                options,
+                sample,
+                &mut columns,
+                (&mut errors, max_errors),
+            )?,
+        }
+        let [subjects, predicates, objects] = columns;
+
+        let dtype = DataType::Categorical(None, options.ordering);
+        match df![
+            Column::Subject.as_ref() => Series::new(Column::Subject.as_ptr(), subjects).cast(&dtype).unwrap(),
+            Column::Predicate.as_ref() => Series::new(Column::Predicate.as_ptr(), predicates).cast(&dtype).unwrap(),
+            Column::Object.as_ref() => Series::new(Column::Object.as_ptr(), objects).cast(&dtype).unwrap(),
+        ] {
+            Ok(edges) if options.preserve_order => edges
+                .with_row_index(Column::Custom("_order").as_ptr(), None)
+                .and_then(|edges| Self::with_object_kinds(edges, options))
+                .and_then(|edges| Self::with_datatypes(edges, options))
+                .map(|edges| (edges, errors))
+                .map_err(|_| String::from("Error numbering the edges")),
+            Ok(edges) => Self::with_object_kinds(edges, options)
+                .and_then(|edges| Self::with_datatypes(edges, options))
+                .map(|edges| (edges, errors))
+                .map_err(|_| String::from("Error classifying the objects")),
+            Err(_) => Err(String::from("Error creating the edges DataFrame")),
+        }
+    }
+
+    /// This function adds the `object_kind` column to the edges, if requested.
+    /// As the objects are stored in their N-Triples form, literals start with a
+    /// quote and blank nodes with `_:`, while the rest of them are IRIs.
+    fn with_object_kinds(edges: DataFrame, options: &ImportOptions) -> PolarsResult<DataFrame> {
+        if !options.object_kinds {
+            return Ok(edges);
+        }
+        let object = col(Column::Object.as_ref()).cast(DataType::String);
+        edges
+            .lazy()
+            .with_column(
+                when(object.clone().str().starts_with(lit("\"")))
+                    .then(lit("literal"))
+                    .when(object.str().starts_with(lit("_:")))
+                    .then(lit("blank"))
+                    .otherwise(lit("iri"))
+                    .cast(DataType::Categorical(None, options.ordering))
+                    .alias(Column::Custom("object_kind").as_ref()),
+            )
+            .collect()
+    }
+
+    /// This function adds the `dtype` column to the edges, if requested. As the
+    /// objects are stored in their N-Triples form, the datatype of the typed
+    /// literals is the IRI following their `^^`, while the rest of the literals
+    /// are either language-tagged or simple.
+    fn with_datatypes(edges: DataFrame, options: &ImportOptions) -> PolarsResult<DataFrame> {
+        if !options.datatypes {
+            return Ok(edges);
+        }
+        let object = col(Column::Object.as_ref()).cast(DataType::String);
+        let datatype = object.clone().str().extract(lit(r#""\^\^(<[^>]*>)$"#), 1);
+        edges
+            .lazy()
+            .with_column(
+                when(object.clone().str().starts_with(lit("\"")).not())
+                    .then(lit(NULL).cast(DataType::String))
+                    .when(datatype.clone().is_not_null())
+                    .then(datatype)
+                    .when(
+                        object
+                            .str()
+                            .contains(lit(r#""@[a-zA-Z]+(-[a-zA-Z0-9]+)*$"#), true),
+                    )
+                    .then(lit(RDF_LANG_STRING))
+                    .otherwise(lit(XSD_STRING))
+                    .cast(DataType::Categorical(None, options.ordering))
+                    .alias(Column::Custom("dtype").as_ref()),
+            )
+            .collect()
+    }
+
+    /// This function parses the triples provided by any of the `rio` parsers into
+    /// the subject, predicate and object columns, in that order. Malformed
+    /// triples are skipped, and the errors are collected. In case a maximum
+    /// number of errors is provided, the parsing fails once it is exceeded.
+    fn parse<P: TriplesParser<Error = TurtleError>>(
+        mut parser: P,
+        options: &ImportOptions,
+        sample: Option<SampleSpec>,
+        columns: &mut [Vec<String>; 3],
+        (errors, max_errors): (&mut Vec<ParseError>, Option<usize>),
+    ) -> Result<(), String> {
+        let [subjects, predicates, objects] = columns;
+        while !parser.is_end() {
+            if let Some(SampleSpec::First(n)) = sample {
+                if subjects.len() >= n {
+                    break;
+                }
+            }
+            let mut on_triple = |triple: Triple| -> Result<(), TurtleError> {
+                if let Some(SampleSpec::Random(fraction)) = sample {
+                    if rand::random::<f64>() >= fraction {
+                        return Ok(());
+                    }
+                }
+                subjects.push(triple.subject.to_string());
+                predicates.push(triple.predicate.to_string());
+                let datatype;
+                let object = match (options.normalize_datatypes, triple.object) {
+                    (
+                        true,
+                        Term::Literal(Literal::Typed {
+                            value,
+                            datatype: iri,
+                        }),
+                    ) => {
+                        datatype = Self::canonical_datatype(iri.iri);
+                        Term::Literal(Literal::Typed {
+                            value,
+                            datatype: NamedNode { iri: &datatype },
+                        })
+                    }
+                    (_, object) => object,
+                };
+                objects.push(match options.normalize_literals {
+                    true => Self::normalize_literal(object),
+                    false => object.to_string(),
+                });
+                Ok(())
+            };
+            if let Err(error) = parser.parse_step(&mut on_triple) {
+                errors.push(ParseError {
+                    line: error
+                        .textual_position()
+                        .map(|position| position.line_number() + 1),
+                    message: error.to_string(),
+                });
+                if let Some(max_errors) = max_errors {
+                    if errors.len() > max_errors {
+                        return Err(format!(
+                            "Too many malformed triples, the last one being {}",
+                            errors[errors.len() - 1]
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// This function canonicalizes the IRI of the datatype of a typed literal.
+    /// The IRIs in the XML Schema namespace, whether written in full, with the
+    /// `https` scheme or as `xsd:` prefixed names, are mapped to the full IRI
+    /// with the `http` scheme, and their local name is matched regardless of
+    /// its casing against the XSD datatypes, e.g. `xsd:INTEGER` is mapped to
+    /// `http://www.w3.org/2001/XMLSchema#integer`. Any other IRI, or a local
+    /// name that is not an XSD datatype, is left untouched.
+    pub(crate) fn canonical_datatype(iri: &str) -> String {
+        const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+        const DATATYPES: [&str; 22] = [
+            "string",
+            "boolean",
+            "decimal",
+            "integer",
+            "double",
+            "float",
+            "date",
+            "time",
+            "dateTime",
+            "dateTimeStamp",
+            "duration",
+            "gYear",
+            "gYearMonth",
+            "long",
+            "int",
+            "short",
+            "byte",
+            "nonNegativeInteger",
+            "positiveInteger",
+            "nonPositiveInteger",
+            "negativeInteger",
+            "anyURI",
+        ];
+        let lowercase = iri.to_lowercase();
+        let local_name = [
+            "http://www.w3.org/2001/xmlschema#",
+            "https://www.w3.org/2001/xmlschema#",
+            "xsd:",
+        ]
+        .iter()
+        .find_map(|namespace| lowercase.strip_prefix(namespace));
+        match local_name.and_then(|name| {
+            DATATYPES
+                .iter()
+                .find(|datatype| datatype.to_lowercase() == name)
+        }) {
+            Some(datatype) => format!("{}{}", XSD, datatype),
+            None => iri.to_string(),
+        }
+    }
+
+    /// This function normalizes the lexical form of a literal by removing any
+    /// leading UTF-8 BOM and collapsing the whitespace, so that the values
+    /// produced by broken tools match the constraints written by the users.
+    /// Any other term is left untouched.
+    fn normalize_literal(term: Term) -> String {
+        let normalize = |value: &str| {
+            value
+                .trim_start_matches('\u{feff}')
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        match term {
+            Term::Literal(Literal::Simple { value }) => Literal::Simple {
+                value: &normalize(value),
+            }
+            .to_string(),
+            Term::Literal(Literal::LanguageTaggedString { value, language }) => {
+                Literal::LanguageTaggedString {
+                    value: &normalize(value),
+                    language,
+                }
+                .to_string()
+            }
+            Term::Literal(Literal::Typed { value, datatype }) => Literal::Typed {
+                value: &normalize(value),
+                datatype,
+            }
+            .to_string(),
+            term => term.to_string(),
+        }
+    }
+
+    /// This function splits a literal, as stored in the DataFrame, into its
+    /// lexical form, without the surrounding quotes, and whatever follows the
+    /// closing quote, such as the `^^` datatype. Empty lexical forms, `""`, are
+    /// supported, while a literal lacking its closing quote results in an error
+    /// instead of a panic.
+    ///
+    /// Arguments:
+    ///
+    /// * `literal`: The literal, including its quotes, as stored in the DataFrame.
+    /// * `i`: The row the literal belongs to, used for reporting errors.
+    fn split_literal(literal: &str, i: usize) -> Result<(&str, &str), String> {
+        match literal.rfind('"') {
+            Some(end) if end > 0 => Ok((&literal[1..end], &literal[end + 1..])),
+            _ => Err(format!(
+                "Literal {} at the {}th row is not enclosed in quotes",
+                literal, i
+            )),
+        }
+    }
+
+    /// This function checks whether the provided string is a well-formed
+    /// N-Triples language tag, that is, letters optionally followed by several
+    /// `-` separated subtags made of letters and digits, such as `en` or `es-ES`.
+    fn is_language_tag(language: &str) -> bool {
+        let mut subtags = language.split('-');
+        subtags.next().is_some_and(|primary| {
+            !primary.is_empty() && primary.chars().all(|c| c.is_ascii_alphabetic())
+        }) && subtags
+            .all(|subtag| !subtag.is_empty() && subtag.chars().all(|c| c.is_ascii_alphanumeric()))
+    }
+
+    /// This function removes the angle brackets surrounding an IRI, making sure
+    /// that the resulting IRI can be written as an N-Triples `IRIREF`. That is,
+    /// it must be wrapped by a single pair of brackets and it cannot contain any
+    /// space nor any of the `<`, `>`, `"`, `{`, `}`, `|`, `^`, `` ` `` or `\`
+    /// characters.
+    ///
+    /// Arguments:
+    ///
+    /// * `iri`: The IRI, including its angle brackets, as stored in the DataFrame.
+    /// * `i`: The row the IRI belongs to, used for reporting errors.
+    ///
+    /// Returns:
+    ///
+    /// The IRI without the angle brackets or a descriptive error message in case
+    /// it would produce an invalid triple.
+    fn strip_iri(iri: &str, i: usize) -> Result<&str, String> {
+        let inner = match iri.strip_prefix('<').and_then(|iri| iri.strip_suffix('>')) {
+            Some(inner) => inner,
+            None => {
+                return Err(format!(
+                    "IRI {} at the {}th row is not enclosed in <>",
+                    iri, i
+                ))
+            }
+        };
+        match inner
+            .chars()
+            .find(|c| c.is_whitespace() || c.is_control() || "<>\"{}|^`\\".contains(*c))
+        {
+            Some(c) => Err(format!(
+                "IRI {} at the {}th row contains the invalid character {:?}",
+                iri, i, c
+            )),
+            None => Ok(inner),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::path::PathBuf;
+
+    use chrono::NaiveDate;
+    use polars::df;
+    use polars::prelude::{CategoricalOrdering, DataFrame, DataType};
+    use pregel_rs::graph_frame::GraphFrame;
+    use pregel_rs::pregel::Column;
+
+    use crate::backends::ntriples::{ImportOptions, NTriples, SampleSpec};
+    use crate::backends::Backend;
+    use crate::pschema::PSchema;
+    use crate::shape::shex::{
+        Bound, NodeConstraint, NumericFacet, Shape, ShapeAnd, TripleConstraint,
+    };
+    use crate::test_support::assert_conforms;
+
+    /// The function imports the provided N-Triples content in memory, so the
+    /// tests do not need to write it to a file beforehand.
+    fn read(content: &str, options: &ImportOptions) -> Result<DataFrame, String> {
+        NTriples::read_from(Cursor::new(content), options, None, None).map(|(edges, _)| edges)
+    }
+
+    /// The function imports the provided N-Triples content in memory, as `read`
+    /// does, and builds a `GraphFrame` out of the resulting edges.
+    fn read_graph(content: &str, options: &ImportOptions) -> Result<GraphFrame, String> {
+        match GraphFrame::from_edges(read(content, options)?) {
+            Ok(graph) => Ok(graph),
+            Err(_) => Err(String::from("Error creating the GraphFrame from edges")),
+        }
+    }
+
+    /// The function returns the path of a temporary file for the tests that
+    /// need one, which is unique to the running process, so concurrent runs of
+    /// the tests do not overwrite each other's files.
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("pschema-{}-{}", std::process::id(), name))
+    }
+
+    fn validate_paper(ordering: CategoricalOrdering) -> Result<usize, String> {
+        let edges = NTriples::import_with_ordering("./examples/paper/paper.nt", ordering)?;
+        let graph = match GraphFrame::from_edges(edges) {
+            Ok(graph) => graph,
+            Err(_) => return Err(String::from("Error creating the GraphFrame from edges")),
+        };
+        let schema: Shape<&str> = TripleConstraint::new(
+            "Human",
+            "<http://example.org/instanceOf>",
+            NodeConstraint::Value("<http://example.org/Human>"),
+        )
+        .into();
+        match PSchema::new(schema).validate(graph) {
+            Ok(subset) => Ok(subset.height()),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    #[test]
+    fn ordering_test() -> Result<(), String> {
+        let lexical = validate_paper(CategoricalOrdering::Lexical)?;
+        let physical = validate_paper(CategoricalOrdering::Physical)?;
+        match lexical > 0 && lexical == physical {
+            true => Ok(()),
+            false => Err(format!("Results differ: {} vs {}", lexical, physical)),
+        }
+    }
+
+    #[test]
+    fn invalid_iri_test() -> Result<(), String> {
+        let mut edges = match df![
+            Column::Subject.as_ref() => ["<http://example.org/<alan>>"],
+            Column::Predicate.as_ref() => ["<http://example.org/instanceOf>"],
+            Column::Object.as_ref() => ["<http://example.org/Human>"],
+        ] {
+            Ok(edges) => edges,
+            Err(_) => return Err(String::from("Error creating the edges DataFrame")),
+        };
+
+        match NTriples::export_writer(&mut Vec::new(), &mut edges) {
+            Ok(_) => Err(String::from("An error should have occurred")),
+            Err(_) => Ok(()),
+        }
+    }
+
+    #[test]
+    fn normalized_datatype_test() -> Result<(), String> {
+        let content = "<http://example.org/a> <http://example.org/p> \"5\"^^<http://www.w3.org/2001/XMLSchema#integer> .\n\
+            <http://example.org/b> <http://example.org/p> \"5\"^^<xsd:integer> .\n\
+            <http://example.org/c> <http://example.org/p> \"5\"^^<http://www.w3.org/2001/XMLSchema#INTEGER> .\n";
+
+        let graph = read_graph(
+            content,
+            &ImportOptions::default().with_normalized_datatypes(),
+        )?;
+        let schema: Shape<&str> = TripleConstraint::new(
+            "Five",
+            "<http://example.org/p>",
+            NodeConstraint::Value("\"5\"^^<http://www.w3.org/2001/XMLSchema#integer>"),
+        )
+        .into();
+        match PSchema::new(schema).validate(graph) {
+            Ok(subset) if subset.height() == 3 => Ok(()),
+            Ok(subset) => Err(format!("Unexpected subset: {:?}", subset)),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    #[test]
+    fn normalized_literal_test() -> Result<(), String> {
+        let content =
+            "<http://example.org/alan> <http://example.org/name> \"\u{feff}Alan  Turing \" .\n";
+
+        let graph = read_graph(
+            content,
+            &ImportOptions::default().with_normalized_literals(),
+        )?;
+        let schema: Shape<&str> = TripleConstraint::new(
+            "Name",
+            "<http://example.org/name>",
+            NodeConstraint::Value("\"Alan Turing\""),
+        )
+        .into();
+        match PSchema::new(schema).validate(graph) {
+            Ok(subset) if subset.height() == 1 => Ok(()),
+            Ok(subset) => Err(format!("Unexpected subset: {:?}", subset)),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    #[test]
+    fn rdf_type_shortcut_test() -> Result<(), String> {
+        let content = "<http://example.org/alan> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.org/Human> .\n";
+
+        let graph = read_graph(content, &ImportOptions::default())?;
+        let schema: Shape<&str> = TripleConstraint::iri(
+            "Human",
+            "a",
+            NodeConstraint::Value("<http://example.org/Human>"),
+        )
+        .into();
+        match PSchema::new(schema).validate(graph) {
+            Ok(subset) if subset.height() == 1 => Ok(()),
+            Ok(subset) => Err(format!("Unexpected subset: {:?}", subset)),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    #[test]
+    fn fragment_iri_test() -> Result<(), String> {
+        let content = concat!(
+            "<http://example.org/alan> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.org/Human> .\n",
+            "<http://example.org/bob> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.org/Human> .\n",
+        );
+
+        for predicate in [
+            "rdf:type",
+            "http://www.w3.org/1999/02/22-rdf-syntax-ns#type",
+            "<http://www.w3.org/1999/02/22-rdf-syntax-ns#type>",
+        ] {
+            let graph = read_graph(content, &ImportOptions::default())?;
+            let schema: Shape<&str> = TripleConstraint::iri(
+                "Human",
+                predicate,
+                NodeConstraint::Value("http://example.org/Human"),
+            )
+            .into();
+            match PSchema::new(schema).validate(graph) {
+                Ok(subset) if subset.height() == 2 => continue,
+                Ok(subset) => {
+                    return Err(format!("Unexpected subset for {}: {:?}", predicate, subset))
+                }
+                Err(error) => return Err(error.to_string()),
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn date_before_test() -> Result<(), String> {
+        let content = concat!(
+            "<http://example.org/alan> <http://example.org/dateOfBirth> \"1912-06-23\"^^<http://www.w3.org/2001/XMLSchema#date> .\n",
+            "<http://example.org/bob> <http://example.org/dateOfBirth> \"2001-02-03T10:00:00+02:00\"^^<http://www.w3.org/2001/XMLSchema#dateTime> .\n",
+        );
+
+        let graph = read_graph(content, &ImportOptions::default())?;
+        let schema: Shape<&str> = TripleConstraint::new(
+            "Before2000",
+            "<http://example.org/dateOfBirth>",
+            NodeConstraint::DateBefore(NaiveDate::from_ymd_opt(2000, 1, 1).unwrap()),
+        )
+        .into();
+        let subset = match PSchema::new(schema).validate(graph) {
+            Ok(subset) => subset,
+            Err(error) => return Err(error.to_string()),
+        };
+        match subset
+            .column(Column::Subject.as_ref())
+            .and_then(|subjects| subjects.cast(&DataType::String))
+        {
+            Ok(subjects)
+                if subjects.str().unwrap().get(0) == Some("<http://example.org/alan>")
+                    && subjects.len() == 1 =>
+            {
+                Ok(())
+            }
+            _ => Err(format!("Unexpected subset: {:?}", subset)),
+        }
+    }
+
+    #[test]
+    fn import_sample_test() -> Result<(), String> {
+        let content = (0..10)
+            .map(|i| {
+                format!(
+                    "<http://example.org/{}> <http://example.org/next> <http://example.org/{}> .\n",
+                    i,
+                    i + 1
+                )
+            })
+            .collect::<String>();
+
+        let options = ImportOptions::default();
+        match NTriples::read_from(
+            Cursor::new(content),
+            &options,
+            Some(SampleSpec::First(3)),
+            None,
+        ) {
+            Ok((edges, _)) if edges.height() == 3 => Ok(()),
+            Ok((edges, _)) => Err(format!("Expected 3 triples, found {}", edges.height())),
+            Err(error) => Err(error),
+        }
+    }
+
+    #[test]
+    fn empty_literal_test() -> Result<(), String> {
+        let content = "<http://example.org/alan> <http://example.org/nickname> \"\" .\n";
+
+        let mut edges = read(content, &ImportOptions::default())?;
+        let mut buffer = Vec::new();
+        NTriples::export_writer(&mut buffer, &mut edges)?;
+        match String::from_utf8_lossy(&buffer) == content {
+            true => Ok(()),
+            false => Err(format!(
+                "Unexpected N-Triples: {}",
+                String::from_utf8_lossy(&buffer)
+            )),
+        }
+    }
+
+    #[test]
+    fn base_iri_test() -> Result<(), String> {
+        let content = "<alan> <knows> <http://example.org/bob> .\n";
+
+        let edges = read(
+            content,
+            &ImportOptions::default().with_base_iri("http://example.org/"),
+        )?;
+        let value = |column: Column| -> Option<String> {
+            edges
+                .column(column.as_ref())
+                .ok()?
+                .as_materialized_series()
+                .cast(&DataType::String)
+                .ok()?
+                .str()
+                .ok()?
+                .get(0)
+                .map(String::from)
+        };
+        match (
+            value(Column::Subject),
+            value(Column::Predicate),
+            value(Column::Object),
+        ) {
+            (Some(subject), Some(predicate), Some(object))
+                if subject == "<http://example.org/alan>"
+                    && predicate == "<http://example.org/knows>"
+                    && object == "<http://example.org/bob>" =>
+            {
+                Ok(())
+            }
+            _ => Err(format!("Unexpected edges: {:?}", edges)),
+        }
+    }
+
+    #[test]
+    fn id_range_test() -> Result<(), String> {
+        let content = concat!(
+            "<http://example.org/paris> <http://www.wikidata.org/prop/direct/P31> <http://www.wikidata.org/entity/Q515> .\n",
+            "<http://example.org/tomelloso> <http://www.wikidata.org/prop/direct/P31> <http://www.wikidata.org/entity/Q3957> .\n",
+            "<http://example.org/alan> <http://www.wikidata.org/prop/direct/P31> <http://www.wikidata.org/entity/Q5> .\n",
+        );
+
+        let graph = read_graph(content, &ImportOptions::default())?;
+        let schema: Shape<&str> = TripleConstraint::new(
+            "Settlement",
+            "<http://www.wikidata.org/prop/direct/P31>",
+            NodeConstraint::IdRange(500, 5000),
+        )
+        .into();
+        match PSchema::new(schema).validate(graph) {
+            Ok(subset) if subset.height() == 2 => Ok(()),
+            Ok(subset) => Err(format!("Unexpected subset: {:?}", subset)),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    #[test]
+    fn custom_matcher_test() -> Result<(), String> {
+        let graph = crate::backends::memory::Memory::graph([
+            (
+                "<http://example.org/alan>",
+                "<http://example.org/knows>",
+                "<http://example.org/bob>",
+            ),
+            (
+                "<http://example.org/bob>",
+                "<http://example.org/knows>",
+                "<http://example.org/carl>",
+            ),
+        ])?;
+        let schema: Shape<&str> = TripleConstraint::new(
+            "KnowsEven",
+            "<http://example.org/knows>",
+            NodeConstraint::custom(|object| object.len() % 2 == 0),
+        )
+        .into();
+        assert_conforms(
+            graph,
+            schema,
+            &[("<http://example.org/alan>", &["KnowsEven"])],
+        )
+    }
+
+    #[test]
+    fn normalized_iris_test() -> Result<(), String> {
+        let content = concat!(
+            "<http://example.org/alan> <http://example.org/likes> <http://example.org/Caf%C3%A9> .\n",
+            "<http://example.org/bob> <http://example.org/likes> <http://example.org/Tea> .\n",
+        );
+
+        let graph = read_graph(content, &ImportOptions::default())?;
+        let constraint = TripleConstraint::new(
+            "CoffeeLover",
+            "<http://example.org/likes>",
+            NodeConstraint::Value("<http://example.org/Café>"),
+        );
+        let raw = PSchema::new(constraint.clone().into()).validate(graph.clone());
+        let normalized = PSchema::new(constraint.with_normalized_iris().into()).validate(graph);
+        match (raw, normalized) {
+            (Ok(raw), Ok(normalized)) if raw.height() == 0 && normalized.height() == 1 => Ok(()),
+            (Ok(raw), Ok(normalized)) => {
+                Err(format!("Unexpected subsets: {:?} {:?}", raw, normalized))
+            }
+            (Err(error), _) | (_, Err(error)) => Err(error.to_string()),
+        }
+    }
+
+    #[test]
+    fn any_lang_string_test() -> Result<(), String> {
+        let content = concat!(
+            "<http://example.org/alan> <http://example.org/label> \"Alan\"@en .\n",
+            "<http://example.org/alan> <http://example.org/label> \"Alan\"@de .\n",
+            "<http://example.org/bob> <http://example.org/label> \"bob@example.org\" .\n",
+        );
+
+        let graph = read_graph(content, &ImportOptions::default())?;
+        let schema: Shape<&str> = TripleConstraint::new(
+            "Labelled",
+            "<http://example.org/label>",
+            NodeConstraint::AnyLangString,
+        )
+        .into();
+        let subset = match PSchema::new(schema).validate(graph) {
+            Ok(subset) => subset,
+            Err(error) => return Err(error.to_string()),
+        };
+        match subset
+            .column(Column::Subject.as_ref())
+            .and_then(|subjects| subjects.cast(&DataType::String))
+        {
+            Ok(subjects)
+                if subjects.len() == 2
+                    && subjects
+                        .str()
+                        .unwrap()
+                        .into_iter()
+                        .all(|subject| subject == Some("<http://example.org/alan>")) =>
+            {
+                Ok(())
+            }
+            _ => Err(format!("Unexpected subset: {:?}", subset)),
+        }
+    }
+
+    #[test]
+    fn export_channel_test() -> Result<(), String> {
+        let content = concat!(
+            "<http://example.org/alan> <http://example.org/knows> <http://example.org/bob> .\n",
+            "<http://example.org/bob> <http://example.org/knows> <http://example.org/carol> .\n",
+            "<http://example.org/carol> <http://example.org/knows> <http://example.org/alan> .\n",
+        );
+        let edges = read(content, &ImportOptions::default())?;
+
+        let mut expected = Vec::new();
+        NTriples::export_writer(&mut expected, &mut edges.clone())?;
+        let expected = String::from_utf8_lossy(&expected).to_string();
+
+        let (sender, receiver) = std::sync::mpsc::sync_channel(1);
+        let producer =
+            std::thread::spawn(move || NTriples::export_channel(&mut edges.clone(), sender));
+        let actual = receiver
+            .iter()
+            .map(|triple| format!("{}\n", triple))
+            .collect::<String>();
+        match producer.join() {
+            Ok(Ok(())) if actual == expected => Ok(()),
+            Ok(Ok(())) => Err(format!("Unexpected triples: {}", actual)),
+            Ok(Err(error)) => Err(error),
+            Err(_) => Err(String::from("The producer panicked")),
+        }
+    }
+
+    #[test]
+    fn nquads_test() -> Result<(), String> {
+        let content = concat!(
+            "<http://www.wikidata.org/entity/Q80> <http://www.wikidata.org/prop/direct/P31> <http://www.wikidata.org/entity/Q5> .\n",
+            "<http://www.wikidata.org/entity/Q80> <http://www.wikidata.org/prop/direct/P19> <http://www.wikidata.org/entity/Q84> .\n",
+            "<http://www.wikidata.org/entity/Q80> <http://www.wikidata.org/prop/direct/P569> \"1955-06-08\"^^<http://www.w3.org/2001/XMLSchema#date> .\n",
+            "<http://www.wikidata.org/entity/Q92743> <http://www.wikidata.org/prop/direct/P31> <http://www.wikidata.org/entity/Q5> .\n",
+        );
+        let graph = read_graph(content, &ImportOptions::default())?;
+        let schema: Shape<&str> = ShapeAnd::new(
+            "Researcher",
+            vec![
+                TripleConstraint::new(
+                    "Human",
+                    "<http://www.wikidata.org/prop/direct/P31>",
+                    NodeConstraint::Value("<http://www.wikidata.org/entity/Q5>"),
+                )
+                .into(),
+                TripleConstraint::new(
+                    "London",
+                    "<http://www.wikidata.org/prop/direct/P19>",
+                    NodeConstraint::Value("<http://www.wikidata.org/entity/Q84>"),
+                )
+                .into(),
+            ],
+        )
+        .into();
+        let mut subset = match PSchema::new(schema).validate(graph) {
+            Ok(subset) => subset,
+            Err(error) => return Err(error.to_string()),
+        };
+
+        let mut quads = Vec::new();
+        NTriples::export_nquads_writer(&mut quads, &mut subset, Some("http://example.org/subset"))?;
+        let mut triples = Vec::new();
+        NTriples::export_nquads_writer(&mut triples, &mut subset, None)?;
+        let mut expected = Vec::new();
+        NTriples::export_writer(&mut expected, &mut subset)?;
+
+        let quads = String::from_utf8_lossy(&quads).to_string();
+        match (quads.lines().count(), triples == expected) {
+            (3, true)
+                if quads
+                    .lines()
+                    .all(|quad| quad.ends_with(" <http://example.org/subset> .")) =>
+            {
+                Ok(())
+            }
+            _ => Err(format!("Unexpected quads: {}", quads)),
+        }
+    }
+
+    #[test]
+    fn preserved_order_test() -> Result<(), String> {
+        let content = concat!(
+            "<http://example.org/zoe> <http://example.org/type> <http://example.org/Person> .\n",
+            "<http://example.org/alan> <http://example.org/type> <http://example.org/Person> .\n",
+            "<http://example.org/zoe> <http://example.org/name> \"Zoe\" .\n",
+            "<http://example.org/bob> <http://example.org/type> <http://example.org/Robot> .\n",
+            "<http://example.org/alan> <http://example.org/name> \"Alan\" .\n",
+        );
+
+        let graph = read_graph(content, &ImportOptions::default().with_preserved_order())?;
+        let schema: Shape<&str> = TripleConstraint::new(
+            "Person",
+            "<http://example.org/type>",
+            NodeConstraint::Value("<http://example.org/Person>"),
+        )
+        .into();
+        let mut subset = match PSchema::new(schema).validate(graph) {
+            Ok(subset) => subset,
+            Err(error) => return Err(error.to_string()),
+        };
+
+        let mut exported = Vec::new();
+        NTriples::export_writer(&mut exported, &mut subset)?;
+        let expected = content
+            .lines()
+            .filter(|triple| !triple.contains("bob"))
+            .map(|triple| format!("{}\n", triple))
+            .collect::<String>();
+        match String::from_utf8_lossy(&exported) == expected {
+            true => Ok(()),
+            false => Err(format!(
+                "Unexpected order: {}",
+                String::from_utf8_lossy(&exported)
+            )),
+        }
+    }
+
+    #[test]
+    fn gzip_test() -> Result<(), String> {
+        let mut edges = df![
+            Column::Subject.as_ref() => ["<http://example.org/alan>"],
+            Column::Predicate.as_ref() => ["<http://example.org/knows>"],
+            Column::Object.as_ref() => ["<http://example.org/bob>"],
+        ]
+        .map_err(|error| error.to_string())?;
+        let gz = temp_path("gzip.nt.gz");
+        let renamed = temp_path("gzip.bin");
+        NTriples::export(gz.to_str().unwrap(), &mut edges)?;
+        NTriples::export_gz(renamed.to_str().unwrap(), &mut edges)?;
+
+        let detected = NTriples::import(gz.to_str().unwrap())?;
+        let forced = NTriples::import_with_options(
+            renamed.to_str().unwrap(),
+            &ImportOptions::default().with_gzip(true),
+        )?;
+        match (detected.height(), forced.height()) {
+            (1, 1) => Ok(()),
+            heights => Err(format!("Unexpected number of edges: {:?}", heights)),
+        }
+    }
+
+    #[test]
+    fn blank_nodes_test() -> Result<(), String> {
+        let content = concat!(
+            "<http://example.org/alan> <http://example.org/address> _:b0 .\n",
+            "_:b0 <http://example.org/city> \"London\" .\n",
+            "_:b0 <http://example.org/zip> \"1\"^^<http://www.w3.org/2001/XMLSchema#integer> .\n",
+        );
+
+        let mut edges = read(content, &ImportOptions::default())?;
+        let mut buffer = Vec::new();
+        NTriples::export_writer(&mut buffer, &mut edges)?;
+        match String::from_utf8_lossy(&buffer) == content {
+            true => Ok(()),
+            false => Err(format!(
+                "Unexpected export: {}",
+                String::from_utf8_lossy(&buffer)
+            )),
+        }
+    }
+
+    #[test]
+    fn mmap_test() -> Result<(), String> {
+        let path = temp_path("mmap.nt");
+        let content = concat!(
+            "<http://example.org/alan> <http://example.org/knows> <http://example.org/bob> .\n",
+            "<http://example.org/bob> <http://example.org/knows> <http://example.org/carol> .\n",
+            "<http://example.org/carol> <http://example.org/name> \"Carol\"@en .\n",
+        );
+        if std::fs::write(&path, content).is_err() {
+            return Err(String::from("Error writing the N-Triples file"));
+        }
+
+        let buffered = NTriples::import(path.to_str().unwrap())?;
+        let mapped = NTriples::import_mmap(path.to_str().unwrap())?;
+        match (buffered.height(), mapped.height()) {
+            (3, 3) => Ok(()),
+            heights => Err(format!("Unexpected number of edges: {:?}", heights)),
+        }
+    }
+
+    #[test]
+    fn language_tagged_export_test() -> Result<(), String> {
+        let mut edges = df![
+            Column::Subject.as_ref() => ["<http://example.org/tim>", "<http://example.org/tim>"],
+            Column::Predicate.as_ref() => ["<http://example.org/label>", "<http://example.org/email>"],
+            Column::Object.as_ref() => ["\"Tim\"@en-GB", "\"tim@example.org\""],
+        ]
+        .map_err(|error| error.to_string())?;
+        let mut buffer = Vec::new();
+        NTriples::export_writer(&mut buffer, &mut edges)?;
+        let expected = concat!(
+            "<http://example.org/tim> <http://example.org/label> \"Tim\"@en-GB .\n",
+            "<http://example.org/tim> <http://example.org/email> \"tim@example.org\" .\n",
+        );
+        match String::from_utf8_lossy(&buffer) == expected {
+            true => Ok(()),
+            false => Err(format!(
+                "Unexpected export: {}",
+                String::from_utf8_lossy(&buffer)
+            )),
+        }
+    }
+
+    #[test]
+    fn datatypes_test() -> Result<(), String> {
+        let content = concat!(
+            "<http://example.org/alan> <http://example.org/age> \"41\"^^<http://www.w3.org/2001/XMLSchema#integer> .\n",
+            "<http://example.org/alan> <http://example.org/name> \"Alan\" .\n",
+            "<http://example.org/alan> <http://example.org/nick> \"Al\"@en .\n",
+            "<http://example.org/alan> <http://example.org/knows> <http://example.org/bob> .\n",
+        );
+        let (edges, _) = NTriples::read_from(
+            Cursor::new(content),
+            &ImportOptions::default().with_datatypes(),
+            None,
+            None,
+        )?;
+        let dtypes = match edges.column(Column::Custom("dtype").as_ref()) {
+            Ok(dtypes) => dtypes
+                .as_materialized_series()
+                .cast(&DataType::String)
+                .map_err(|error| error.to_string())?,
+            Err(_) => return Err(String::from("The dtype column is missing")),
+        };
+        let dtypes = dtypes
+            .str()
+            .map_err(|error| error.to_string())?
+            .into_iter()
+            .collect::<Vec<_>>();
+        match dtypes.as_slice() {
+            [Some("<http://www.w3.org/2001/XMLSchema#integer>"), Some("<http://www.w3.org/2001/XMLSchema#string>"), Some("<http://www.w3.org/1999/02/22-rdf-syntax-ns#langString>"), None] => {
+                Ok(())
+            }
+            _ => Err(format!("Unexpected datatypes: {:?}", dtypes)),
+        }
+    }
+
+    #[test]
+    fn object_kinds_test() -> Result<(), String> {
+        let content = concat!(
+            "<http://example.org/alan> <http://example.org/city> \"London\" .\n",
+            "<http://example.org/alan> <http://example.org/knows> <http://example.org/bob> .\n",
+        );
+
+        let options = ImportOptions::default()
+            .with_object_kinds()
+            .with_preserved_order();
+        let graph = read_graph(content, &options)?;
+        let schema: Shape<&str> =
+            TripleConstraint::new("City", "<http://example.org/city>", NodeConstraint::Any).into();
+        let subset = match PSchema::new(schema).validate(graph) {
+            Ok(subset) => subset,
+            Err(error) => return Err(error.to_string()),
+        };
+        // The quotes of the literal are dropped, so only its kind tells it apart from an IRI.
+        let mut subset = subset
+            .lazy()
+            .with_column(
+                col(Column::Object.as_ref())
+                    .cast(DataType::String)
+                    .str()
+                    .strip_chars(lit("\"")),
+            )
+            .collect()
+            .map_err(|error| error.to_string())?;
+        let mut buffer = Vec::new();
+        NTriples::export_writer(&mut buffer, &mut subset)?;
+        match String::from_utf8_lossy(&buffer) == content {
+            true => Ok(()),
+            false => Err(format!(
+                "Unexpected export: {}",
+                String::from_utf8_lossy(&buffer)
+            )),
+        }
+    }
+
+    #[test]
+    fn strict_test() -> Result<(), String> {
+        let content = concat!(
+            "<http://example.org/alan> <http://example.org/knows> <http://example.org/bob> .\n",
+            "<http://example.org/bob> <http://example.org/knows> .\n",
+            "<http://example.org/carol> <http://example.org/knows> <http://example.org/alan> .\n",
+            "<http://example.org/dave> knows <http://example.org/alan> .\n",
+        );
+
+        let strict = |max_errors: usize| {
+            let options = ImportOptions::default();
+            NTriples::read_from(Cursor::new(content), &options, None, Some(max_errors))
+        };
+        let (edges, errors) = strict(2)?;
+        let lines = errors.iter().map(|error| error.line).collect::<Vec<_>>();
+        match (edges.height(), lines, strict(1)) {
+            (2, lines, Err(_)) if lines == vec![Some(2), Some(4)] => Ok(()),
+            (height, lines, _) => Err(format!(
+                "Unexpected import: {} edges, errors at {:?}",
+                height, lines
+            )),
+        }
+    }
+
+    #[test]
+    fn interned_test() -> Result<(), String> {
+        let (edges, dictionary) = NTriples::import_interned("./examples/paper/paper.nt")?;
+        let code = |term: &str| match dictionary.code(term) {
+            Some(code) => Ok(code),
+            None => Err(format!("{} was not interned", term)),
+        };
+        let schema: Shape<u32> = TripleConstraint::new(
+            "Human",
+            code("<http://example.org/instanceOf>")?,
+            NodeConstraint::Value(code("<http://example.org/Human>")?),
+        )
+        .into();
+        let graph = match GraphFrame::from_edges(edges) {
+            Ok(graph) => graph,
+            Err(_) => return Err(String::from("Error creating the GraphFrame from edges")),
+        };
+        let subset = match PSchema::new(schema)
+            .validate(graph)
+            .map_err(|error| error.to_string())
+            .and_then(|subset| {
+                dictionary
+                    .decode(&subset)
+                    .map_err(|error| error.to_string())
+            }) {
+            Ok(subset) => subset,
+            Err(error) => return Err(error),
+        };
+        let mut subjects = match subset.column(Column::Subject.as_ref()) {
+            Ok(subjects) => subjects.str().unwrap().into_iter().collect::<Vec<_>>(),
+            Err(error) => return Err(error.to_string()),
+        };
+        subjects.dedup();
+        match subjects == vec![Some("<http://example.org/alan>")] {
+            true => Ok(()),
+            false => Err(format!("Unexpected subjects: {:?}", subjects)),
+        }
+    }
+
+    #[test]
+    fn string_facets_test() -> Result<(), String> {
+        let edges = crate::backends::memory::Memory::from_triples([
+            (
+                "<http://example.org/alan>",
+                "<http://example.org/name>",
+                "\"Alan Turing\"@en",
+            ),
+            (
+                "<http://example.org/bob>",
+                "<http://example.org/name>",
+                "\"B\"",
+            ),
+            (
+                "<http://example.org/carol>",
+                "<http://example.org/name>",
+                "\"12345\"^^<http://www.w3.org/2001/XMLSchema#string>",
+            ),
+        ])?;
+        let graph = match GraphFrame::from_edges(edges) {
+            Ok(graph) => graph,
+            Err(_) => return Err(String::from("Error creating the GraphFrame from edges")),
+        };
+        if TripleConstraint::new("Named", "<http://example.org/name>", NodeConstraint::Any)
+            .with_pattern("[A-Z")
+            .is_ok()
+        {
+            return Err(String::from(
+                "The invalid pattern should have been rejected",
+            ));
+        }
+        let schema: Shape<&str> =
+            TripleConstraint::new("Named", "<http://example.org/name>", NodeConstraint::Any)
+                .with_pattern("^[A-Z]")?
+                .with_min_length(2)
+                .with_max_length(20)
+                .into();
+        let subset = match PSchema::new(schema).validate(graph) {
+            Ok(subset) => subset,
+            Err(error) => return Err(error.to_string()),
+        };
+        let subjects = match subset
+            .column(Column::Subject.as_ref())
+            .and_then(|subjects| subjects.cast(&DataType::String))
+        {
+            Ok(subjects) => subjects
+                .str()
+                .unwrap()
+                .into_iter()
+                .flatten()
+                .map(String::from)
+                .collect::<Vec<_>>(),
+            Err(error) => return Err(error.to_string()),
+        };
+        match subjects == vec!["<http://example.org/alan>"] {
+            true => Ok(()),
+            false => Err(format!("Unexpected subjects: {:?}", subjects)),
+        }
+    }
+
+    #[test]
+    fn numeric_facet_test() -> Result<(), String> {
+        let population = "<http://www.wikidata.org/prop/direct/P1082>";
+        let edges = crate::backends::memory::Memory::from_triples([
+            (
+                "<http://example.org/london>",
+                population,
+                "\"+8799800\"^^<http://www.w3.org/2001/XMLSchema#decimal>",
+            ),
+            (
+                "<http://example.org/wilmslow>",
+                population,
+                "\"24497\"^^<http://www.w3.org/2001/XMLSchema#integer>",
+            ),
+            ("<http://example.org/nowhere>", population, "\"unknown\""),
+            // Numbers written as plain strings are not numeric literals.
+            ("<http://example.org/manchester>", population, "\"2705000\""),
+        ])?;
+        let graph = match GraphFrame::from_edges(edges) {
+            Ok(graph) => graph,
+            Err(_) => return Err(String::from("Error creating the GraphFrame from edges")),
+        };
+        let schema: Shape<&str> =
+            NumericFacet::new("BigCity", population, Some(Bound::Exclusive(1e6)), None).into();
+        let subset = match PSchema::new(schema).validate(graph) {
+            Ok(subset) => subset,
+            Err(error) => return Err(error.to_string()),
+        };
+        let subjects = match subset
+            .column(Column::Subject.as_ref())
+            .and_then(|subjects| subjects.cast(&DataType::String))
+        {
+            Ok(subjects) => subjects
+                .str()
+                .unwrap()
+                .into_iter()
+                .flatten()
+                .map(String::from)
+                .collect::<Vec<_>>(),
+            Err(error) => return Err(error.to_string()),
+        };
+        match subjects == vec!["<http://example.org/london>"] {
+            true => Ok(()),
+            false => Err(format!("Unexpected subjects: {:?}", subjects)),
         }
     }
 }
@@ -1,15 +1,67 @@
 use std::fs::File;
+use std::io::Write;
 
 use polars::prelude::*;
+use pregel_rs::pregel::Column;
 
 use super::Backend;
+use crate::utils::check::check_string_cache;
 
 pub struct Parquet;
 
 /// The `Parquet` block defines a Rust module that contains `import` and `export`.
 impl Backend for Parquet {
-    fn import(_path: &str) -> Result<DataFrame, String> {
-        todo!()
+    /// This function reads the edges stored in a Parquet file, such as those
+    /// written by `export`. The `subject`, `predicate` and `object` columns must
+    /// be present; those holding strings are cast to `Categorical`, as produced
+    /// by `NTriples::import`, so the edges can be validated right away. The
+    /// integer ids, as produced by `DuckDB::import`, are left untouched.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The path to the Parquet file.
+    ///
+    /// Returns:
+    ///
+    /// This function returns a `Result<DataFrame, String>`, where the `String`
+    /// is an error message in case the file cannot be read or any of the
+    /// required columns is missing.
+    fn import(path: &str) -> Result<DataFrame, String> {
+        // The cache must be in place before reading, as the categorical columns of the file are
+        // read into the global string cache too, so they can be compared with the labels.
+        check_string_cache().map_err(|error| error.to_string())?;
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(_) => return Err(String::from("Cannot open the Parquet file")),
+        };
+        let mut edges = match ParquetReader::new(file).finish() {
+            Ok(edges) => edges,
+            Err(error) => return Err(format!("Error reading the Parquet file: {}", error)),
+        };
+
+        let categorical = DataType::Categorical(None, CategoricalOrdering::Lexical);
+        for column in [Column::Subject, Column::Predicate, Column::Object] {
+            let series = match edges.column(column.as_ref()) {
+                Ok(series) => series.as_materialized_series().clone(),
+                Err(_) => {
+                    return Err(format!(
+                        "The Parquet file has no {} column",
+                        column.as_ref()
+                    ))
+                }
+            };
+            if series.dtype() == &DataType::String {
+                match series.cast(&categorical) {
+                    Ok(series) => {
+                        if edges.with_column(series).is_err() {
+                            return Err(String::from("Error casting the edges"));
+                        }
+                    }
+                    Err(_) => return Err(String::from("Error casting the edges")),
+                }
+            }
+        }
+        Ok(edges)
     }
 
     fn export(path: &str, df: &mut DataFrame) -> Result<(), String> {
@@ -18,9 +70,89 @@ impl Backend for Parquet {
             Err(_) => return Err(String::from("Error creating the Parquet file")),
         };
 
-        match ParquetWriter::new(buffer).finish(df) {
+        Self::export_writer(buffer, df)
+    }
+
+    /// This function serializes a `DataFrame` as Parquet into any `Write`
+    /// implementor, such as a file or an in-memory buffer.
+    fn export_writer<W: Write>(writer: W, df: &mut DataFrame) -> Result<(), String> {
+        match ParquetWriter::new(writer).finish(df) {
             Ok(_) => Ok(()),
             Err(_) => Err(String::from("Error writing to the Parquet file")),
         }
     }
 }
+
+impl Parquet {
+    /// This function forwards to `Backend::export_writer`, so the callers that
+    /// do not have the `Backend` trait in scope can still serialize the edges
+    /// into any `Write` implementor.
+    pub fn export_writer<W: Write>(writer: W, df: &mut DataFrame) -> Result<(), String> {
+        <Self as Backend>::export_writer(writer, df)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use polars::df;
+    use pregel_rs::graph_frame::GraphFrame;
+    use pregel_rs::pregel::Column;
+
+    use super::Parquet;
+    use crate::backends::ntriples::NTriples;
+    use crate::backends::Backend;
+    use crate::pschema::PSchema;
+    use crate::shape::shex::{NodeConstraint, Shape, TripleConstraint};
+
+    #[test]
+    fn round_trip_test() -> Result<(), String> {
+        let path = std::env::temp_dir().join("pschema-round-trip.nt");
+        let content = concat!(
+            "<http://example.org/alan> <http://example.org/type> <http://example.org/Person> .\n",
+            "<http://example.org/bob> <http://example.org/type> <http://example.org/Robot> .\n",
+        );
+        if std::fs::write(&path, content).is_err() {
+            return Err(String::from("Error writing the N-Triples file"));
+        }
+        let exported = std::env::temp_dir().join("pschema-round-trip.parquet");
+        Parquet::export(
+            exported.to_str().unwrap(),
+            &mut NTriples::import(path.to_str().unwrap())?,
+        )?;
+
+        let graph = match GraphFrame::from_edges(Parquet::import(exported.to_str().unwrap())?) {
+            Ok(graph) => graph,
+            Err(_) => return Err(String::from("Error creating the GraphFrame from edges")),
+        };
+        let schema: Shape<&str> = TripleConstraint::new(
+            "Person",
+            "<http://example.org/type>",
+            NodeConstraint::Value("<http://example.org/Person>"),
+        )
+        .into();
+        match PSchema::new(schema).validate(graph) {
+            Ok(subset) if subset.height() == 1 => Ok(()),
+            Ok(subset) => Err(format!("Unexpected subset: {:?}", subset)),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    #[test]
+    fn missing_column_test() -> Result<(), String> {
+        let mut edges = match df![
+            Column::Subject.as_ref() => ["<http://example.org/alan>"],
+            Column::Object.as_ref() => ["<http://example.org/Person>"],
+        ] {
+            Ok(edges) => edges,
+            Err(_) => return Err(String::from("Error creating the edges DataFrame")),
+        };
+        let path = std::env::temp_dir().join("pschema-missing-column.parquet");
+        Parquet::export(path.to_str().unwrap(), &mut edges)?;
+
+        match Parquet::import(path.to_str().unwrap()) {
+            Ok(_) => Err(String::from("The predicate column should be missing")),
+            Err(error) if error.contains(Column::Predicate.as_ref()) => Ok(()),
+            Err(error) => Err(error),
+        }
+    }
+}
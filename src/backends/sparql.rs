@@ -0,0 +1,216 @@
+use std::io::BufReader;
+use std::time::Duration;
+
+use polars::prelude::*;
+
+use super::ntriples::{ImportOptions, NTriples};
+
+/// The `Sparql` struct loads the edges from a remote SPARQL endpoint by means
+/// of `CONSTRUCT` queries, so a slice of a triplestore can be validated without
+/// dumping it to disk. As the endpoint and the query do not fit the path taken
+/// by `Backend::import`, it does not implement the trait.
+///
+/// Properties:
+///
+/// * `endpoint`: the URL of the SPARQL endpoint.
+/// * `timeout`: the maximum duration of the whole request, if any.
+/// * `headers`: the additional headers sent along with the request, such as
+/// the `Authorization` one.
+/// * `max_errors`: the maximum number of malformed triples tolerated in the
+/// response, which are skipped.
+#[derive(Clone, Debug)]
+pub struct Sparql {
+    endpoint: String,
+    timeout: Option<Duration>,
+    headers: Vec<(String, String)>,
+    max_errors: usize,
+}
+
+impl Sparql {
+    pub fn new(endpoint: &str) -> Self {
+        Self {
+            endpoint: endpoint.to_string(),
+            timeout: None,
+            headers: Vec::new(),
+            max_errors: 0,
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// This function makes the import skip up to the provided number of
+    /// malformed triples in the response, instead of failing on the first one.
+    ///
+    /// Arguments:
+    ///
+    /// * `max_errors`: The maximum number of malformed triples tolerated.
+    pub fn with_max_errors(mut self, max_errors: usize) -> Self {
+        self.max_errors = max_errors;
+        self
+    }
+
+    /// This function runs a `CONSTRUCT` query against the endpoint and imports
+    /// the resulting triples, as `NTriples::import` does. The response is
+    /// requested as N-Triples and parsed as Turtle, which N-Triples is a subset
+    /// of, so endpoints answering with Turtle are supported as well; relative
+    /// IRIs are resolved against the URL of the endpoint.
+    ///
+    /// Arguments:
+    ///
+    /// * `query`: The `CONSTRUCT` query to be run.
+    ///
+    /// Returns:
+    ///
+    /// This function returns a `Result<DataFrame, String>`, where the `String`
+    /// is an error message in case the request fails, the endpoint answers
+    /// with an error or the response has more malformed triples than tolerated.
+    pub fn construct(&self, query: &str) -> Result<DataFrame, String> {
+        let mut agent = ureq::AgentBuilder::new();
+        if let Some(timeout) = self.timeout {
+            agent = agent.timeout(timeout);
+        }
+        let request = self.headers.iter().fold(
+            agent
+                .build()
+                .post(&self.endpoint)
+                .set("Accept", "application/n-triples, text/turtle;q=0.9"),
+            |request, (name, value)| request.set(name, value),
+        );
+        let response = match request.send_form(&[("query", query)]) {
+            Ok(response) => response,
+            Err(ureq::Error::Status(status, response)) => {
+                return Err(format!(
+                    "The endpoint answered with {}: {}",
+                    status,
+                    response.into_string().unwrap_or_default()
+                ))
+            }
+            Err(error) => return Err(format!("Error querying the endpoint: {}", error)),
+        };
+        NTriples::read_from(
+            BufReader::new(response.into_reader()),
+            &ImportOptions::default().with_base_iri(&self.endpoint),
+            None,
+            Some(self.max_errors),
+        )
+        .map(|(edges, _)| edges)
+    }
+
+    /// This function runs a `CONSTRUCT` query against the provided endpoint
+    /// with no timeout nor additional headers, as described in `construct`.
+    pub fn import_query(endpoint: &str, query: &str) -> Result<DataFrame, String> {
+        Self::new(endpoint).construct(query)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+    use std::time::Duration;
+
+    use super::Sparql;
+
+    /// This function serves a single request with the provided status and body,
+    /// returning the URL of the endpoint and the handle of the thread serving
+    /// it, which yields the headers of the request.
+    fn serve(status: &'static str, body: &'static str) -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/sparql", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut headers = String::new();
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap_or_default();
+                }
+                if line.trim().is_empty() {
+                    break;
+                }
+                headers.push_str(&line);
+            }
+            reader.read_exact(&mut vec![0; length]).unwrap();
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/n-triples\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            reader.get_mut().write_all(response.as_bytes()).unwrap();
+            headers
+        });
+        (endpoint, handle)
+    }
+
+    #[test]
+    fn construct_test() -> Result<(), String> {
+        let (endpoint, handle) = serve(
+            "200 OK",
+            concat!(
+                "<http://example.org/alan> <http://example.org/knows> <http://example.org/bob> .\n",
+                "<http://example.org/bob> <http://example.org/knows> <http://example.org/alan> .\n",
+            ),
+        );
+        let edges = Sparql::new(&endpoint)
+            .with_timeout(Duration::from_secs(10))
+            .with_header("Authorization", "Bearer secret")
+            .construct("CONSTRUCT WHERE { ?s ?p ?o }")?;
+        let headers = handle
+            .join()
+            .map_err(|_| String::from("The server panicked"))?;
+        match (edges.height(), headers.contains("Bearer secret")) {
+            (2, true) => Ok(()),
+            _ => Err(format!(
+                "Unexpected request {} or edges {:?}",
+                headers, edges
+            )),
+        }
+    }
+
+    #[test]
+    fn malformed_response_test() -> Result<(), String> {
+        let body = concat!(
+            "<http://example.org/alan> <http://example.org/knows> <http://example.org/bob> .\n",
+            "<http://example.org/bob> <http://example.org/knows> .\n",
+        );
+        let (endpoint, handle) = serve("200 OK", body);
+        let result = Sparql::import_query(&endpoint, "CONSTRUCT WHERE { ?s ?p ?o }");
+        let _ = handle.join();
+        if let Ok(edges) = result {
+            return Err(format!("The malformed triple was dropped: {:?}", edges));
+        }
+        let (endpoint, handle) = serve("200 OK", body);
+        let edges = Sparql::new(&endpoint)
+            .with_max_errors(1)
+            .construct("CONSTRUCT WHERE { ?s ?p ?o }")?;
+        let _ = handle.join();
+        match edges.height() {
+            1 => Ok(()),
+            _ => Err(format!("Unexpected edges {:?}", edges)),
+        }
+    }
+
+    #[test]
+    fn endpoint_error_test() -> Result<(), String> {
+        let (endpoint, handle) = serve("400 Bad Request", "Malformed query");
+        let result = Sparql::import_query(&endpoint, "CONSTRUCT");
+        let _ = handle.join();
+        match result {
+            Err(error) if error.contains("Malformed query") => Ok(()),
+            result => Err(format!("Unexpected result: {:?}", result)),
+        }
+    }
+}
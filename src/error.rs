@@ -0,0 +1,84 @@
+use polars::error::PolarsError;
+
+use crate::shape::shex::ShapeError;
+
+/// The `PSchemaError` enum gathers the errors the validation may result in, so
+/// the public API of the crate does not depend on the error type of a specific
+/// version of Polars. The errors raised by Polars are still available through
+/// the `Polars` variant for those who need the details.
+#[derive(Debug)]
+pub enum PSchemaError {
+    /// An error raised by Polars while processing the graph.
+    Polars(PolarsError),
+    /// The schema is not valid.
+    Schema(ShapeError),
+    /// The graph cannot be validated, e.g. because it has no edges.
+    Graph(String),
+    /// The graph lacks the column with the given name.
+    MissingColumn(String),
+    /// An error raised while writing the outputs of the validation.
+    Io(std::io::Error),
+    /// The validation did not finish within the given duration.
+    Timeout(std::time::Duration),
+    /// The labels kept on changing after the given number of supersteps.
+    NotConverged(u8),
+}
+
+impl std::fmt::Display for PSchemaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PSchemaError::Polars(error) => write!(f, "{}", error),
+            PSchemaError::Schema(error) => write!(f, "Invalid schema: {}", error),
+            PSchemaError::Graph(error) => write!(f, "Invalid graph: {}", error),
+            PSchemaError::MissingColumn(column) => {
+                write!(f, "The graph has no {} column", column)
+            }
+            PSchemaError::Io(error) => write!(f, "{}", error),
+            PSchemaError::Timeout(timeout) => {
+                write!(f, "The validation did not finish within {:?}", timeout)
+            }
+            PSchemaError::NotConverged(iterations) => write!(
+                f,
+                "The labels did not converge within {} iterations",
+                iterations
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PSchemaError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PSchemaError::Polars(error) => Some(error),
+            PSchemaError::Schema(error) => Some(error),
+            PSchemaError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// The missing columns and the empty graphs are reported by Polars as such, so
+/// they are turned into the corresponding variants.
+impl From<PolarsError> for PSchemaError {
+    fn from(error: PolarsError) -> Self {
+        match error {
+            PolarsError::SchemaFieldNotFound(column) => {
+                PSchemaError::MissingColumn(column.to_string())
+            }
+            PolarsError::NoData(message) => PSchemaError::Graph(message.to_string()),
+            error => PSchemaError::Polars(error),
+        }
+    }
+}
+
+impl From<ShapeError> for PSchemaError {
+    fn from(error: ShapeError) -> Self {
+        PSchemaError::Schema(error)
+    }
+}
+
+impl From<std::io::Error> for PSchemaError {
+    fn from(error: std::io::Error) -> Self {
+        PSchemaError::Io(error)
+    }
+}
@@ -3,6 +3,12 @@
 /// contains code related to different backends or databases that the program
 /// can use to store and retrieve data.
 pub mod backends;
+/// `pub mod error;` is creating a public module named `error`. This module
+/// contains the errors the validation may result in.
+pub mod error;
+/// `pub mod prelude;` is creating a public module named `prelude`. This module
+/// re-exports the most common types, so they can be imported at once.
+pub mod prelude;
 /// `pub mod pschema;` is creating a public module named `pschema`. This module
 /// contains code related to creating knowledge graphs from Wikibase data.
 pub mod pschema;
@@ -10,6 +16,9 @@ pub mod pschema;
 /// contains code related to defining and manipulating shapes or structures of data
 /// in the codebase.
 pub mod shape;
+/// `pub mod test_support;` is creating a public module named `test_support`.
+/// This module contains utilities for testing schemas against graphs.
+pub mod test_support;
 /// `pub mod utils;` is creating a public module named `utils`. This module contains
 /// utility functions and helper code that can be used throughout the codebase.
 pub mod utils;
@@ -0,0 +1,36 @@
+//! The `prelude` re-exports the types most commonly needed for defining and
+//! running a validation, so they can all be brought into scope at once.
+//!
+//! ```
+//! use pschema_rs::prelude::*;
+//!
+//! let schema: Shape<&str> = ShapeAnd::new(
+//!     "Researcher",
+//!     vec![
+//!         TripleConstraint::new("Human", "P31", NodeConstraint::Value("Q5")).into(),
+//!         Cardinality::new(
+//!             "Awards",
+//!             TripleConstraint::new("Award", "P166", NodeConstraint::Any).into(),
+//!             Bound::Zero,
+//!             Bound::Many,
+//!         )
+//!         .into(),
+//!     ],
+//! )
+//! .into();
+//! let _pschema = PSchema::new(schema);
+//! ```
+
+pub use crate::backends::csv::Csv;
+pub use crate::backends::duckdb::DuckDB;
+pub use crate::backends::memory::Memory;
+pub use crate::backends::ntriples::NTriples;
+pub use crate::backends::parquet::Parquet;
+pub use crate::backends::sparql::Sparql;
+pub use crate::backends::Backend;
+pub use crate::error::PSchemaError;
+pub use crate::pschema::PSchema;
+pub use crate::shape::shex::{
+    Bound, Cardinality, IncomingReference, NodeConstraint, NumericFacet, Shape, ShapeAnd, ShapeNot,
+    ShapeOr, ShapeReference, SubclassConstraint, TripleConstraint,
+};
@@ -1,21 +1,113 @@
+use crate::backends::Backend;
+use crate::error::PSchemaError;
 use crate::shape::shape_tree::{ShapeTree, ShapeTreeItem};
-use crate::shape::shex::{Shape, Validate};
-use crate::utils::check::check_field;
+use crate::shape::shex::{is_in_values, Labels, Shape, ShapeError, Validate};
+use crate::utils::cache::Cache;
+use crate::utils::check::{check_field, check_predicate_dtype, check_string_cache};
+use crate::utils::components::components;
+use crate::utils::glob;
+use crate::utils::reification::flatten_statements;
+use crate::utils::same_as::merge_same_as;
+use crate::utils::symbol_table::{Label, SymbolTable};
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
 
-use polars::enable_string_cache;
 use polars::prelude::*;
 use pregel_rs::graph_frame::GraphFrame;
 use pregel_rs::pregel::{Column, MessageReceiver, PregelBuilder};
+use rayon::prelude::*;
+use wikidata_rs::dtype::DataType as WikidataType;
+use wikidata_rs::id::Id;
+
+/// `Program` is a closure building a custom expression run by the Pregel algorithm.
+pub type Program = Arc<dyn Fn() -> Expr + Send + Sync>;
 
-/// The `PSchema` struct has a single field `start` of type `Shape`.
+/// The `PSchema` struct holds the starting shape of the schema together with
+/// the options that tune the validation.
 ///
 /// Properties:
 ///
 /// * `start`: `start` is a property of the `PSchema` struct which is of type
 /// `Shape`. It represents the starting shape of a particular schema or data
 /// structure.
+/// * `cache`: an optional on-disk `Cache` of previous validations, together
+/// with the function computing their keys.
+/// * `aggregate_messages`: the closure building the expression that aggregates
+/// the messages received by each vertex, if a custom one is provided.
+/// * `v_prog`: the closure building the expression that updates the vertices,
+/// if a custom one is provided.
+/// * `reified_statements`: whether the reified Wikibase statements are to be
+/// flattened into direct edges before the validation.
+/// * `message_dtype`: the datatype of the messages sent among the vertices.
+/// * `self_loops`: whether the edges whose subject and object are the same are
+/// to be considered during the validation.
+/// * `same_as`: the predicate linking the aliases of the same entity, if they
+/// are to be merged before the validation.
+/// * `max_edges`: the maximum number of edges the graph can have, if any.
+/// * `superstep_timeout`: the maximum duration of each of the supersteps, if any.
+/// * `timeout`: the maximum duration of the whole validation, if any.
+/// * `convergence_check`: whether the labels are checked to be stable once the
+/// supersteps are over.
+/// * `branch_labels`: whether the labels of the matched branches of the
+/// `ShapeOr` shapes are kept alongside theirs.
+/// * `messages`: the messages of the supersteps once compiled, which are shared
+/// among the clones of the `PSchema` with the same schema and message datatype.
+#[derive(Clone)]
 pub struct PSchema<T: Literal + Clone> {
     start: Shape<T>,
+    cache: Option<(Cache, CacheKey<T>)>,
+    aggregate_messages: Option<Program>,
+    v_prog: Option<Program>,
+    reified_statements: bool,
+    message_dtype: DataType,
+    self_loops: bool,
+    same_as: Option<T>,
+    max_edges: Option<usize>,
+    superstep_timeout: Option<Duration>,
+    timeout: Option<(Duration, Timer<T>)>,
+    convergence_check: bool,
+    branch_labels: bool,
+    messages: Arc<OnceLock<Messages>>,
+}
+
+/// `CacheKey` is the type of the functions keying the cached validations, which
+/// is set by `with_cache`, so only the schemas being cached require their values
+/// to be `Debug` and `Hash`.
+type CacheKey<T> = fn(&PSchema<T>, &GraphFrame) -> PolarsResult<u64>;
+
+/// `Validation` is the type of the functions validating a graph, which are run
+/// by `timed` within the timeout of the schema, if any.
+type Validation<T> = fn(PSchema<T>, GraphFrame) -> Result<DataFrame, PSchemaError>;
+
+/// `Timer` is the type of the functions running a validation on a separate
+/// thread within the given duration, which is set by `with_timeout`, so only
+/// the schemas being time-boxed require their values to be `Send + 'static`.
+type Timer<T> =
+    fn(PSchema<T>, GraphFrame, Validation<T>, Duration) -> Result<DataFrame, PSchemaError>;
+
+/// `Messages` holds the messages sent to the subjects and to the objects of the
+/// edges, respectively, in the order of the supersteps.
+type Messages = Arc<(Vec<Expr>, Vec<Expr>)>;
+
+/// The `ReferenceStats` struct reports how a reference shape was resolved
+/// during a validation, which helps diagnosing references matching nothing.
+///
+/// Properties:
+///
+/// * `label`: the label of the `ShapeReference` or `IncomingReference`.
+/// * `candidates`: the number of edges labelled with the predicate of the
+/// reference, that is, those that could be followed.
+/// * `resolved`: the number of candidate edges whose target conforms to the
+/// referenced shape, i.e., carries its label.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReferenceStats {
+    pub label: &'static str,
+    pub candidates: usize,
+    pub resolved: usize,
 }
 
 /// This code implements a Pregel algorithm for graph processing using the
@@ -41,7 +133,192 @@ impl<T: Literal + Clone> PSchema<T> {
     /// A new instance of the `PSchema` struct with the `start` field set to the `start`
     /// parameter passed to the `new` function.
     pub fn new(start: Shape<T>) -> PSchema<T> {
-        Self { start }
+        Self {
+            start,
+            cache: None,
+            aggregate_messages: None,
+            v_prog: None,
+            reified_statements: false,
+            message_dtype: DataType::Categorical(None, CategoricalOrdering::Lexical),
+            self_loops: true,
+            same_as: None,
+            max_edges: None,
+            superstep_timeout: None,
+            timeout: None,
+            convergence_check: false,
+            branch_labels: false,
+            messages: Arc::default(),
+        }
+    }
+
+    /// This function sets the datatype of the messages sent during the Pregel
+    /// supersteps, which defaults to a lexically ordered `Categorical`. Both
+    /// `Categorical` and `String` messages hold the labels of the shapes as they
+    /// are, whereas `UInt16`, `UInt32`, `UInt64`, `Int32` and `Int64` messages
+    /// hold integer codes standing for the labels, which take less memory. The
+    /// codes are turned back into the labels once the supersteps are over, so
+    /// the `labels` column of the result holds `String`s in that case. Any other
+    /// datatype results in an error when validating.
+    ///
+    /// Arguments:
+    ///
+    /// * `dtype`: The `DataType` of the messages.
+    pub fn with_message_dtype(mut self, dtype: DataType) -> Self {
+        self.message_dtype = dtype;
+        self.messages = Arc::default();
+        self
+    }
+
+    /// This function enables the traversal of reified Wikibase statements. When
+    /// set, every `p:Pn` edge pointing to a statement node whose `ps:Pn` edge
+    /// points to a value is flattened into a direct `wdt:Pn` edge, so schemas
+    /// written for the truthy form of the data also match the full statements.
+    /// Only graphs whose columns hold IRIs are supported.
+    pub fn with_reified_statements(mut self) -> Self {
+        self.reified_statements = true;
+        self
+    }
+
+    /// This function enables caching the results of the validation on disk.
+    /// The entries are keyed by a hash of the schema, the options of the
+    /// validation and the edges of the graph, so the cached subset is only
+    /// returned when all of them match. As closures cannot be told apart, the
+    /// validations of schemas holding a `NodeConstraint::Custom`, as well as
+    /// those running custom vertex programs or aggregates, result in an error.
+    ///
+    /// Arguments:
+    ///
+    /// * `cache`: The `Cache` where the results are to be stored.
+    pub fn with_cache(mut self, cache: Cache) -> Self
+    where
+        T: Debug + Hash,
+    {
+        self.cache = Some((cache, Self::cache_key));
+        self
+    }
+
+    /// This function replaces the vertex program run by the Pregel algorithm,
+    /// which by default sets the labels of the vertices to the aggregated
+    /// messages. This is meant for experimentation, as the validation relies
+    /// on the `labels` column holding the list of labels of each vertex.
+    ///
+    /// Arguments:
+    ///
+    /// * `v_prog`: A closure returning the Polars expression of the vertex program.
+    /// It may capture any state, such as thresholds or labels.
+    pub fn with_custom_vprog(mut self, v_prog: impl Fn() -> Expr + Send + Sync + 'static) -> Self {
+        self.v_prog = Some(Arc::new(v_prog));
+        self
+    }
+
+    /// This function replaces the expression used for aggregating the messages
+    /// received by each of the vertices, which by default drops the NULL ones.
+    ///
+    /// Arguments:
+    ///
+    /// * `aggregate_messages`: A closure returning the Polars expression that
+    /// aggregates the messages. It may capture any state, as `with_custom_vprog`.
+    pub fn with_custom_aggregate(
+        mut self,
+        aggregate_messages: impl Fn() -> Expr + Send + Sync + 'static,
+    ) -> Self {
+        self.aggregate_messages = Some(Arc::new(aggregate_messages));
+        self
+    }
+
+    /// This function discards the self-loops of the graph, that is, the edges
+    /// whose subject and object are the same, before running the validation.
+    /// Otherwise, a node could satisfy a `ShapeReference` by referencing itself.
+    /// Note that this also prevents any shape from matching a self-loop, so it
+    /// should not be enabled when those edges are meaningful for the schema.
+    pub fn without_self_loops(mut self) -> Self {
+        self.self_loops = false;
+        self
+    }
+
+    /// This function merges the entities described under several IRIs before
+    /// running the validation. The subjects and objects linked by the provided
+    /// predicate, such as `owl:sameAs`, are rewritten to a single representative
+    /// of their equivalence class, so the conformance is computed on the merged
+    /// entities. Hence, the resulting subset refers to the representatives only.
+    ///
+    /// Arguments:
+    ///
+    /// * `predicate`: The predicate linking the aliases of the same entity.
+    pub fn with_sameas_merge(mut self, predicate: T) -> Self {
+        self.same_as = Some(predicate);
+        self
+    }
+
+    /// This function sets the maximum number of edges the graph can have, so the
+    /// validation fails fast with an error instead of running out of memory in
+    /// the middle of the Pregel supersteps when a dump is too large. The edges
+    /// are counted as provided, before any transformation of the graph.
+    ///
+    /// Arguments:
+    ///
+    /// * `max_edges`: The maximum number of edges of the graph to be validated.
+    pub fn with_max_edges(mut self, max_edges: usize) -> Self {
+        self.max_edges = Some(max_edges);
+        self
+    }
+
+    /// This function time-boxes each of the Pregel supersteps, so the validation
+    /// fails with `PSchemaError::Timeout` instead of running for too long on
+    /// adversarial graphs. The clock is reset at the start of every superstep.
+    /// Note that Polars computations cannot be interrupted in the middle of an
+    /// expression, so the duration of each superstep is checked when the next
+    /// one starts and once the algorithm finishes. Once a superstep exceeds the
+    /// budget, the remaining ones send no messages, so they end quickly.
+    ///
+    /// Arguments:
+    ///
+    /// * `timeout`: The maximum `Duration` of each superstep.
+    pub fn with_superstep_timeout(mut self, timeout: Duration) -> Self {
+        self.superstep_timeout = Some(timeout);
+        self
+    }
+
+    /// This function time-boxes the whole validation, which is then run on a
+    /// separate thread, so every validating method returns a timeout error once
+    /// the deadline passes instead of blocking the caller. The methods running
+    /// several validations, such as `validate_grouped` or `validate_dir`, are
+    /// time-boxed as a whole. Note that Polars computations cannot be
+    /// interrupted, so the underlying work may continue briefly in the
+    /// background after the error is returned; its result is discarded. As the
+    /// schema is moved into that thread, the type of its values must be
+    /// `Send + 'static`.
+    ///
+    /// Arguments:
+    ///
+    /// * `timeout`: The maximum `Duration` of the validation.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self
+    where
+        T: Send + 'static,
+    {
+        self.timeout = Some((timeout, Self::on_thread));
+        self
+    }
+
+    /// This function enables checking whether the labels of the vertices are
+    /// stable once the supersteps are over. The number of supersteps is computed
+    /// out of the schema, so the validation is run once again with an extra one
+    /// re-running the last level of the schema, and an error is returned if the
+    /// labels differ, as that means the number of iterations was not enough for
+    /// the data, which is the case when the labels keep on being propagated
+    /// along reference cycles. Note that this doubles the cost of the validation.
+    pub fn with_convergence_check(mut self) -> Self {
+        self.convergence_check = true;
+        self
+    }
+
+    /// This function makes the vertices conforming to a `ShapeOr` carry the
+    /// labels of the branches they matched alongside the label of the `ShapeOr`,
+    /// which is helpful for diagnosing why a node was selected. Note that each of
+    /// the branches is validated on its own, so this is costlier than a plain run.
+    pub fn with_branch_labels(mut self) -> Self {
+        self.branch_labels = true;
+        self
     }
 
     /// The function validates a graph and runs a Pregel algorithm on it to get the
@@ -58,12 +335,210 @@ impl<T: Literal + Clone> PSchema<T> {
     ///
     /// Returns:
     ///
-    /// a `Result<DataFrame, PolarsError>`. If the function executes successfully,
+    /// a `Result<DataFrame, PSchemaError>`. If the function executes successfully,
     /// it returns an `Ok(DataFrame)` containing the labels of the vertices. If
-    /// there is an error during execution, it returns an `Err(PolarsError)` with a
+    /// there is an error during execution, it returns an `Err(PSchemaError)` with a
     /// description of the error.
-    pub fn validate(self, graph: GraphFrame) -> PolarsResult<DataFrame> {
-        enable_string_cache();
+    pub fn validate(self, graph: GraphFrame) -> Result<DataFrame, PSchemaError> {
+        self.timed(Instant::now(), graph, Self::subset)
+    }
+
+    /// The function runs the provided validation on the graph within what is
+    /// left of the timeout set through `with_timeout`, if any, counting from
+    /// the provided instant, so the methods running several validations are
+    /// time-boxed as a whole. Every validating method goes through here.
+    ///
+    /// Arguments:
+    ///
+    /// * `start`: The instant the validating method was called at.
+    /// * `graph`: The `GraphFrame` to be validated.
+    /// * `validation`: The validation to be run on the graph.
+    fn timed(
+        self,
+        start: Instant,
+        graph: GraphFrame,
+        validation: Validation<T>,
+    ) -> Result<DataFrame, PSchemaError> {
+        let (timeout, timer) = match self.timeout {
+            Some(timeout) => timeout,
+            None => return validation(self, graph),
+        };
+        match timeout.checked_sub(start.elapsed()) {
+            // The timeout reported is the whole one rather than what was left of it.
+            Some(remaining) => match timer(self, graph, validation, remaining) {
+                Err(PSchemaError::Timeout(left)) if left == remaining => {
+                    Err(PSchemaError::Timeout(timeout))
+                }
+                result => result,
+            },
+            None => Err(PSchemaError::Timeout(timeout)),
+        }
+    }
+
+    /// The function runs the provided validation on a separate thread, so we
+    /// can stop waiting for it once the provided duration passes.
+    fn on_thread(
+        self,
+        graph: GraphFrame,
+        validation: Validation<T>,
+        timeout: Duration,
+    ) -> Result<DataFrame, PSchemaError>
+    where
+        T: Send + 'static,
+    {
+        // The validation is run on a detached thread, so we can stop waiting for it once the
+        // deadline passes. If the receiver is gone by the time it finishes, the result is dropped.
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(validation(self, graph));
+        });
+        match receiver.recv_timeout(timeout) {
+            Ok(result) => result,
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => Err(PSchemaError::Timeout(timeout)),
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => Err(PSchemaError::Graph(
+                String::from("The validation thread panicked"),
+            )),
+        }
+    }
+
+    /// The function validates a graph as `validate` does, but it returns the
+    /// conforming nodes instead of their edges: one row per matched subject with
+    /// its `subject` and `labels` columns. As the vertices are not joined with
+    /// the edges, this is cheaper when only the classification of the nodes is
+    /// needed. Note that the cache, if any, is not used, as it holds the edges,
+    /// whereas the timeout set through `with_timeout`, if any, is.
+    ///
+    /// Arguments:
+    ///
+    /// * `graph`: A `GraphFrame` object representing the graph to be processed.
+    ///
+    /// Returns:
+    ///
+    /// a `Result<DataFrame, PSchemaError>` with the distinct matched subjects
+    /// and their labels.
+    pub fn validate_nodes(self, graph: GraphFrame) -> Result<DataFrame, PSchemaError> {
+        self.timed(Instant::now(), graph, Self::nodes)
+    }
+
+    /// The function computes the conforming nodes, as described in
+    /// `validate_nodes`.
+    fn nodes(self, graph: GraphFrame) -> Result<DataFrame, PSchemaError> {
+        let graph = self.prepare(graph)?;
+        Ok(self
+            .labels(&graph)?
+            .lazy()
+            .filter(col(Column::Custom("labels").as_ref()).list().len().gt(0))
+            .select([
+                col(Column::VertexId.as_ref()).alias(Column::Subject.as_ref()),
+                col(Column::Custom("labels").as_ref()),
+            ])
+            .collect()?)
+    }
+
+    /// The function checks and prepares the graph before running the Pregel
+    /// algorithm on it, as described in `validate`, so the rest of the methods
+    /// can build on it.
+    fn subset(self, graph: GraphFrame) -> Result<DataFrame, PSchemaError> {
+        let graph = self.prepare(graph)?;
+        self.cached(graph, Self::run)
+    }
+
+    /// The function runs the provided validation on a prepared graph, unless a
+    /// previous run of the same schema on the same graph is found in the cache.
+    fn cached(
+        self,
+        graph: GraphFrame,
+        validation: fn(Self, GraphFrame) -> Result<DataFrame, PSchemaError>,
+    ) -> Result<DataFrame, PSchemaError> {
+        // In case a cache is provided, we try to retrieve the result of a previous run with the
+        // same schema and the same graph, so we can avoid recomputing it.
+        match self.cache.clone() {
+            Some((cache, cache_key)) => {
+                let key = cache_key(&self, &graph)?;
+                match cache.get(key) {
+                    Some(subset) => Ok(subset),
+                    None => {
+                        let mut subset = validation(self, graph)?;
+                        cache.put(key, &mut subset)?;
+                        Ok(subset)
+                    }
+                }
+            }
+            None => validation(self, graph),
+        }
+    }
+
+    /// The function computes the key of the cached results of the validation of
+    /// the graph, out of the schema, its options and the edges.
+    fn cache_key(&self, graph: &GraphFrame) -> PolarsResult<u64>
+    where
+        T: Debug + Hash,
+    {
+        Cache::key(&self.start, &self.cache_options()?, &graph.edges)
+    }
+
+    /// The function describes the options affecting the result of the validation,
+    /// so they are taken into account by the key of the cached results. The
+    /// custom vertex programs and aggregates cannot be told apart, so an error
+    /// is returned in case any of them is set, as their results cannot be cached.
+    fn cache_options(&self) -> PolarsResult<String>
+    where
+        T: Debug,
+    {
+        if self.v_prog.is_some() || self.aggregate_messages.is_some() {
+            return Err(PolarsError::InvalidOperation(
+                "Validations running custom vertex programs or aggregates cannot be cached".into(),
+            ));
+        }
+        Ok(format!(
+            "message_dtype={:?};reified_statements={};self_loops={};same_as={:?};branch_labels={}",
+            self.message_dtype,
+            self.reified_statements,
+            self.self_loops,
+            self.same_as,
+            self.branch_labels
+        ))
+    }
+
+    /// The function checks the graph and applies the requested transformations
+    /// to it, such as discarding the self-loops or merging the aliases, so it is
+    /// ready for the Pregel algorithm to be run on it.
+    fn prepare(&self, graph: GraphFrame) -> Result<GraphFrame, PSchemaError> {
+        check_string_cache()?;
+        // We make sure the graph is not larger than allowed before doing any work on it,
+        // so we fail fast instead of running out of memory while transforming it.
+        if let Some(max_edges) = self.max_edges {
+            if graph.edges.height() > max_edges {
+                return Err(PolarsError::ComputeError(
+                    format!(
+                        "The graph has {} edges, which exceeds the maximum of {}",
+                        graph.edges.height(),
+                        max_edges
+                    )
+                    .into(),
+                )
+                .into());
+            }
+        }
+        // The vertices are rebuilt out of the edges whenever these are transformed, so we keep the
+        // attributes provided by the caller, if any, to join them back afterwards.
+        let attributes = graph.vertices.clone();
+        // The self-loops are discarded beforehand, if requested, so we can check whether there are
+        // any edges left.
+        let graph = match self.self_loops {
+            true => graph,
+            false => {
+                let edges = graph
+                    .edges
+                    .lazy()
+                    .filter(col(Column::Subject.as_ref()).neq(col(Column::Object.as_ref())))
+                    .collect()?;
+                match GraphFrame::from_edges(edges) {
+                    Ok(graph) => graph,
+                    Err(error) => return Err(PSchemaError::Graph(error.to_string())),
+                }
+            }
+        };
         // First, we check if the graph has the required columns. If the graph does not have the
         // required columns or in case they are empty, we return an error. The required columns are:
         //  - `subject`: the source vertex of the edge
@@ -72,195 +547,2618 @@ impl<T: Literal + Clone> PSchema<T> {
         check_field(&graph.edges, Column::Subject)?;
         check_field(&graph.edges, Column::Predicate)?;
         check_field(&graph.edges, Column::Object)?;
-        // Secondly, we create two iterators for the nodes in the `Shape Expression` tree. The former
-        // is used to validate those nodes that will be considered in the send messages phase, while
-        // the latter is used during the phase where the vertices are updated.
-        let start = self.start;
-        let mut send_messages_iter = ShapeTree::new(start.clone()).into_iter(); // iterator to send messages
+        // We also make sure the predicates of the schema can be compared with those of the graph.
+        check_predicate_dtype(&graph.edges, &self.start)?;
+        // The reified statements are flattened into direct edges if requested.
+        let graph = match self.reified_statements {
+            true => match GraphFrame::from_edges(flatten_statements(graph.edges)?) {
+                Ok(graph) => graph,
+                Err(error) => return Err(PSchemaError::Graph(error.to_string())),
+            },
+            false => graph,
+        };
+        // The aliases of the same entity are merged into a single vertex if requested.
+        let graph = match self.same_as.clone() {
+            Some(predicate) => match GraphFrame::from_edges(merge_same_as(graph.edges, predicate)?)
+            {
+                Ok(graph) => graph,
+                Err(error) => return Err(PSchemaError::Graph(error.to_string())),
+            },
+            None => graph,
+        };
+        // Finally, the attributes of the vertices are joined back before running the algorithm, so
+        // they are available to custom vertex programs.
+        Ok(Self::with_attributes(graph, attributes)?)
+    }
+
+    /// The function joins the attributes of the vertices provided by the caller
+    /// into those of the prepared graph, so they are kept even if the vertices
+    /// were rebuilt out of the edges. The vertices lacking any attribute, such as
+    /// the aliases merged into another one, are given null values.
+    ///
+    /// Arguments:
+    ///
+    /// * `graph`: The prepared `GraphFrame`.
+    /// * `attributes`: The vertices provided by the caller, with their attributes.
+    ///
+    /// Returns:
+    ///
+    /// The prepared `GraphFrame` whose vertices hold the provided attributes.
+    fn with_attributes(mut graph: GraphFrame, attributes: DataFrame) -> PolarsResult<GraphFrame> {
+        let id = Column::VertexId;
+        if attributes.width() <= 1 || !attributes.schema().contains(id.as_ref()) {
+            return Ok(graph);
+        }
+        graph.vertices = graph
+            .vertices
+            .lazy()
+            .select([col(id.as_ref())])
+            .left_join(attributes.lazy(), id.as_ref(), id.as_ref())
+            .collect()?;
+        Ok(graph)
+    }
+
+    fn run(self, graph: GraphFrame) -> Result<DataFrame, PSchemaError> {
+        let result = self.labels(&graph)?;
+        // The attributes of the vertices provided by the caller, if any, are returned alongside the
+        // labels.
+        let attributes = graph
+            .vertices
+            .get_column_names()
+            .into_iter()
+            .filter(|name| name.as_str() != Column::VertexId.as_ref())
+            .map(|name| col(name.as_str()))
+            .collect::<Vec<_>>();
+        // The position of the edges in the input is kept, if available, so their order is restored,
+        // and so is the kind of their objects, so they are exported as the same kind of term.
+        let order = Column::Custom("_order");
+        let ordered = graph.edges.schema().contains(order.as_ref());
+        let attributes = [order, Column::Custom("object_kind")]
+            .into_iter()
+            .filter(|column| graph.edges.schema().contains(column.as_ref()))
+            .map(|column| col(column.as_ref()))
+            .chain(attributes)
+            .collect::<Vec<_>>();
+        // Finally, we keep the vertices having any label and join them with their edges.
+        let subset = result
+            .lazy()
+            .filter(col(Column::Custom("labels").as_ref()).list().len().gt(0))
+            .select([
+                col(Column::VertexId.as_ref()),
+                col(Column::Custom("labels").as_ref()),
+            ])
+            .left_join(
+                graph.vertices.lazy(),
+                Column::VertexId.as_ref(),
+                Column::VertexId.as_ref(),
+            )
+            .left_join(
+                graph.edges.lazy(),
+                Column::VertexId.as_ref(),
+                Column::Subject.as_ref(),
+            )
+            .select(
+                [
+                    col(Column::VertexId.as_ref()).alias(Column::Subject.as_ref()),
+                    col(Column::Predicate.as_ref()),
+                    col(Column::Object.as_ref()),
+                    col(Column::Custom("labels").as_ref()),
+                ]
+                .into_iter()
+                .chain(attributes)
+                .collect::<Vec<_>>(),
+            );
+        match ordered {
+            true => Ok(subset
+                .sort([order.as_ref()], SortMultipleOptions::default())
+                .collect()?),
+            false => Ok(subset.collect()?),
+        }
+    }
+
+    /// The function runs the Pregel algorithm on a prepared graph and returns the
+    /// labels of each of its vertices.
+    fn labels(&self, graph: &GraphFrame) -> Result<DataFrame, PSchemaError> {
+        // The codes of the labels of the schema must fit the datatype of the messages, if any.
+        if let Some(max) = self.max_message_label() {
+            self.check_max_label(max)?;
+        }
+        let encoding = self.encoding()?;
+        // Secondly, we run the supersteps and get the labels of the vertices. In case the
+        // convergence check is enabled, we make sure an additional superstep would not have changed
+        // them, so the number of iterations computed out of the schema was enough for the data.
+        let result = self.supersteps(graph, &encoding, false)?;
+        if self.convergence_check {
+            let sort = |labels: &DataFrame| {
+                labels.sort([Column::VertexId.as_ref()], SortMultipleOptions::default())
+            };
+            let extra = self.supersteps(graph, &encoding, true)?;
+            if !sort(&result)?.equals_missing(&sort(&extra)?) {
+                let iterations = ShapeTree::new(self.start.clone()).iterations()?;
+                return Err(PSchemaError::NotConverged(iterations));
+            }
+        }
+        let result = match self.branch_labels {
+            true => self.matched_branches(graph, &encoding, result)?,
+            false => result,
+        };
+        // Finally, the codes of the labels, if any, are turned back into the labels themselves.
+        let labels = Column::Custom("labels");
+        match self.message_dtype {
+            DataType::Categorical(_, _) | DataType::String => Ok(result),
+            _ => Ok(result
+                .lazy()
+                .with_column(encoding.decode(col(labels.as_ref())).alias(labels.as_ref()))
+                .collect()?),
+        }
+    }
+
+    /// The function returns the highest code a label can be assigned out of the
+    /// datatype of the messages. The codes are assigned by a `SymbolTable`, so
+    /// they cannot exceed a `Label` either. `Categorical` and `String` messages
+    /// hold the labels as they are, so there is no limit in that case.
+    fn max_message_label(&self) -> Option<usize> {
+        let max = match self.message_dtype {
+            DataType::UInt16 => u16::MAX as usize,
+            DataType::Int32 => i32::MAX as usize,
+            DataType::UInt32 => u32::MAX as usize,
+            DataType::Int64 | DataType::UInt64 => usize::MAX,
+            _ => return None,
+        };
+        Some(max.min(Label::MAX as usize))
+    }
+
+    /// The function checks that the labels of the schema can be identified by
+    /// the provided highest identifier, which is derived from the datatype of
+    /// the messages but for the tests, so schemas with too many labels fail early.
+    ///
+    /// Arguments:
+    ///
+    /// * `max`: The highest identifier a label can be assigned.
+    ///
+    /// Returns:
+    ///
+    /// An error in case the highest label of the schema exceeds `max`.
+    fn check_max_label(&self, max: usize) -> Result<(), PSchemaError> {
+        match self.start.max_label() {
+            Some(max_label) if max_label as usize <= max => Ok(()),
+            _ => Err(PSchemaError::Schema(ShapeError::TooManyLabels(
+                self.start.labels().len(),
+                max + 1,
+            ))),
+        }
+    }
+
+    /// The function decides how the labels are written in the messages out of
+    /// their datatype. `Categorical` and `String` messages hold the labels as
+    /// they are, while integer messages hold their codes, which are assigned by
+    /// a `SymbolTable` in lexical order, so they are the same across runs.
+    fn encoding(&self) -> PolarsResult<Labels> {
+        match &self.message_dtype {
+            DataType::Categorical(_, _) | DataType::String => {
+                Ok(Labels::new(self.message_dtype.clone()))
+            }
+            DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64
+            | DataType::Int32
+            | DataType::Int64 => {
+                let mut labels = self.start.labels().into_iter().collect::<Vec<_>>();
+                labels.sort();
+                let mut symbols = SymbolTable::new();
+                for label in labels {
+                    if let Err(error) = symbols.try_insert(label) {
+                        return Err(PolarsError::InvalidOperation(error.into()));
+                    }
+                }
+                Ok(Labels::encoded(self.message_dtype.clone(), symbols))
+            }
+            dtype => Err(PolarsError::InvalidOperation(
+                format!("Messages of type {} cannot hold the labels", dtype).into(),
+            )),
+        }
+    }
+
+    /// The function appends the labels of the matched branches of every `ShapeOr`
+    /// in the schema to the labels of the vertices conforming to it. Each of the
+    /// branches is validated on its own, as their labels are overwritten by the
+    /// time the `ShapeOr` is evaluated.
+    ///
+    /// Arguments:
+    ///
+    /// * `graph`: The `GraphFrame` being validated.
+    /// * `encoding`: How the labels are written in the messages.
+    /// * `result`: The labels of each of the vertices.
+    ///
+    /// Returns:
+    ///
+    /// A `DataFrame` holding the labels of each of the vertices, including those
+    /// of the matched branches.
+    fn matched_branches(
+        &self,
+        graph: &GraphFrame,
+        encoding: &Labels,
+        result: DataFrame,
+    ) -> Result<DataFrame, PSchemaError> {
+        let labels = Column::Custom("labels");
+        let mut result = result.lazy();
+        for shape in ShapeTree::new(self.start.clone()).into_iter().flatten() {
+            let label = shape.get_label();
+            let branches = match shape {
+                Shape::ShapeOr(shape) => shape.get_shapes(),
+                _ => continue,
+            };
+            for branch in branches {
+                let branch_label = branch.get_label();
+                let conforming = Self {
+                    start: branch,
+                    cache: None,
+                    messages: Arc::default(),
+                    convergence_check: false,
+                    branch_labels: false,
+                    ..self.clone()
+                }
+                .supersteps(graph, encoding, false)?
+                .lazy()
+                .filter(
+                    col(labels.as_ref())
+                        .list()
+                        .contains(encoding.lit(branch_label)),
+                )
+                .select([col(Column::VertexId.as_ref())])
+                .collect()?;
+                let conforming = conforming
+                    .column(Column::VertexId.as_ref())?
+                    .as_materialized_series()
+                    .clone();
+                result = result.with_column(
+                    when(
+                        col(labels.as_ref())
+                            .list()
+                            .contains(encoding.lit(label))
+                            .and(col(Column::VertexId.as_ref()).is_in(lit(conforming))),
+                    )
+                    .then(concat_list([
+                        col(labels.as_ref()),
+                        encoding.lit(branch_label).cast(encoding.dtype().clone()),
+                    ])?)
+                    .otherwise(col(labels.as_ref()))
+                    .alias(labels.as_ref()),
+                );
+            }
+        }
+        Ok(result.collect()?)
+    }
+
+    /// The function runs the Pregel supersteps over the graph, so the labels of
+    /// the vertices are computed. The messages sent during each superstep are
+    /// compiled beforehand, so no expression is built in between them.
+    ///
+    /// Arguments:
+    ///
+    /// * `graph`: The `GraphFrame` to be processed.
+    /// * `encoding`: How the labels are written in the messages.
+    /// * `extra_superstep`: Whether the last superstep is to be run twice, so
+    /// the labels it results in are checked to be stable.
+    ///
+    /// Returns:
+    ///
+    /// A `DataFrame` holding the labels of each of the vertices.
+    fn supersteps(
+        &self,
+        graph: &GraphFrame,
+        encoding: &Labels,
+        extra_superstep: bool,
+    ) -> Result<DataFrame, PSchemaError> {
+        let messages = self.messages(encoding);
+        let (mut subject_messages, mut object_messages) = (messages.0.clone(), messages.1.clone());
+        let mut iterations = ShapeTree::new(self.start.clone()).iterations()?;
+        if let (true, Some(subject), Some(object)) = (
+            extra_superstep,
+            subject_messages.last().cloned(),
+            object_messages.last().cloned(),
+        ) {
+            subject_messages.push(subject);
+            object_messages.push(object);
+            iterations = iterations.checked_add(1).ok_or_else(|| {
+                PolarsError::ComputeError("The schema exceeds the maximum number of levels".into())
+            })?;
+        }
+        let mut subject_messages = subject_messages.into_iter();
+        let mut object_messages = object_messages.into_iter();
+        let no_messages = lit(NULL).cast(encoding.dtype().clone());
+        self.pregel(
+            graph,
+            iterations,
+            || {
+                subject_messages
+                    .next()
+                    .unwrap_or_else(|| no_messages.clone())
+            },
+            || {
+                object_messages
+                    .next()
+                    .unwrap_or_else(|| no_messages.clone())
+            },
+            encoding,
+        )
+    }
+
+    /// The function runs the Pregel algorithm over the graph for the provided
+    /// number of iterations, sending the messages built by the provided closures,
+    /// which are called at the start of each superstep. The superstep timeout,
+    /// if any, is enforced here.
+    ///
+    /// Arguments:
+    ///
+    /// * `graph`: The `GraphFrame` to be processed.
+    /// * `iterations`: The number of supersteps to be run.
+    /// * `subject_messages`: The closure building the messages sent to the
+    /// subjects of the edges in the next superstep.
+    /// * `object_messages`: The closure building the messages sent to the
+    /// objects of the edges in the next superstep.
+    /// * `encoding`: How the labels are written in the messages.
+    ///
+    /// Returns:
+    ///
+    /// A `DataFrame` holding the labels of each of the vertices.
+    fn pregel(
+        &self,
+        graph: &GraphFrame,
+        iterations: u8,
+        mut subject_messages: impl FnMut() -> Expr,
+        object_messages: impl FnMut() -> Expr,
+        encoding: &Labels,
+    ) -> Result<DataFrame, PSchemaError> {
+        let no_messages = lit(NULL).cast(encoding.dtype().clone());
+        let aggregate_messages = self.aggregate_messages.clone();
+        let v_prog = self.v_prog.clone();
+        // The messages of each superstep are requested right when it starts, so that is when we
+        // check whether the previous one exceeded the time budget, if any, and reset the clock.
+        let timeout = self.superstep_timeout;
+        let mut superstep: Option<Instant> = None;
+        let mut timed_out = false;
         let pregel = PregelBuilder::new(graph.clone())
-            .max_iterations(ShapeTree::new(start).iterations())
+            .max_iterations(iterations)
             .with_vertex_column(Column::Custom("labels"))
             .initial_message(Self::initial_message())
             .send_messages_function(MessageReceiver::Subject, || {
-                Self::send_messages(send_messages_iter.by_ref())
+                if let Some(timeout) = timeout {
+                    if let Some(start) = superstep {
+                        timed_out |= start.elapsed() > timeout;
+                    }
+                    superstep = Some(Instant::now());
+                }
+                // Once a superstep exceeds the budget, the rest send no messages, so they end quickly.
+                match timed_out {
+                    false => subject_messages(),
+                    true => no_messages.clone(),
+                }
+            })
+            .send_messages_function(MessageReceiver::Object, object_messages)
+            .aggregate_messages_function(move || match &aggregate_messages {
+                Some(aggregate_messages) => aggregate_messages(),
+                None => Self::aggregate_messages(),
+            })
+            .v_prog_function(move || match &v_prog {
+                Some(v_prog) => v_prog(),
+                None => Self::v_prog(),
             })
-            .aggregate_messages_function(Self::aggregate_messages)
-            .v_prog_function(Self::v_prog)
             .build();
-        // Finally, we can run the algorithm and get the result. The result is a DataFrame
-        // containing the labels of the vertices.
-        match pregel.run() {
-            Ok(result) => result
+        let result = pregel.run();
+        // The last superstep ends with the algorithm, so its duration is checked afterwards.
+        if let Some(timeout) = timeout {
+            if timed_out || superstep.is_some_and(|start| start.elapsed() > timeout) {
+                return Err(PSchemaError::Timeout(timeout));
+            }
+        }
+        Ok(result?)
+    }
+
+    /// The function validates the edges contained in a `LazyFrame` without the
+    /// need of going through any of the backends. The three required columns
+    /// are selected from the provided frame, a `GraphFrame` is built out of
+    /// them and the validation is run as in `validate`.
+    ///
+    /// Arguments:
+    ///
+    /// * `lf`: A `LazyFrame` containing, at least, the `subject`, `predicate`
+    /// and `object` columns.
+    ///
+    /// Returns:
+    ///
+    /// a `Result<DataFrame, PSchemaError>` with the same semantics as `validate`.
+    /// An error is returned in case any of the required columns is missing.
+    pub fn validate_lazyframe(self, lf: LazyFrame) -> Result<DataFrame, PSchemaError> {
+        let start = Instant::now();
+        let edges = lf
+            .select(&[
+                col(Column::Subject.as_ref()),
+                col(Column::Predicate.as_ref()),
+                col(Column::Object.as_ref()),
+            ])
+            .collect()?;
+        match GraphFrame::from_edges(edges) {
+            Ok(graph) => self.timed(start, graph, Self::subset),
+            Err(error) => Err(PSchemaError::Graph(error.to_string())),
+        }
+    }
+
+    /// The function splits the graph into its weakly connected components and
+    /// validates each of them in parallel, merging the results afterwards. This
+    /// may speed up the validation of large graphs made of many independent
+    /// components. The graph is checked and prepared as a whole beforehand, so
+    /// the options of the schema, the attributes of the vertices and the order
+    /// of the edges are honoured as in `validate`, and so is the timeout.
+    ///
+    /// Arguments:
+    ///
+    /// * `graph`: A `GraphFrame` object representing the graph to be processed.
+    ///
+    /// Returns:
+    ///
+    /// a `Result<DataFrame, PSchemaError>` with the same semantics as `validate`.
+    pub fn validate_by_component(self, graph: GraphFrame) -> Result<DataFrame, PSchemaError>
+    where
+        T: Send + Sync,
+    {
+        self.timed(Instant::now(), graph, |pschema, graph| {
+            let graph = pschema.prepare(graph)?;
+            pschema.cached(graph, Self::run_by_component)
+        })
+    }
+
+    /// The function runs the Pregel algorithm on each of the weakly connected
+    /// components of a prepared graph in parallel, as described in
+    /// `validate_by_component`. As no edge links two different components, no
+    /// reference can be resolved across them.
+    fn run_by_component(self, graph: GraphFrame) -> Result<DataFrame, PSchemaError>
+    where
+        T: Send + Sync,
+    {
+        let mut edges = graph.edges;
+        let component = Series::new("component".into(), components(&edges)?);
+        edges.with_column(component)?;
+        let partitions = edges.partition_by(["component"], false)?;
+
+        let mut subsets = partitions
+            .into_par_iter()
+            .map(|edges| match GraphFrame::from_edges(edges) {
+                // The vertices are rebuilt out of the edges of the component, so we join back the
+                // attributes of the prepared graph.
+                Ok(component) => {
+                    let component = Self::with_attributes(component, graph.vertices.clone())?;
+                    self.clone().run(component)
+                }
+                Err(error) => Err(PSchemaError::Graph(error.to_string())),
+            })
+            .collect::<Result<Vec<_>, PSchemaError>>()?
+            .into_iter();
+        let first = subsets
+            .next()
+            .ok_or_else(|| PSchemaError::Graph(String::from("No components were found")))?;
+        // The subsets are stacked fallibly, so a mismatch among their schemas is reported.
+        let subset = subsets.try_fold(first, |acc, subset| acc.vstack(&subset))?;
+        // Each of the subsets is sorted on its own, so the order of the input is restored as a
+        // whole once they are stacked.
+        let order = Column::Custom("_order");
+        match subset.schema().contains(order.as_ref()) {
+            true => Ok(subset.sort([order.as_ref()], SortMultipleOptions::default())?),
+            false => Ok(subset),
+        }
+    }
+
+    /// The function validates each of the files of a directory whose name
+    /// matches a glob pattern, e.g. `*.nt`, and concatenates the resulting
+    /// subsets, so a single report is built out of many small files. The files
+    /// are imported through the provided `Backend` and validated in parallel.
+    /// A `source_file` column holding the path of the file each row comes from
+    /// is added, together with an `error` column. A file that cannot be
+    /// imported or validated does not abort the whole run; instead, a single
+    /// row holding the error message is added for it, the rest being NULL. The
+    /// timeout, if any, applies to the whole directory, so the files that are
+    /// not validated in time get a row holding the timeout error.
+    ///
+    /// Arguments:
+    ///
+    /// * `dir`: The directory holding the files to be validated.
+    /// * `pattern`: The glob pattern the names of the files must match, where
+    /// `*` matches any sequence of characters and `?` any single character.
+    ///
+    /// Returns:
+    ///
+    /// a `Result<DataFrame, PSchemaError>` with the report, or an error in case
+    /// the directory cannot be read.
+    pub fn validate_dir<B: Backend, P: AsRef<std::path::Path>>(
+        self,
+        dir: P,
+        pattern: &str,
+    ) -> Result<DataFrame, PSchemaError>
+    where
+        T: Send + Sync,
+    {
+        let mut paths = std::fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file())
+            .filter(
+                |path| match path.file_name().and_then(|name| name.to_str()) {
+                    Some(name) => glob::matches(pattern, name),
+                    None => false,
+                },
+            )
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        let start = Instant::now();
+        let reports = paths
+            .into_par_iter()
+            .map(|path| {
+                let source = path.to_string_lossy().to_string();
+                let subset = B::import(&source)
+                    .and_then(|edges| GraphFrame::from_edges(edges).map_err(|e| e.to_string()))
+                    .and_then(|graph| {
+                        let subset = self.clone().timed(start, graph, Self::subset);
+                        subset.map_err(|e| e.to_string())
+                    });
+                match subset {
+                    Ok(subset) => Ok(subset.lazy().with_columns([
+                        lit(source).alias("source_file"),
+                        lit(NULL).cast(DataType::String).alias("error"),
+                    ])),
+                    Err(error) => Ok(df![
+                        "source_file" => [source],
+                        "error" => [error],
+                    ]?
+                    .lazy()),
+                }
+            })
+            .collect::<PolarsResult<Vec<_>>>()?;
+
+        match reports.is_empty() {
+            true => Err(PSchemaError::Graph(String::from(
+                "No files in the directory match the pattern",
+            ))),
+            false => Ok(concat_lf_diagonal(reports, UnionArgs::default())?.collect()?),
+        }
+    }
+
+    /// The function validates a graph and computes the ratio of the subjects
+    /// having a certain predicate that conform to the schema. This is useful for
+    /// data-quality dashboards, answering questions such as "which percentage
+    /// of the humans conform to the `Researcher` shape".
+    ///
+    /// Arguments:
+    ///
+    /// * `graph`: A `GraphFrame` object representing the graph to be processed.
+    /// * `target_predicate`: The predicate the candidate subjects must have.
+    ///
+    /// Returns:
+    ///
+    /// a `Result<Option<f64>, PSchemaError>` containing the ratio of the matched
+    /// candidates over the total number of candidates, or `None` in case there
+    /// are no candidates at all.
+    pub fn conformance_ratio(
+        self,
+        graph: GraphFrame,
+        target_predicate: T,
+    ) -> Result<Option<f64>, PSchemaError> {
+        let start = Instant::now();
+        let candidates = graph
+            .edges
+            .clone()
+            .lazy()
+            .filter(col(Column::Predicate.as_ref()).eq(lit(target_predicate)))
+            .select([col(Column::Subject.as_ref())])
+            .unique(None, UniqueKeepStrategy::Any);
+
+        let total = candidates.clone().collect()?.height();
+        if total == 0 {
+            return Ok(None);
+        }
+
+        let matched = self
+            .timed(start, graph, Self::subset)?
+            .lazy()
+            .select([col(Column::Subject.as_ref())])
+            .unique(None, UniqueKeepStrategy::Any)
+            .inner_join(
+                candidates,
+                col(Column::Subject.as_ref()),
+                col(Column::Subject.as_ref()),
+            )
+            .collect()?
+            .height();
+
+        Ok(Some(matched as f64 / total as f64))
+    }
+
+    /// The function validates a graph, exports the resulting subset using the
+    /// provided backend and writes the schema that produced it as JSON next to
+    /// it, so the provenance of the subset is kept.
+    ///
+    /// Arguments:
+    ///
+    /// * `graph`: A `GraphFrame` object representing the graph to be processed.
+    /// * `subset_path`: The path where the subset is to be exported.
+    /// * `schema_path`: The path where the serialized schema is to be written.
+    ///
+    /// Returns:
+    ///
+    /// a `Result<(), String>`, where the `String` is an error message in case
+    /// any of the steps fails.
+    #[cfg(feature = "serde")]
+    pub fn export_with_schema<B: Backend>(
+        self,
+        graph: GraphFrame,
+        subset_path: &str,
+        schema_path: &str,
+    ) -> Result<(), String>
+    where
+        T: serde::Serialize,
+    {
+        let schema = match serde_json::to_string_pretty(&self.start) {
+            Ok(schema) => schema,
+            Err(error) => return Err(format!("Cannot serialize the schema: {}", error)),
+        };
+        match self.validate(graph) {
+            Ok(mut subset) => B::export(subset_path, &mut subset)?,
+            Err(error) => return Err(error.to_string()),
+        };
+        match std::fs::write(schema_path, schema) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(String::from("Error writing the schema file")),
+        }
+    }
+
+    /// The function validates a graph and returns only the triples that were not
+    /// in the subset produced by a previous validation. This is useful in
+    /// iterative workflows, where the schema is tweaked and the graph validated
+    /// again, as it tells which triples the new schema matches.
+    ///
+    /// Arguments:
+    ///
+    /// * `graph`: A `GraphFrame` object representing the graph to be processed.
+    /// * `previous_subset`: The subset resulting from the previous validation.
+    ///
+    /// Returns:
+    ///
+    /// a `Result<DataFrame, PSchemaError>` with the rows of the new subset whose
+    /// `subject`, `predicate` and `object` were not in the previous one.
+    pub fn validate_delta(
+        self,
+        graph: GraphFrame,
+        previous_subset: &DataFrame,
+    ) -> Result<DataFrame, PSchemaError> {
+        let triple = [
+            col(Column::Subject.as_ref()),
+            col(Column::Predicate.as_ref()),
+            col(Column::Object.as_ref()),
+        ];
+        Ok(self
+            .timed(Instant::now(), graph, Self::subset)?
+            .lazy()
+            .join(
+                previous_subset.clone().lazy().select(triple.clone()),
+                triple.clone(),
+                triple,
+                JoinArgs::new(JoinType::Anti),
+            )
+            .collect()?)
+    }
+
+    /// The function validates a graph and writes a JSON manifest describing the
+    /// run next to the subset, so validations can be audited afterwards. The
+    /// manifest records the version of the crate, the input the graph was
+    /// imported from, the hash of the schema, the number of edges and vertices
+    /// of the graph, the number of rows and subjects of the subset and the
+    /// duration of the validation. The hash of the schema is computed with a
+    /// fixed hash function, so it is the same across Rust releases and runs.
+    ///
+    /// Arguments:
+    ///
+    /// * `graph`: A `GraphFrame` object representing the graph to be processed.
+    /// * `source`: The path of the input the graph was imported from.
+    /// * `manifest_path`: The path where the JSON manifest is to be written.
+    ///
+    /// Returns:
+    ///
+    /// a `Result<DataFrame, PSchemaError>` with the subset, as `validate` does.
+    pub fn validate_with_manifest<P: AsRef<std::path::Path>>(
+        self,
+        graph: GraphFrame,
+        source: &str,
+        manifest_path: P,
+    ) -> Result<DataFrame, PSchemaError>
+    where
+        T: Hash,
+    {
+        let schema_hash = Cache::schema_key(&self.start);
+        let (edges, vertices) = (graph.edges.height(), graph.vertices.height());
+
+        let start = Instant::now();
+        let subset = self.validate(graph)?;
+        let elapsed = start.elapsed();
+
+        let matches = subset
+            .column(Column::Subject.as_ref())
+            .and_then(|subjects| subjects.n_unique())?;
+        // The manifest is written by hand, so it is available without the `serde` feature.
+        let manifest = format!(
+            "{{\n  \"version\": {},\n  \"source\": {},\n  \"schema_hash\": \"{:016x}\",\n  \
+             \"edges\": {},\n  \"vertices\": {},\n  \"rows\": {},\n  \"matches\": {},\n  \
+             \"elapsed_ms\": {}\n}}\n",
+            json_string(env!("CARGO_PKG_VERSION")),
+            json_string(source),
+            schema_hash,
+            edges,
+            vertices,
+            subset.height(),
+            matches,
+            elapsed.as_millis()
+        );
+        std::fs::write(manifest_path, manifest)?;
+        Ok(subset)
+    }
+
+    /// The function validates a graph and splits the resulting subset into one
+    /// `DataFrame` per top-level shape, that is, per shape directly contained in
+    /// the starting `ShapeAnd` or `ShapeOr`, or the starting shape itself
+    /// otherwise. As the inner labels are overwritten by their parents during
+    /// the Pregel supersteps, each of the top-level shapes is validated on its
+    /// own, and the subjects in the subset matching it make up its group. Thus,
+    /// a subject matching several shapes appears in each of their groups.
+    ///
+    /// Arguments:
+    ///
+    /// * `graph`: A `GraphFrame` object representing the graph to be processed.
+    ///
+    /// Returns:
+    ///
+    /// a `Result<HashMap<&'static str, DataFrame>, PSchemaError>` mapping the
+    /// label of each of the top-level shapes matched by any subject to the rows
+    /// of the subset belonging to those subjects.
+    pub fn validate_grouped(
+        self,
+        graph: GraphFrame,
+    ) -> Result<HashMap<&'static str, DataFrame>, PSchemaError> {
+        let shapes = match &self.start {
+            Shape::ShapeAnd(shape) => shape.get_shapes(),
+            Shape::ShapeOr(shape) => shape.get_shapes(),
+            shape => vec![shape.clone()],
+        };
+        let start = Instant::now();
+        let subset = self.clone().timed(start, graph.clone(), Self::subset)?;
+
+        let mut groups = HashMap::new();
+        for shape in shapes {
+            let label = shape.get_label();
+            let subjects = PSchema {
+                start: shape,
+                messages: Arc::default(),
+                ..self.clone()
+            }
+            .timed(start, graph.clone(), Self::subset)?
+            .lazy()
+            .select([col(Column::Subject.as_ref())])
+            .unique(None, UniqueKeepStrategy::Any);
+            let group = subset
+                .clone()
+                .lazy()
+                .inner_join(
+                    subjects,
+                    col(Column::Subject.as_ref()),
+                    col(Column::Subject.as_ref()),
+                )
+                .collect()?;
+            if group.height() > 0 {
+                groups.insert(label, group);
+            }
+        }
+        Ok(groups)
+    }
+
+    /// The function returns the labels of the triple constraints expecting a
+    /// concrete value that match edges whose object is a datatype id rather than
+    /// a real value. That is the case of the graphs imported from DuckDB, where
+    /// every literal is collapsed into the id of its datatype, so a constraint
+    /// on a specific literal value cannot distinguish among the literals of the
+    /// same datatype. This serves as a warning to be checked before validating.
+    /// Graphs lacking the `dtype` column produced by `DuckDB::import` yield no
+    /// warnings.
+    ///
+    /// Arguments:
+    ///
+    /// * `graph`: A `GraphFrame` object representing the graph to be processed.
+    ///
+    /// Returns:
+    ///
+    /// a `Result<Vec<&'static str>, PSchemaError>` containing the labels of the
+    /// offending triple constraints.
+    pub fn collapsed_literal_constraints(
+        &self,
+        graph: &GraphFrame,
+    ) -> Result<Vec<&'static str>, PSchemaError> {
+        if graph
+            .edges
+            .schema()
+            .get(Column::Custom("dtype").as_ref())
+            .is_none()
+        {
+            return Ok(Vec::new());
+        }
+        let entity = u32::from(Id::DataType(WikidataType::Entity));
+
+        let mut labels = Vec::new();
+        for shape in ShapeTree::new(self.start.clone()).into_iter().flatten() {
+            let (predicates, value) = match &shape {
+                Shape::TripleConstraint(constraint) => match constraint.value_constraint() {
+                    Some(constraint) => constraint,
+                    None => continue,
+                },
+                _ => continue,
+            };
+            let collapsed = is_in_values(col(Column::Predicate.as_ref()), predicates.to_vec())
+                .and(col(Column::Object.as_ref()).eq(lit(value.clone())))
+                .and(col(Column::Custom("dtype").as_ref()).neq(lit(entity)));
+            let matches = graph.edges.clone().lazy().filter(collapsed).collect()?;
+            if matches.height() > 0 && !labels.contains(&shape.get_label()) {
+                labels.push(shape.get_label());
+            }
+        }
+        Ok(labels)
+    }
+
+    /// The function returns the labels of the shapes in the `Shape Expression`
+    /// tree that have not been assigned to any vertex. This is helpful for
+    /// debugging schemas that are run against real data, as it allows us to
+    /// know which constraints never fired. Note that the labels of the inner
+    /// shapes are overwritten by their parents during the Pregel supersteps,
+    /// so each of the nodes in the tree is validated on its own.
+    ///
+    /// Arguments:
+    ///
+    /// * `graph`: A `GraphFrame` object representing the graph to be processed.
+    ///
+    /// Returns:
+    ///
+    /// a `Result<Vec<&'static str>, PSchemaError>` containing the labels that
+    /// appear in no vertex's `labels` list, in the order they are processed.
+    pub fn unmatched_labels(self, graph: GraphFrame) -> Result<Vec<&'static str>, PSchemaError> {
+        let start = Instant::now();
+        let mut visited = HashSet::new();
+        let mut unmatched = Vec::new();
+        for shape in ShapeTree::new(self.start.clone()).into_iter().flatten() {
+            let label = shape.get_label();
+            if !visited.insert(label) {
+                continue;
+            }
+            let pschema = PSchema {
+                start: shape,
+                messages: Arc::default(),
+                ..self.clone()
+            };
+            if pschema.timed(start, graph.clone(), Self::subset)?.height() == 0 {
+                unmatched.push(label);
+            }
+        }
+        Ok(unmatched)
+    }
+
+    /// The function reports, for each of the reference shapes in the schema,
+    /// how many edges could be followed and how many of them reached a node
+    /// conforming to the referenced shape. The target of a `ShapeReference` is
+    /// the object of the edge, while that of an `IncomingReference` is its
+    /// subject. Each referenced shape is validated on its own.
+    ///
+    /// Arguments:
+    ///
+    /// * `graph`: A `GraphFrame` object representing the graph to be processed.
+    ///
+    /// Returns:
+    ///
+    /// a `Result<Vec<ReferenceStats>, PSchemaError>` with the counters of each of
+    /// the reference shapes, in the order they are evaluated.
+    pub fn reference_stats(&self, graph: &GraphFrame) -> Result<Vec<ReferenceStats>, PSchemaError> {
+        let start = Instant::now();
+        let mut stats = Vec::new();
+        for shape in ShapeTree::new(self.start.clone()).into_iter().flatten() {
+            let label = shape.get_label();
+            let (predicate, reference, target) = match shape {
+                Shape::ShapeReference(shape) => (
+                    shape.get_predicate().clone(),
+                    shape.get_reference(),
+                    Column::Object,
+                ),
+                Shape::IncomingReference(shape) => (
+                    shape.get_predicate().clone(),
+                    shape.get_reference(),
+                    Column::Subject,
+                ),
+                _ => continue,
+            };
+            let conforming = PSchema {
+                start: reference,
+                cache: None,
+                messages: Arc::default(),
+                ..self.clone()
+            }
+            .timed(start, graph.clone(), Self::subset)?
+            .lazy()
+            .select([col(Column::Subject.as_ref()).alias(target.as_ref())])
+            .unique(None, UniqueKeepStrategy::Any);
+            let candidates = graph
+                .edges
+                .clone()
+                .lazy()
+                .filter(col(Column::Predicate.as_ref()).eq(lit(predicate)));
+            let resolved = candidates
+                .clone()
+                .semi_join(conforming, col(target.as_ref()), col(target.as_ref()))
+                .collect()?
+                .height();
+            stats.push(ReferenceStats {
+                label,
+                candidates: candidates.collect()?.height(),
+                resolved,
+            });
+        }
+        Ok(stats)
+    }
+
+    fn initial_message() -> Expr {
+        lit(NULL)
+    }
+
+    /// The function returns the compiled messages of the schema, compiling them
+    /// on the first call. They are shared among the clones of the `PSchema`, so
+    /// the runs of the same schema reuse them instead of building them again,
+    /// such as the one per component of `validate_by_component` or the extra run
+    /// of the convergence check. The encoding only depends on the schema and the
+    /// datatype of the messages, so changing any of them discards the messages.
+    ///
+    /// Arguments:
+    ///
+    /// * `encoding`: How the labels are written in the messages.
+    fn messages(&self, encoding: &Labels) -> Messages {
+        self.messages
+            .get_or_init(|| Arc::new(Self::compile(self.start.clone(), encoding)))
+            .clone()
+    }
+
+    /// The function compiles the expressions of the messages sent during each
+    /// of the supersteps, as they only depend on the shapes, so they are built
+    /// once per schema instead of right before each superstep of every run.
+    ///
+    /// Arguments:
+    ///
+    /// * `start`: The starting shape of the schema.
+    /// * `encoding`: How the labels are written in the messages, which are cast
+    /// to its datatype.
+    ///
+    /// Returns:
+    ///
+    /// A pair with the messages sent to the subjects and to the objects of the
+    /// edges, respectively, in the order of the supersteps.
+    fn compile(start: Shape<T>, encoding: &Labels) -> (Vec<Expr>, Vec<Expr>) {
+        ShapeTree::new(start)
+            .into_iter()
+            .map(|level| {
+                (
+                    Self::send_messages(&mut std::iter::once(level.clone()), encoding),
+                    Self::send_incoming_messages(&mut std::iter::once(level), encoding),
+                )
+            })
+            .unzip()
+    }
+
+    /// The function sends messages to the vertices in the graph. It takes a
+    /// `ShapeTreeItem` iterator as an argument and returns an `Expr` that
+    /// represents the messages to be sent. Depending on the type of the node
+    /// being sent, the function sends a different message to the vertex. Thus,
+    /// a different validation function is called for each node.
+    ///
+    /// Arguments:
+    ///
+    /// * `iterator`: The `iterator` parameter is a mutable reference to a
+    /// `ShapeTreeItem` iterator. It is used to iterate over the nodes in the
+    /// `ShapeTree` and send messages to the vertices in the graph.
+    /// * `encoding`: How the labels are written in the messages, which are cast
+    /// to its datatype.
+    ///
+    /// Returns:
+    ///
+    /// The function `send_messages` returns an `Expr` that represents the
+    /// messages to be sent.
+    fn send_messages(
+        iterator: &mut dyn Iterator<Item = ShapeTreeItem<T>>,
+        encoding: &Labels,
+    ) -> Expr {
+        let mut messages = lit(NULL);
+        if let Some(schema) = iterator.next() {
+            for shape in schema {
+                messages = match shape {
+                    Shape::TripleConstraint(shape) if shape.is_inverse() => messages,
+                    Shape::TripleConstraint(shape) => shape.validate(messages, encoding),
+                    Shape::ShapeReference(shape) => shape.validate(messages, encoding),
+                    Shape::IncomingReference(_) => messages,
+                    Shape::ShapeAnd(shape) => shape.validate(messages, encoding),
+                    Shape::ShapeOr(shape) => shape.validate(messages, encoding),
+                    Shape::Cardinality(shape) => shape.validate(messages, encoding),
+                    Shape::ShapeNot(shape) => shape.validate(messages, encoding),
+                    Shape::NumericFacet(shape) => shape.validate(messages, encoding),
+                }
+            }
+        }
+        messages.cast(encoding.dtype().clone())
+    }
+
+    /// The function sends the messages of the `IncomingReference` shapes and of
+    /// the inverse `TripleConstraint`s to the objects of the edges, as those are
+    /// the nodes such shapes are assigned to. The rest of the shapes are
+    /// validated in `send_messages`.
+    ///
+    /// Arguments:
+    ///
+    /// * `iterator`: The `ShapeTreeItem` iterator, advanced in lockstep with the
+    /// one used in `send_messages`.
+    /// * `encoding`: How the labels are written in the messages, which are cast
+    /// to its datatype.
+    ///
+    /// Returns:
+    ///
+    /// The function returns an `Expr` that represents the messages to be sent.
+    fn send_incoming_messages(
+        iterator: &mut dyn Iterator<Item = ShapeTreeItem<T>>,
+        encoding: &Labels,
+    ) -> Expr {
+        let mut messages = lit(NULL);
+        if let Some(schema) = iterator.next() {
+            for shape in schema {
+                messages = match shape {
+                    Shape::IncomingReference(shape) => shape.validate(messages, encoding),
+                    Shape::TripleConstraint(shape) if shape.is_inverse() => {
+                        shape.validate(messages, encoding)
+                    }
+                    _ => messages,
+                };
+            }
+        }
+        messages.cast(encoding.dtype().clone())
+    }
+
+    /// The function returns an expression that aggregates messages by exploding a
+    /// column and dropping NULL values.
+    ///
+    /// Returns:
+    ///
+    /// The function `aggregate_messages()` returns an expression that selects the `msg`
+    /// column from a DataFrame, explodes the column (i.e., creates a new row for each
+    /// element in the column), and drops any rows that have NULL values in the
+    /// resulting column.
+    fn aggregate_messages() -> Expr {
+        Column::msg(None).drop_nulls()
+    }
+
+    fn v_prog() -> Expr {
+        Column::msg(None)
+    }
+}
+
+/// This function writes a string as a JSON string literal, escaping the quotes,
+/// the backslashes and the control characters it may contain.
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::PSchemaError;
+    use crate::pschema::{json_string, PSchema, ReferenceStats};
+    use crate::shape::shape_tree::ShapeTree;
+    use crate::shape::shex::{
+        Bound, Cardinality, Labels, NodeConstraint, Shape, ShapeAnd, ShapeError, ShapeNot, ShapeOr,
+        ShapeReference, SubclassConstraint, TripleConstraint,
+    };
+    use crate::test_support::assert_conforms;
+    use crate::utils::cache::Cache;
+    use crate::utils::examples::Value::*;
+    use crate::utils::examples::*;
+
+    use std::sync::Arc;
+
+    use polars::df;
+    use polars::enable_string_cache;
+    use polars::prelude::*;
+    use pregel_rs::graph_frame::GraphFrame;
+    use pregel_rs::pregel::Column;
+    use pregel_rs::pregel::Column::*;
+    use wikidata_rs::dtype::DataType as WikidataType;
+
+    fn assert(expected: DataFrame, actual: DataFrame) -> Result<(), String> {
+        let count = actual
+            .lazy()
+            .group_by([Column::Subject.as_ref()])
+            .agg([col("labels").first()])
+            .sort([Column::Subject.as_ptr()], Default::default())
+            .select([col("labels").list().len()])
+            .collect()
+            .unwrap();
+        println!("count: {:?}", count);
+        match count == expected {
+            true => Ok(()),
+            false => return Err(String::from("The DataFrames are not equals")),
+        }
+    }
+
+    fn test<T: Literal + Clone>(
+        graph: Result<GraphFrame, String>,
+        result: Vec<u32>,
+        schema: Shape<T>,
+    ) -> Result<(), String> {
+        let graph = match graph {
+            Ok(graph) => graph,
+            Err(error) => return Err(error),
+        };
+        let expected =
+            match DataFrame::new(vec![Series::new(Custom("labels").as_ptr(), result).into()]) {
+                Ok(expected) => expected,
+                Err(_) => return Err(String::from("Error creating the expected DataFrame")),
+            };
+        match PSchema::new(schema).validate(graph) {
+            Ok(actual) => {
+                println!("actual: {:?}", actual);
+                assert(expected, actual)
+            }
+            Err(error) => {
+                println!("{}", error);
+                Err(error.to_string())
+            }
+        }
+    }
+
+    #[test]
+    fn simple_test() -> Result<(), String> {
+        test(paper_graph(), vec![1u32, 1u32], simple_schema())
+    }
+
+    #[test]
+    fn paper_test() -> Result<(), String> {
+        let researcher = TimBernersLee.id().to_string();
+        assert_conforms(
+            paper_graph()?,
+            paper_schema(),
+            &[(researcher.as_str(), &["Researcher"])],
+        )
+    }
+
+    #[test]
+    fn from_shapes_test() -> Result<(), String> {
+        let schema = ShapeAnd::from_shapes(
+            "Researcher",
+            [
+                ("Human", InstanceOf, Human),
+                ("London", BirthPlace, London),
+                ("DateTime", BirthDate, DateTime),
+            ]
+            .into_iter()
+            .map(|(label, predicate, object)| -> Shape<u32> {
+                TripleConstraint::new(label, predicate.id(), NodeConstraint::Value(object.id()))
+                    .into()
+            }),
+        );
+        test(paper_graph(), vec![1u32], schema.into())
+    }
+
+    #[test]
+    fn complex_test() -> Result<(), String> {
+        test(paper_graph(), vec![1u32], complex_schema())
+    }
+
+    #[test]
+    fn reference_test() -> Result<(), String> {
+        test(paper_graph(), vec![1u32], reference_schema())
+    }
+
+    #[test]
+    fn optional_test() -> Result<(), String> {
+        test(paper_graph(), vec![1u32], optional_schema())
+    }
+
+    #[test]
+    fn conditional_test() -> Result<(), String> {
+        test(paper_graph(), vec![1u32, 1u32, 1u32], conditional_schema())
+    }
+
+    #[test]
+    fn any_test() -> Result<(), String> {
+        test(paper_graph(), vec![1u32, 1u32, 1u32], any_schema())
+    }
+
+    #[test]
+    fn not_value_set_test() -> Result<(), String> {
+        test(paper_graph(), vec![1u32], not_value_set_schema())
+    }
+
+    #[test]
+    fn typed_test() -> Result<(), String> {
+        use crate::backends::ntriples::{ImportOptions, NTriples};
+
+        let schema = TripleConstraint::typed("BirthDate", BirthDate.id(), WikidataType::DateTime);
+        let tim = TimBernersLee.id().to_string();
+        assert_conforms(
+            paper_graph()?,
+            schema.into(),
+            &[(tim.as_str(), &["BirthDate"])],
+        )?;
+        // The predicate and the datatype must hold on the same edge.
+        let schema = TripleConstraint::typed("BirthDate", BirthDate.id(), WikidataType::String);
+        assert_conforms(paper_graph()?, schema.into(), &[])?;
+
+        // The graphs imported from N-Triples are checked against their datatype IRIs instead.
+        let content = concat!(
+            "<http://example.org/alan> <http://example.org/birthDate> \"1912-06-23\"^^<http://www.w3.org/2001/XMLSchema#dateTime> .\n",
+            "<http://example.org/bob> <http://example.org/birthDate> \"unknown\" .\n",
+            "<http://example.org/carol> <http://example.org/name> \"1906-12-09\"^^<http://www.w3.org/2001/XMLSchema#dateTime> .\n",
+        );
+        let (edges, _) = NTriples::read_from(
+            std::io::Cursor::new(content),
+            &ImportOptions::default().with_datatypes(),
+            None,
+            None,
+        )?;
+        let graph = GraphFrame::from_edges(edges)
+            .map_err(|_| String::from("Error creating the GraphFrame from edges"))?;
+        let schema = TripleConstraint::typed_iri(
+            "BirthDate",
+            "http://example.org/birthDate",
+            "xsd:dateTime",
+        );
+        assert_conforms(
+            graph,
+            schema.into(),
+            &[("<http://example.org/alan>", &["BirthDate"])],
+        )
+    }
+
+    #[test]
+    fn value_set_test() -> Result<(), String> {
+        let graph = || {
+            let edges = match df![
+                Subject.as_ref() => [London.id(), TimBernersLee.id(), VintCerf.id()],
+                Predicate.as_ref() => [InstanceOf.id(), InstanceOf.id(), InstanceOf.id()],
+                Object.as_ref() => [City.id(), Human.id(), Human.id()],
+            ] {
+                Ok(edges) => edges,
+                Err(_) => return Err(String::from("Error creating the edges DataFrame")),
+            };
+            GraphFrame::from_edges(edges)
+                .map_err(|_| String::from("Error creating the GraphFrame from edges"))
+        };
+        let london = London.id().to_string();
+        assert_conforms(
+            graph()?,
+            value_set_schema(),
+            &[(london.as_str(), &["Settlement"])],
+        )?;
+        // A single-element set behaves as `Value` does, while the empty set matches nothing.
+        let single = TripleConstraint::new(
+            "Settlement",
+            InstanceOf.id(),
+            NodeConstraint::ValueSet(vec![City.id()]),
+        );
+        assert_conforms(
+            graph()?,
+            single.into(),
+            &[(london.as_str(), &["Settlement"])],
+        )?;
+        let empty = TripleConstraint::new(
+            "Settlement",
+            InstanceOf.id(),
+            NodeConstraint::ValueSet(vec![]),
+        );
+        assert_conforms(graph()?, empty.into(), &[])
+    }
+
+    #[test]
+    fn value_set_from_file_test() -> Result<(), String> {
+        let path = std::env::temp_dir().join("pschema-value-set-test.csv");
+        if std::fs::write(
+            &path,
+            "# Allowed classes\nQ5,human\n\nQ11448906,science award\n",
+        )
+        .is_err()
+        {
+            return Err(String::from("Error writing the value set"));
+        }
+        let schema = TripleConstraint::new(
+            "IsHumanOrAward",
+            InstanceOf.id(),
+            NodeConstraint::value_set_from_file(&path)?,
+        );
+        test(paper_graph(), vec![1u32, 1u32, 1u32], schema.into())
+    }
+
+    #[test]
+    fn incoming_reference_test() -> Result<(), String> {
+        test(paper_graph(), vec![1u32], incoming_reference_schema())
+    }
+
+    #[test]
+    fn inverse_triple_constraint_test() -> Result<(), String> {
+        let schema = TripleConstraint::new(
+            "BirthPlaceOf",
+            BirthPlace.id(),
+            NodeConstraint::Value(TimBernersLee.id()),
+        )
+        .with_inverse();
+        let london = London.id().to_string();
+        assert_conforms(
+            paper_graph()?,
+            schema.into(),
+            &[(london.as_str(), &["BirthPlaceOf"])],
+        )
+    }
+
+    #[test]
+    fn cardinality_test() -> Result<(), String> {
+        test(paper_graph(), vec![1u32], cardinality_schema())
+    }
+
+    #[test]
+    fn shape_not_test() -> Result<(), String> {
+        let schema = ShapeNot::new(
+            "NotHuman",
+            TripleConstraint::new("Human", InstanceOf.id(), NodeConstraint::Value(Human.id()))
+                .into(),
+        );
+        let (london, award, cern) = (London.id(), Award.id(), CERN.id());
+        let (london, award, cern) = (london.to_string(), award.to_string(), cern.to_string());
+        assert_conforms(
+            paper_graph()?,
+            schema.into(),
+            &[
+                (london.as_str(), &["NotHuman"]),
+                (award.as_str(), &["NotHuman"]),
+                (cern.as_str(), &["NotHuman"]),
+            ],
+        )
+    }
+
+    #[test]
+    fn exactly_two_test() -> Result<(), String> {
+        // Subjects 1, 2 and 3 received two, one and three awards, respectively, while 4 received
+        // none.
+        let edges = match df![
+            Column::Subject.as_ref() => [1u32, 1, 2, 3, 3, 3, 4],
+            Column::Predicate.as_ref() => [AwardReceived.id(), AwardReceived.id(), AwardReceived.id(), AwardReceived.id(), AwardReceived.id(), AwardReceived.id(), InstanceOf.id()],
+            Column::Object.as_ref() => [10u32, 11, 10, 10, 11, 12, Human.id()],
+        ] {
+            Ok(edges) => edges,
+            Err(_) => return Err(String::from("Error creating the edges DataFrame")),
+        };
+        let graph = GraphFrame::from_edges(edges).map_err(|error| error.to_string())?;
+        let awards = |label, min, max| -> Shape<u32> {
+            Cardinality::new(
+                label,
+                TripleConstraint::new("Award", AwardReceived.id(), NodeConstraint::Any).into(),
+                min,
+                max,
+            )
+            .into()
+        };
+        assert_conforms(
+            graph.clone(),
+            awards("ExactlyTwo", Bound::Inclusive(2), Bound::Inclusive(2)),
+            &[("1", &["ExactlyTwo"])],
+        )?;
+        assert_conforms(
+            graph,
+            awards("NoAwards", Bound::Inclusive(0), Bound::Inclusive(0)),
+            &[("4", &["NoAwards"])],
+        )
+    }
+
+    #[test]
+    fn vprog_to_vprog_test() -> Result<(), String> {
+        test(paper_graph(), vec![1u32], vprog_to_vprog_schema())
+    }
+
+    #[test]
+    fn and_reference_test() -> Result<(), String> {
+        // London has no instance of edge in the paper graph, so the first reference is not
+        // satisfied, while the second one is, as CERN received an award.
+        assert_conforms(paper_graph()?, and_reference_schema(), &[])?;
+        let city = match df![
+            Subject.as_ref() => [London.id()],
+            Predicate.as_ref() => [InstanceOf.id()],
+            Object.as_ref() => [City.id()],
+        ] {
+            Ok(city) => city,
+            Err(_) => return Err(String::from("Error creating the edges DataFrame")),
+        };
+        let edges = match paper_graph()?.edges.vstack(&city) {
+            Ok(edges) => edges,
+            Err(error) => return Err(error.to_string()),
+        };
+        let graph = GraphFrame::from_edges(edges)
+            .map_err(|_| String::from("Error creating the GraphFrame from edges"))?;
+        let tim = TimBernersLee.id().to_string();
+        assert_conforms(
+            graph,
+            and_reference_schema(),
+            &[(tim.as_str(), &["grouping"])],
+        )
+    }
+
+    #[test]
+    fn cardinality_reference_test() -> Result<(), String> {
+        test(paper_graph(), vec![1u32], cardinality_reference_schema())
+    }
+
+    #[test]
+    fn predicates_test() -> Result<(), String> {
+        test(paper_graph(), vec![1u32], predicates_schema())
+    }
+
+    #[test]
+    fn lazyframe_test() -> Result<(), String> {
+        let edges = match paper_graph() {
+            Ok(graph) => graph.edges,
+            Err(error) => return Err(error),
+        };
+        let expected = match DataFrame::new(vec![Series::new(
+            Custom("labels").as_ptr(),
+            vec![1u32, 1u32],
+        )
+        .into()])
+        {
+            Ok(expected) => expected,
+            Err(_) => return Err(String::from("Error creating the expected DataFrame")),
+        };
+        match PSchema::new(simple_schema()).validate_lazyframe(edges.lazy()) {
+            Ok(actual) => assert(expected, actual),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    #[test]
+    fn invalid_lazyframe() -> Result<(), String> {
+        let edges = match df![
+            Column::Subject.as_ref() => [TimBernersLee].iter().map(Value::id).collect::<Vec<_>>(),
+            Column::Object.as_ref() => [Human].iter().map(Value::id).collect::<Vec<_>>(),
+        ] {
+            Ok(edges) => edges,
+            Err(_) => return Err(String::from("Error creating the edges DataFrame")),
+        };
+
+        match PSchema::new(simple_schema()).validate_lazyframe(edges.lazy()) {
+            Ok(_) => Err(String::from("An error should have occurred")),
+            Err(_) => Ok(()),
+        }
+    }
+
+    #[test]
+    fn unmatched_labels_test() -> Result<(), String> {
+        let graph = match paper_graph() {
+            Ok(graph) => graph,
+            Err(error) => return Err(error),
+        };
+        let schema: Shape<u32> = ShapeOr::new(
+            "InstanceOf",
+            vec![
+                TripleConstraint::new("Human", InstanceOf.id(), NodeConstraint::Value(Human.id()))
+                    .into(),
+                TripleConstraint::new(
+                    "Impossible",
+                    InstanceOf.id(),
+                    NodeConstraint::Value(Spain.id()),
+                )
+                .into(),
+            ],
+        )
+        .into();
+        match PSchema::new(schema).unmatched_labels(graph) {
+            Ok(unmatched) if unmatched == vec!["Impossible"] => Ok(()),
+            Ok(unmatched) => Err(format!("Unexpected unmatched labels: {:?}", unmatched)),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    #[test]
+    fn cache_test() -> Result<(), String> {
+        let dir = std::env::temp_dir().join("pschema-cache");
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache = Cache::new(&dir);
+
+        let first = match PSchema::new(paper_schema())
+            .with_cache(cache.clone())
+            .validate(paper_graph()?)
+        {
+            Ok(subset) => subset,
+            Err(error) => return Err(error.to_string()),
+        };
+        let second = match PSchema::new(paper_schema())
+            .with_cache(cache.clone())
+            .validate(paper_graph()?)
+        {
+            Ok(subset) => subset,
+            Err(error) => return Err(error.to_string()),
+        };
+
+        match (cache.hits(), first.height() == second.height()) {
+            (1, true) => Ok(()),
+            (hits, _) => Err(format!("Unexpected number of cache hits: {}", hits)),
+        }
+    }
+
+    #[test]
+    fn cache_options_test() -> Result<(), String> {
+        let dir = std::env::temp_dir().join("pschema-cache-options");
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache = Cache::new(&dir);
+
+        let plain = PSchema::new(paper_schema()).with_cache(cache.clone());
+        let branches = PSchema::new(paper_schema())
+            .with_branch_labels()
+            .with_cache(cache.clone());
+        for pschema in [plain, branches] {
+            if let Err(error) = pschema.validate(paper_graph()?) {
+                return Err(error.to_string());
+            }
+        }
+        // The runs are configured differently, so none of them is read from the cache.
+        match cache.hits() {
+            0 => Ok(()),
+            hits => Err(format!("Unexpected number of cache hits: {}", hits)),
+        }
+    }
+
+    #[test]
+    fn cache_custom_constraint_test() -> Result<(), String> {
+        let dir = std::env::temp_dir().join("pschema-cache-custom");
+        let schema =
+            TripleConstraint::new("Any", InstanceOf.id(), NodeConstraint::custom(|_| true));
+        match PSchema::new(schema.into())
+            .with_cache(Cache::new(&dir))
+            .validate(paper_graph()?)
+        {
+            Ok(_) => Err(String::from("An error should have occurred")),
+            Err(_) => Ok(()),
+        }
+    }
+
+    #[test]
+    fn by_component_test() -> Result<(), String> {
+        let edges = match df![
+            Column::Subject.as_ref() => [TimBernersLee, Award].iter().map(Value::id).collect::<Vec<_>>(),
+            Column::Predicate.as_ref() => [InstanceOf, InstanceOf].iter().map(Value::id).collect::<Vec<_>>(),
+            Column::Object.as_ref() => [Human, ScienceAward].iter().map(Value::id).collect::<Vec<_>>(),
+        ] {
+            Ok(edges) => edges,
+            Err(_) => return Err(String::from("Error creating the edges DataFrame")),
+        };
+        let graph = match GraphFrame::from_edges(edges) {
+            Ok(graph) => graph,
+            Err(_) => return Err(String::from("Error creating the GraphFrame from edges")),
+        };
+        let expected = match DataFrame::new(vec![Series::new(
+            Custom("labels").as_ptr(),
+            vec![1u32, 1u32],
+        )
+        .into()])
+        {
+            Ok(expected) => expected,
+            Err(_) => return Err(String::from("Error creating the expected DataFrame")),
+        };
+        match PSchema::new(conditional_schema()).validate_by_component(graph) {
+            Ok(actual) => assert(expected, actual),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    #[test]
+    fn by_component_order_test() -> Result<(), String> {
+        let mut graph = paper_graph()?;
+        // The edges are given in reverse order and the vertices an attribute, so we can check
+        // both of them survive the partitioning of the graph.
+        let order = (0..graph.edges.height() as u32).rev().collect::<Vec<_>>();
+        if graph
+            .edges
+            .with_column(Series::new("_order".into(), order))
+            .is_err()
+        {
+            return Err(String::from("Error adding the order of the edges"));
+        }
+        graph.vertices = match graph
+            .vertices
+            .lazy()
+            .with_column(lit("entity").alias("entity_type"))
+            .collect()
+        {
+            Ok(vertices) => vertices,
+            Err(_) => return Err(String::from("Error creating the vertices DataFrame")),
+        };
+        let orders = |subset: Result<DataFrame, PSchemaError>| match subset {
+            Ok(subset) if subset.column("entity_type").is_err() => {
+                Err(String::from("The vertex attributes were not preserved"))
+            }
+            Ok(subset) => match subset
+                .column("_order")
+                .and_then(|order| order.u32().cloned())
+            {
+                Ok(order) => Ok(order.into_iter().collect::<Vec<_>>()),
+                Err(error) => Err(error.to_string()),
+            },
+            Err(error) => Err(error.to_string()),
+        };
+        let expected = orders(PSchema::new(simple_schema()).validate(graph.clone()))?;
+        let actual = orders(PSchema::new(simple_schema()).validate_by_component(graph.clone()))?;
+        if actual != expected {
+            return Err(format!("Unexpected order: {:?}", actual));
+        }
+        // The maximum number of edges is checked against the whole graph.
+        match PSchema::new(simple_schema())
+            .with_max_edges(graph.edges.height() - 1)
+            .validate_by_component(graph)
+        {
+            Ok(_) => Err(String::from("The maximum number of edges was ignored")),
+            Err(_) => Ok(()),
+        }
+    }
+
+    #[test]
+    fn custom_aggregate_test() -> Result<(), String> {
+        // Both of the humans conform to the schema, unless the captured label is discarded
+        // when aggregating the messages, so only the one born in London is left.
+        let schema = ShapeOr::new(
+            "Person",
+            vec![
+                TripleConstraint::new("Human", InstanceOf.id(), NodeConstraint::Value(Human.id()))
+                    .into(),
+                TripleConstraint::new(
+                    "London",
+                    BirthPlace.id(),
+                    NodeConstraint::Value(London.id()),
+                )
+                .into(),
+            ],
+        );
+        let discarded = "Human";
+        let pschema = PSchema::new(schema.into()).with_custom_aggregate(move || {
+            Column::msg(None)
+                .drop_nulls()
+                .filter(Column::msg(None).neq(lit(discarded)))
+        });
+        let tim = TimBernersLee.id().to_string();
+        match pschema.validate(paper_graph()?) {
+            Ok(subset) => {
+                let subjects = subset
+                    .column(Column::Subject.as_ref())
+                    .map_err(|error| error.to_string())?
+                    .unique()
+                    .map_err(|error| error.to_string())?;
+                match subjects.len() == 1
+                    && subjects.get(0).ok().map(|subject| subject.to_string()) == Some(tim)
+                {
+                    true => Ok(()),
+                    false => Err(format!("Unexpected subjects: {:?}", subjects)),
+                }
+            }
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    #[test]
+    fn reified_statements_test() -> Result<(), String> {
+        enable_string_cache();
+        let categorical = DataType::Categorical(None, CategoricalOrdering::Lexical);
+        let edges = match df![
+            Column::Subject.as_ref() => [
+                "<http://www.wikidata.org/entity/Q80>",
+                "<http://www.wikidata.org/entity/statement/Q80-1>",
+            ],
+            Column::Predicate.as_ref() => [
+                "<http://www.wikidata.org/prop/P31>",
+                "<http://www.wikidata.org/prop/statement/P31>",
+            ],
+            Column::Object.as_ref() => [
+                "<http://www.wikidata.org/entity/statement/Q80-1>",
+                "<http://www.wikidata.org/entity/Q5>",
+            ],
+        ]
+        .and_then(|edges| edges.lazy().select([all().cast(categorical)]).collect())
+        {
+            Ok(edges) => edges,
+            Err(_) => return Err(String::from("Error creating the edges DataFrame")),
+        };
+        let graph = match GraphFrame::from_edges(edges) {
+            Ok(graph) => graph,
+            Err(_) => return Err(String::from("Error creating the GraphFrame from edges")),
+        };
+        let schema: Shape<&str> = TripleConstraint::new(
+            "IsHuman",
+            "<http://www.wikidata.org/prop/direct/P31>",
+            NodeConstraint::Value("<http://www.wikidata.org/entity/Q5>"),
+        )
+        .into();
+        let expected =
+            match DataFrame::new(vec![
+                Series::new(Custom("labels").as_ptr(), vec![1u32]).into()
+            ]) {
+                Ok(expected) => expected,
+                Err(_) => return Err(String::from("Error creating the expected DataFrame")),
+            };
+        match PSchema::new(schema)
+            .with_reified_statements()
+            .validate(graph)
+        {
+            Ok(actual) => assert(expected, actual),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    #[test]
+    fn reified_statements_columns_test() -> Result<(), String> {
+        let edges = match df![
+            Column::Subject.as_ref() => [
+                "<http://www.wikidata.org/entity/Q80>",
+                "<http://www.wikidata.org/entity/statement/Q80-1>",
+            ],
+            Column::Predicate.as_ref() => [
+                "<http://www.wikidata.org/prop/P31>",
+                "<http://www.wikidata.org/prop/statement/P31>",
+            ],
+            Column::Object.as_ref() => [
+                "<http://www.wikidata.org/entity/statement/Q80-1>",
+                "<http://www.wikidata.org/entity/Q5>",
+            ],
+            "_order" => [0u32, 1u32],
+            "object_kind" => ["iri", "iri"],
+        ] {
+            Ok(edges) => edges,
+            Err(_) => return Err(String::from("Error creating the edges DataFrame")),
+        };
+        let graph = match GraphFrame::from_edges(edges) {
+            Ok(graph) => graph,
+            Err(_) => return Err(String::from("Error creating the GraphFrame from edges")),
+        };
+        let schema: Shape<&str> = TripleConstraint::new(
+            "IsHuman",
+            "<http://www.wikidata.org/prop/direct/P31>",
+            NodeConstraint::Value("<http://www.wikidata.org/entity/Q5>"),
+        )
+        .into();
+        // The flattened edge keeps the datatype of the columns and the order of its value.
+        let subset = match PSchema::new(schema)
+            .with_reified_statements()
+            .validate(graph)
+        {
+            Ok(subset) => subset,
+            Err(error) => return Err(error.to_string()),
+        };
+        let orders = match subset
+            .column("_order")
+            .and_then(|order| order.u32().cloned())
+        {
+            Ok(orders) => orders.into_iter().collect::<Vec<_>>(),
+            Err(error) => return Err(error.to_string()),
+        };
+        match (
+            subset
+                .column(Subject.as_ref())
+                .map(|subjects| subjects.dtype().clone()),
+            subset.column("object_kind").is_ok(),
+            orders,
+        ) {
+            (Ok(DataType::String), true, orders) if orders == vec![Some(0), Some(1)] => {}
+            _ => return Err(format!("Unexpected subset: {:?}", subset)),
+        }
+        // The statements cannot be told apart when the predicates are integer ids.
+        match PSchema::new(simple_schema())
+            .with_reified_statements()
+            .validate(paper_graph()?)
+        {
+            Err(PSchemaError::Graph(_)) => Ok(()),
+            result => Err(format!("Unexpected result: {:?}", result)),
+        }
+    }
+
+    #[test]
+    fn conformance_ratio_test() -> Result<(), String> {
+        match PSchema::new(paper_schema()).conformance_ratio(paper_graph()?, InstanceOf.id()) {
+            Ok(Some(ratio)) if (ratio - 1f64 / 3f64).abs() < f64::EPSILON => Ok(()),
+            Ok(ratio) => Err(format!("Unexpected conformance ratio: {:?}", ratio)),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    #[test]
+    fn message_dtype_test() -> Result<(), String> {
+        let expected =
+            match DataFrame::new(vec![
+                Series::new(Custom("labels").as_ptr(), vec![1u32]).into()
+            ]) {
+                Ok(expected) => expected,
+                Err(_) => return Err(String::from("Error creating the expected DataFrame")),
+            };
+        match PSchema::new(complex_schema())
+            .with_message_dtype(DataType::String)
+            .validate(paper_graph()?)
+        {
+            Ok(actual) => assert(expected, actual),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    #[test]
+    fn integer_message_dtype_test() -> Result<(), String> {
+        let labels = |dtype: DataType| -> Result<DataFrame, String> {
+            let subset = PSchema::new(complex_schema())
+                .with_message_dtype(dtype)
+                .validate(paper_graph()?)
+                .map_err(|error| error.to_string())?;
+            subset
                 .lazy()
-                .filter(col(Column::Custom("labels").as_ref()).list().len().gt(0))
-                .left_join(
-                    graph.edges.lazy(),
-                    Column::VertexId.as_ref(),
-                    Column::Subject.as_ref(),
-                )
-                .select(&[
-                    col(Column::VertexId.as_ref()).alias(Column::Subject.as_ref()),
-                    col(Column::Predicate.as_ref()),
-                    col(Column::Object.as_ref()),
-                    col(Column::Custom("labels").as_ref()),
+                .select([
+                    col(Column::Subject.as_ref()),
+                    col(Custom("labels").as_ref()).cast(DataType::List(Box::new(DataType::String))),
                 ])
-                .collect(),
-            Err(error) => Err(error),
+                .unique(None, UniqueKeepStrategy::Any)
+                .sort([Column::Subject.as_ref()], Default::default())
+                .collect()
+                .map_err(|error| error.to_string())
+        };
+        let expected = labels(DataType::Categorical(None, CategoricalOrdering::Lexical))?;
+        for dtype in [DataType::UInt16, DataType::Int32] {
+            let actual = labels(dtype.clone())?;
+            if !actual.equals_missing(&expected) || expected.height() == 0 {
+                return Err(format!("Unexpected labels with {}: {:?}", dtype, actual));
+            }
         }
+        Ok(())
     }
 
-    fn initial_message() -> Expr {
-        lit(NULL)
+    #[test]
+    fn invalid_message_dtype() -> Result<(), String> {
+        match PSchema::new(complex_schema())
+            .with_message_dtype(DataType::Float64)
+            .validate(paper_graph()?)
+        {
+            Ok(_) => Err(String::from("An error should have occurred")),
+            Err(_) => Ok(()),
+        }
     }
 
-    /// The function sends messages to the vertices in the graph. It takes a
-    /// `ShapeTreeItem` iterator as an argument and returns an `Expr` that
-    /// represents the messages to be sent. Depending on the type of the node
-    /// being sent, the function sends a different message to the vertex. Thus,
-    /// a different validation function is called for each node.
-    ///
-    /// Arguments:
-    ///
-    /// * `iterator`: The `iterator` parameter is a mutable reference to a
-    /// `ShapeTreeItem` iterator. It is used to iterate over the nodes in the
-    /// `ShapeTree` and send messages to the vertices in the graph.
-    ///
-    /// Returns:
-    ///
-    /// The function `send_messages` returns an `Expr` that represents the
-    /// messages to be sent.
-    fn send_messages(iterator: &mut dyn Iterator<Item = ShapeTreeItem<T>>) -> Expr {
-        let mut messages = lit(NULL);
-        if let Some(schema) = iterator.next() {
-            for shape in schema {
-                messages = match shape {
-                    Shape::TripleConstraint(shape) => shape.validate(messages),
-                    Shape::ShapeReference(shape) => shape.validate(messages),
-                    Shape::ShapeAnd(shape) => shape.validate(messages),
-                    Shape::ShapeOr(shape) => shape.validate(messages),
-                    Shape::Cardinality(shape) => shape.validate(messages),
-                }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn export_with_schema_test() -> Result<(), String> {
+        use crate::backends::parquet::Parquet;
+
+        let subset_path = std::env::temp_dir().join("pschema-provenance.parquet");
+        let schema_path = std::env::temp_dir().join("pschema-provenance.json");
+        PSchema::new(paper_schema()).export_with_schema::<Parquet>(
+            paper_graph()?,
+            subset_path.to_str().unwrap(),
+            schema_path.to_str().unwrap(),
+        )?;
+
+        let schema = match std::fs::read_to_string(&schema_path) {
+            Ok(schema) => schema,
+            Err(_) => return Err(String::from("Error reading the schema file")),
+        };
+        match (
+            subset_path.exists(),
+            serde_json::from_str::<Shape<u32>>(&schema),
+        ) {
+            (true, Ok(schema)) if schema == paper_schema() => Ok(()),
+            _ => Err(String::from("The schema was not exported properly")),
+        }
+    }
+
+    #[test]
+    fn manifest_test() -> Result<(), String> {
+        let path = std::env::temp_dir().join("pschema-manifest.json");
+        if let Err(error) = PSchema::new(paper_schema()).validate_with_manifest(
+            paper_graph()?,
+            "data/\"paper\".nt",
+            &path,
+        ) {
+            return Err(error.to_string());
+        }
+        let manifest = std::fs::read_to_string(&path).map_err(|error| error.to_string())?;
+        let schema_hash = format!(
+            "\"schema_hash\": \"{:016x}\"",
+            Cache::schema_key(&paper_schema())
+        );
+        match manifest.contains("\"source\": \"data/\\\"paper\\\".nt\"")
+            && manifest.contains(&schema_hash)
+            && manifest.contains("\"matches\": 1,")
+        {
+            true => Ok(()),
+            false => Err(format!("Unexpected manifest: {}", manifest)),
+        }
+    }
+
+    #[test]
+    fn json_string_test() {
+        assert_eq!(r#""a\"b\\c\nd\u0001""#, json_string("a\"b\\c\nd\u{1}"))
+    }
+
+    #[test]
+    fn validate_dir_test() -> Result<(), String> {
+        use crate::backends::ntriples::NTriples;
+
+        let dir = std::env::temp_dir().join("pschema-validate-dir");
+        let _ = std::fs::remove_dir_all(&dir);
+        let human = |entity: &str| {
+            format!(
+                "<http://www.wikidata.org/entity/{}> <http://www.wikidata.org/prop/direct/P31> <http://www.wikidata.org/entity/Q5> .\n",
+                entity
+            )
+        };
+        let written = std::fs::create_dir_all(&dir)
+            .and_then(|_| std::fs::write(dir.join("Q80.nt"), human("Q80")))
+            .and_then(|_| std::fs::write(dir.join("Q92743.nt"), human("Q92743")))
+            .and_then(|_| std::fs::write(dir.join("empty.nt"), ""))
+            .and_then(|_| std::fs::write(dir.join("ignored.ttl"), human("Q42")));
+        if written.is_err() {
+            return Err(String::from("Error writing the fixture files"));
+        }
+
+        let schema: Shape<&str> = TripleConstraint::new(
+            "Human",
+            "<http://www.wikidata.org/prop/direct/P31>",
+            NodeConstraint::Value("<http://www.wikidata.org/entity/Q5>"),
+        )
+        .into();
+        let report = match PSchema::new(schema).validate_dir::<NTriples, _>(&dir, "*.nt") {
+            Ok(report) => report,
+            Err(error) => return Err(error.to_string()),
+        };
+        let errors = match report.column("error") {
+            Ok(errors) => errors.len() - errors.null_count(),
+            Err(_) => return Err(String::from("The error column is missing")),
+        };
+        match (report.height(), errors) {
+            (3, 1) => Ok(()),
+            _ => Err(format!("Unexpected report: {:?}", report)),
+        }
+    }
+
+    #[test]
+    fn same_as_test() -> Result<(), String> {
+        let graph = || {
+            let edges = match df![
+                Column::Subject.as_ref() => [TimBernersLee, VintCerf, TimBernersLee].iter().map(Value::id).collect::<Vec<_>>(),
+                Column::Predicate.as_ref() => [InstanceOf, BirthPlace, SameAs].iter().map(Value::id).collect::<Vec<_>>(),
+                Column::Object.as_ref() => [Human, London, VintCerf].iter().map(Value::id).collect::<Vec<_>>(),
+            ] {
+                Ok(edges) => edges,
+                Err(_) => return Err(String::from("Error creating the edges DataFrame")),
+            };
+            match GraphFrame::from_edges(edges) {
+                Ok(graph) => Ok(graph),
+                Err(_) => Err(String::from("Error creating the GraphFrame from edges")),
             }
+        };
+
+        let schema = || -> Shape<u32> {
+            ShapeAnd::new(
+                "Researcher",
+                vec![
+                    TripleConstraint::new(
+                        "Human",
+                        InstanceOf.id(),
+                        NodeConstraint::Value(Human.id()),
+                    )
+                    .into(),
+                    TripleConstraint::new(
+                        "London",
+                        BirthPlace.id(),
+                        NodeConstraint::Value(London.id()),
+                    )
+                    .into(),
+                ],
+            )
+            .into()
+        };
+
+        let unmerged = match PSchema::new(schema()).validate(graph()?) {
+            Ok(subset) => subset.height(),
+            Err(error) => return Err(error.to_string()),
+        };
+        let merged = match PSchema::new(schema())
+            .with_sameas_merge(SameAs.id())
+            .validate(graph()?)
+        {
+            Ok(subset) => subset,
+            Err(error) => return Err(error.to_string()),
+        };
+        match (unmerged, merged.height()) {
+            (0, 2) => Ok(()),
+            _ => Err(format!("Unexpected merged subset: {:?}", merged)),
         }
-        messages.cast(DataType::Categorical(None, CategoricalOrdering::Lexical))
     }
 
-    /// The function returns an expression that aggregates messages by exploding a
-    /// column and dropping NULL values.
-    ///
-    /// Returns:
-    ///
-    /// The function `aggregate_messages()` returns an expression that selects the `msg`
-    /// column from a DataFrame, explodes the column (i.e., creates a new row for each
-    /// element in the column), and drops any rows that have NULL values in the
-    /// resulting column.
-    fn aggregate_messages() -> Expr {
-        Column::msg(None).drop_nulls()
+    #[test]
+    fn max_edges_test() -> Result<(), String> {
+        match PSchema::new(simple_schema())
+            .with_max_edges(5)
+            .validate(paper_graph()?)
+        {
+            Ok(_) => Err(String::from("The graph should exceed the maximum of edges")),
+            Err(_) => Ok(()),
+        }
     }
 
-    fn v_prog() -> Expr {
-        Column::msg(None)
+    #[test]
+    fn validate_grouped_test() -> Result<(), String> {
+        let groups = match PSchema::new(conditional_schema()).validate_grouped(paper_graph()?) {
+            Ok(groups) => groups,
+            Err(error) => return Err(error.to_string()),
+        };
+        let subjects = |label: &str| -> Result<Vec<u32>, String> {
+            let group = match groups.get(label) {
+                Some(group) => group,
+                None => return Err(format!("Missing group {}", label)),
+            };
+            let mut subjects = match group
+                .column(Column::Subject.as_ref())
+                .and_then(|column| column.as_materialized_series().u32().cloned())
+            {
+                Ok(subjects) => subjects.into_iter().flatten().collect::<Vec<_>>(),
+                Err(error) => return Err(error.to_string()),
+            };
+            subjects.sort();
+            subjects.dedup();
+            Ok(subjects)
+        };
+
+        let mut humans = vec![TimBernersLee.id(), VintCerf.id()];
+        humans.sort();
+        match (groups.len(), subjects("Human")?, subjects("ScienceAward")?) {
+            (2, actual, awards) if actual == humans && awards == vec![Award.id()] => Ok(()),
+            _ => Err(format!("Unexpected groups: {:?}", groups)),
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::pschema::PSchema;
-    use crate::shape::shex::Shape;
-    use crate::utils::examples::Value::*;
-    use crate::utils::examples::*;
+    #[test]
+    fn too_many_labels_test() -> Result<(), String> {
+        // The five labels of the schema get the identifiers from zero to four.
+        let pschema = PSchema::new(complex_schema());
+        if let Err(error) = pschema.check_max_label(4) {
+            return Err(error.to_string());
+        }
+        match pschema.check_max_label(3) {
+            Err(PSchemaError::Schema(ShapeError::TooManyLabels(5, 4))) => (),
+            Err(error) => return Err(format!("Unexpected error: {}", error)),
+            Ok(_) => return Err(String::from("The labels should not have fit")),
+        }
+        // The limit is derived from the datatype of the messages, if they hold codes.
+        match (
+            pschema.max_message_label(),
+            pschema
+                .clone()
+                .with_message_dtype(DataType::UInt16)
+                .max_message_label(),
+            pschema
+                .with_message_dtype(DataType::Int64)
+                .max_message_label(),
+        ) {
+            (None, Some(max), Some(wide)) if max == u16::MAX as usize && wide == max => Ok(()),
+            limits => Err(format!("Unexpected limits: {:?}", limits)),
+        }
+    }
 
-    use polars::df;
-    use polars::prelude::*;
-    use pregel_rs::graph_frame::GraphFrame;
-    use pregel_rs::pregel::Column;
-    use pregel_rs::pregel::Column::*;
+    #[test]
+    fn superstep_timeout_test() -> Result<(), String> {
+        // Matching each of the objects takes a while, so the only superstep lasts far longer than
+        // the budget, whereas a generous budget lets the very same validation finish.
+        let schema = || -> Shape<u32> {
+            TripleConstraint::new(
+                "Slow",
+                InstanceOf.id(),
+                NodeConstraint::custom(|_| {
+                    std::thread::sleep(std::time::Duration::from_millis(20));
+                    true
+                }),
+            )
+            .into()
+        };
+        let budget = std::time::Duration::from_millis(10);
+        match PSchema::new(schema())
+            .with_superstep_timeout(budget)
+            .validate(paper_graph()?)
+        {
+            Err(PSchemaError::Timeout(timeout)) if timeout == budget => (),
+            Err(error) => return Err(error.to_string()),
+            Ok(_) => return Err(String::from("The validation should have timed out")),
+        }
+        match PSchema::new(schema())
+            .with_superstep_timeout(std::time::Duration::from_secs(600))
+            .validate(paper_graph()?)
+        {
+            Ok(subset) if subset.height() > 0 => Ok(()),
+            Ok(_) => Err(String::from("Every subject should have conformed")),
+            Err(error) => Err(error.to_string()),
+        }
+    }
 
-    fn assert(expected: DataFrame, actual: DataFrame) -> Result<(), String> {
-        let count = actual
+    #[test]
+    fn validate_nodes_test() -> Result<(), String> {
+        let nodes = match PSchema::new(simple_schema()).validate_nodes(paper_graph()?) {
+            Ok(nodes) => nodes,
+            Err(error) => return Err(error.to_string()),
+        };
+        if nodes.width() != 2
+            || nodes.column(Predicate.as_ref()).is_ok()
+            || nodes.column(Object.as_ref()).is_ok()
+        {
+            return Err(format!(
+                "Unexpected columns: {:?}",
+                nodes.get_column_names()
+            ));
+        }
+        let labels = match nodes
             .lazy()
-            .group_by([Column::Subject.as_ref()])
-            .agg([col("labels").first()])
-            .sort([Column::Subject.as_ptr()], Default::default())
-            .select([col("labels").list().len()])
+            .explode([col(Custom("labels").as_ref())])
+            .select([concat_str(
+                [
+                    col(Subject.as_ref()).cast(DataType::String),
+                    col(Custom("labels").as_ref()).cast(DataType::String),
+                ],
+                " ",
+                true,
+            )
+            .alias("node")])
             .collect()
-            .unwrap();
-        println!("count: {:?}", count);
-        match count == expected {
+        {
+            Ok(labels) => labels,
+            Err(error) => return Err(error.to_string()),
+        };
+        let mut actual = match labels.column("node") {
+            Ok(column) => column
+                .str()
+                .unwrap()
+                .into_iter()
+                .flatten()
+                .map(String::from)
+                .collect::<Vec<_>>(),
+            Err(error) => return Err(error.to_string()),
+        };
+        actual.sort();
+        let mut expected = [TimBernersLee, VintCerf]
+            .iter()
+            .map(|value| format!("{} IsHuman", value.id()))
+            .collect::<Vec<_>>();
+        expected.sort();
+        match actual == expected {
             true => Ok(()),
-            false => return Err(String::from("The DataFrames are not equals")),
+            false => Err(format!("Unexpected nodes: {:?}", actual)),
         }
     }
 
-    fn test<T: Literal + Clone>(
-        graph: Result<GraphFrame, String>,
-        result: Vec<u32>,
-        schema: Shape<T>,
-    ) -> Result<(), String> {
-        let graph = match graph {
-            Ok(graph) => graph,
-            Err(error) => return Err(error),
+    #[test]
+    fn timeout_test() -> Result<(), String> {
+        match PSchema::new(paper_schema())
+            .with_timeout(std::time::Duration::from_nanos(1))
+            .validate(paper_graph()?)
+        {
+            Ok(_) => return Err(String::from("The validation should have timed out")),
+            Err(PSchemaError::Timeout(_)) => (),
+            Err(error) => return Err(format!("Unexpected error: {}", error)),
+        }
+        match PSchema::new(paper_schema())
+            .with_timeout(std::time::Duration::from_nanos(1))
+            .validate_nodes(paper_graph()?)
+        {
+            Ok(_) => return Err(String::from("The validation should have timed out")),
+            Err(PSchemaError::Timeout(_)) => (),
+            Err(error) => return Err(format!("Unexpected error: {}", error)),
+        }
+        // The rest of the validating methods are time-boxed as well, as a whole.
+        let timeout = std::time::Duration::from_nanos(1);
+        let pschema = PSchema::new(reference_schema()).with_timeout(timeout);
+        let results = [
+            pschema
+                .clone()
+                .validate_delta(paper_graph()?, &DataFrame::empty())
+                .map(|_| ()),
+            pschema.clone().validate_grouped(paper_graph()?).map(|_| ()),
+            pschema.clone().unmatched_labels(paper_graph()?).map(|_| ()),
+            pschema.reference_stats(&paper_graph()?).map(|_| ()),
+        ];
+        match results.into_iter().find_map(|result| match result {
+            Err(PSchemaError::Timeout(budget)) if budget == timeout => None,
+            result => Some(result),
+        }) {
+            Some(result) => Err(format!("Unexpected result: {:?}", result)),
+            None => Ok(()),
+        }
+    }
+
+    #[test]
+    fn caller_managed_string_cache_test() -> Result<(), String> {
+        let _holder = polars::StringCacheHolder::hold();
+        test(paper_graph(), vec![1u32, 1u32], simple_schema())
+    }
+
+    #[test]
+    fn predicate_dtype_test() -> Result<(), String> {
+        let schema: Shape<&str> = TripleConstraint::new(
+            "Human",
+            "<http://www.wikidata.org/prop/direct/P31>",
+            NodeConstraint::Value("<http://www.wikidata.org/entity/Q5>"),
+        )
+        .into();
+        match PSchema::new(schema).validate(paper_graph()?) {
+            Ok(_) => Err(String::from("The predicate types should not match")),
+            Err(PSchemaError::Polars(PolarsError::SchemaMismatch(_))) => Ok(()),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    #[test]
+    fn validate_delta_test() -> Result<(), String> {
+        let previous = match PSchema::new(simple_schema()).validate(paper_graph()?) {
+            Ok(previous) => previous,
+            Err(error) => return Err(error.to_string()),
         };
-        let expected =
-            match DataFrame::new(vec![Series::new(Custom("labels").as_ptr(), result).into()]) {
-                Ok(expected) => expected,
-                Err(_) => return Err(String::from("Error creating the expected DataFrame")),
+        let delta =
+            match PSchema::new(conditional_schema()).validate_delta(paper_graph()?, &previous) {
+                Ok(delta) => delta,
+                Err(error) => return Err(error.to_string()),
             };
-        match PSchema::new(schema).validate(graph) {
-            Ok(actual) => {
-                println!("actual: {:?}", actual);
-                assert(expected, actual)
-            }
-            Err(error) => {
-                println!("{}", error);
-                Err(error.to_string())
-            }
+        let subjects = match delta
+            .column(Column::Subject.as_ref())
+            .and_then(|column| column.as_materialized_series().u32().cloned())
+        {
+            Ok(subjects) => subjects.into_iter().flatten().collect::<Vec<_>>(),
+            Err(error) => return Err(error.to_string()),
+        };
+        match subjects.iter().all(|subject| *subject == Award.id()) && !subjects.is_empty() {
+            true => Ok(()),
+            false => Err(format!("Unexpected delta: {:?}", delta)),
         }
     }
 
     #[test]
-    fn simple_test() -> Result<(), String> {
-        test(paper_graph(), vec![1u32, 1u32], simple_schema())
+    fn compiled_messages_test() -> Result<(), String> {
+        enable_string_cache();
+        let graph = paper_graph()?;
+        let pschema = PSchema::new(complex_schema());
+        let encoding = Labels::new(DataType::Categorical(None, CategoricalOrdering::Lexical));
+        let compiled = pschema
+            .supersteps(&graph, &encoding, false)
+            .map_err(|error| error.to_string())?;
+        // The messages are built right before each of the supersteps instead, as they used to be.
+        let iterations = ShapeTree::new(complex_schema())
+            .iterations()
+            .map_err(|error| error.to_string())?;
+        let mut subjects = ShapeTree::new(complex_schema()).into_iter();
+        let mut objects = ShapeTree::new(complex_schema()).into_iter();
+        let uncompiled = pschema
+            .pregel(
+                &graph,
+                iterations,
+                || PSchema::<u32>::send_messages(&mut subjects, &encoding),
+                || PSchema::<u32>::send_incoming_messages(&mut objects, &encoding),
+                &encoding,
+            )
+            .map_err(|error| error.to_string())?;
+        let sort = |labels: DataFrame| {
+            labels
+                .sort([VertexId.as_ref()], SortMultipleOptions::default())
+                .map_err(|error| error.to_string())
+        };
+        match sort(compiled)?.equals_missing(&sort(uncompiled)?) {
+            true => Ok(()),
+            false => Err(String::from("The compiled messages changed the labels")),
+        }
     }
 
     #[test]
-    fn paper_test() -> Result<(), String> {
-        test(paper_graph(), vec![1u32], paper_schema())
+    fn reused_messages_test() -> Result<(), String> {
+        let encoding = Labels::new(DataType::Categorical(None, CategoricalOrdering::Lexical));
+        let pschema = PSchema::new(complex_schema());
+        let compiled = pschema.messages(&encoding);
+        // The clones share the compiled messages, which are the ones built for the schema.
+        if !Arc::ptr_eq(&compiled, &pschema.clone().messages(&encoding))
+            || *compiled != PSchema::compile(complex_schema(), &encoding)
+        {
+            return Err(String::from("The compiled messages were not reused"));
+        }
+        // Changing the datatype of the messages discards them, as their encoding changes.
+        let encoding = Labels::new(DataType::String);
+        match Arc::ptr_eq(
+            &compiled,
+            &pschema
+                .with_message_dtype(DataType::String)
+                .messages(&encoding),
+        ) {
+            true => Err(String::from("The compiled messages were not discarded")),
+            false => Ok(()),
+        }
     }
 
     #[test]
-    fn complex_test() -> Result<(), String> {
-        test(paper_graph(), vec![1u32], complex_schema())
+    fn branch_labels_test() -> Result<(), String> {
+        let subset = PSchema::new(conditional_schema())
+            .with_branch_labels()
+            .validate(paper_graph()?)
+            .map_err(|error| error.to_string())?;
+        let labels = subset
+            .lazy()
+            .filter(col(Subject.as_ref()).eq(lit(TimBernersLee.id())))
+            .select([col(Custom("labels").as_ref())
+                .first()
+                .cast(DataType::List(Box::new(DataType::String)))])
+            .explode([col(Custom("labels").as_ref())])
+            .collect()
+            .map_err(|error| error.to_string())?;
+        let mut labels = labels
+            .column(Custom("labels").as_ref())
+            .map_err(|error| error.to_string())?
+            .str()
+            .map_err(|error| error.to_string())?
+            .into_iter()
+            .flatten()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        labels.sort();
+        match labels == ["Human", "InstanceOf"] {
+            true => Ok(()),
+            false => Err(format!("Unexpected labels: {:?}", labels)),
+        }
     }
 
     #[test]
-    fn reference_test() -> Result<(), String> {
-        test(paper_graph(), vec![1u32], reference_schema())
+    fn u64_ids_test() -> Result<(), String> {
+        // The ids exceed the `u32` range, so they would collide if they were truncated.
+        let (alan, bob) = (u32::MAX as u64 + 1, 2 * u32::MAX as u64 + 2);
+        let human = u32::MAX as u64 + 5;
+        let edges = match df![
+            Column::Subject.as_ref() => [alan, bob],
+            Column::Predicate.as_ref() => [31u64, 31],
+            Column::Object.as_ref() => [human, 1],
+        ] {
+            Ok(edges) => edges,
+            Err(_) => return Err(String::from("Error creating the edges DataFrame")),
+        };
+        let graph = GraphFrame::from_edges(edges).map_err(|error| error.to_string())?;
+        let alan = alan.to_string();
+        assert_conforms(
+            graph,
+            TripleConstraint::new("Human", 31u64, NodeConstraint::Value(human)).into(),
+            &[(alan.as_str(), &["Human"])],
+        )
     }
 
     #[test]
-    fn optional_test() -> Result<(), String> {
-        test(paper_graph(), vec![1u32], optional_schema())
+    fn reference_stats_test() -> Result<(), String> {
+        let stats = PSchema::new(complex_schema())
+            .reference_stats(&paper_graph()?)
+            .map_err(|error| error.to_string())?;
+        let expected = ReferenceStats {
+            label: "BirthUnitedKingdom",
+            candidates: 1,
+            resolved: 1,
+        };
+        match stats.as_slice() {
+            [stats] if stats == &expected => Ok(()),
+            stats => Err(format!("Unexpected statistics: {:?}", stats)),
+        }
     }
 
     #[test]
-    fn conditional_test() -> Result<(), String> {
-        test(paper_graph(), vec![1u32, 1u32, 1u32], conditional_schema())
+    fn subclass_test() -> Result<(), String> {
+        // Instances 1, 2 and 3 belong to the leaf class 10, the middle class 11 and the root class
+        // 12, respectively, while 4 belongs to the unrelated class 20.
+        let (instance_of, subclass_of) = (31u32, 279u32);
+        let edges = match df![
+            Column::Subject.as_ref() => [1u32, 2, 3, 4, 10, 11],
+            Column::Predicate.as_ref() => [instance_of, instance_of, instance_of, instance_of, subclass_of, subclass_of],
+            Column::Object.as_ref() => [10u32, 11, 12, 20, 11, 12],
+        ] {
+            Ok(edges) => edges,
+            Err(_) => return Err(String::from("Error creating the edges DataFrame")),
+        };
+        let schema = SubclassConstraint::new("Instance", instance_of, subclass_of, 12u32, 2);
+        test(
+            GraphFrame::from_edges(edges).map_err(|error| error.to_string()),
+            vec![1u32, 1u32, 1u32],
+            schema.into(),
+        )
     }
 
     #[test]
-    fn any_test() -> Result<(), String> {
-        test(paper_graph(), vec![1u32, 1u32, 1u32], any_schema())
+    fn convergence_check_test() -> Result<(), String> {
+        // Both humans know each other, so there is a reference cycle in the data.
+        let edges = match df![
+            Column::Subject.as_ref() => [1u32, 2, 1, 2],
+            Column::Predicate.as_ref() => [InstanceOf.id(), InstanceOf.id(), 3, 3],
+            Column::Object.as_ref() => [Human.id(), Human.id(), 2, 1],
+        ] {
+            Ok(edges) => edges,
+            Err(_) => return Err(String::from("Error creating the edges DataFrame")),
+        };
+        let graph = GraphFrame::from_edges(edges).map_err(|error| error.to_string())?;
+        let human =
+            TripleConstraint::new("Human", InstanceOf.id(), NodeConstraint::Value(Human.id()));
+        // The labels of a schema with a single level do not depend on those of the neighbours.
+        if let Err(error) = PSchema::new(human.clone().into())
+            .with_convergence_check()
+            .validate(graph.clone())
+        {
+            return Err(error.to_string());
+        }
+        // Re-running the reference changes the labels, as they keep on going around the cycle.
+        let schema = ShapeReference::new("KnowsHuman", 3, human.into());
+        match PSchema::new(schema.into())
+            .with_convergence_check()
+            .validate(graph)
+        {
+            Ok(_) => Err(String::from("The labels should not have converged")),
+            Err(PSchemaError::NotConverged(2)) => Ok(()),
+            Err(error) => Err(error.to_string()),
+        }
     }
 
     #[test]
-    fn cardinality_test() -> Result<(), String> {
-        test(paper_graph(), vec![1u32], cardinality_schema())
+    fn distinct_objects_test() -> Result<(), String> {
+        // Both proteins have three annotations, but those of the first one are all the same.
+        let edges = match df![
+            Column::Subject.as_ref() => [1u32, 1, 1, 2, 2, 2],
+            Column::Predicate.as_ref() => [7u32, 7, 7, 7, 7, 7],
+            Column::Object.as_ref() => [100u32, 100, 100, 100, 101, 102],
+        ] {
+            Ok(edges) => edges,
+            Err(_) => return Err(String::from("Error creating the edges DataFrame")),
+        };
+        let schema = |distinct: bool| -> Result<Shape<u32>, String> {
+            let cardinality = Cardinality::new(
+                "AtLeastTwoAnnotations",
+                TripleConstraint::new("Annotation", 7, NodeConstraint::Any).into(),
+                Bound::Inclusive(2),
+                Bound::Many,
+            );
+            match distinct {
+                true => Ok(cardinality
+                    .with_distinct_objects()
+                    .map_err(|error| error.to_string())?
+                    .into()),
+                false => Ok(cardinality.into()),
+            }
+        };
+        let graph = || GraphFrame::from_edges(edges.clone()).map_err(|error| error.to_string());
+        test(graph(), vec![1u32, 1u32], schema(false)?)?;
+        test(graph(), vec![1u32], schema(true)?)
     }
 
     #[test]
-    fn vprog_to_vprog_test() -> Result<(), String> {
-        test(paper_graph(), vec![1u32], vprog_to_vprog_schema())
+    fn self_loops_test() -> Result<(), String> {
+        let graph = || {
+            let edges = match df![
+                Column::Subject.as_ref() => [TimBernersLee, TimBernersLee, TimBernersLee].iter().map(Value::id).collect::<Vec<_>>(),
+                Column::Predicate.as_ref() => [InstanceOf, BirthPlace, Country].iter().map(Value::id).collect::<Vec<_>>(),
+                Column::Object.as_ref() => [Human, TimBernersLee, UnitedKingdom].iter().map(Value::id).collect::<Vec<_>>(),
+            ] {
+                Ok(edges) => edges,
+                Err(_) => return Err(String::from("Error creating the edges DataFrame")),
+            };
+            match GraphFrame::from_edges(edges) {
+                Ok(graph) => Ok(graph),
+                Err(_) => Err(String::from("Error creating the GraphFrame from edges")),
+            }
+        };
+        let schema = || -> Shape<u32> {
+            ShapeReference::new(
+                "BirthUnitedKingdom",
+                BirthPlace.id(),
+                TripleConstraint::new(
+                    "UnitedKingdom",
+                    Country.id(),
+                    NodeConstraint::Value(UnitedKingdom.id()),
+                )
+                .into(),
+            )
+            .into()
+        };
+
+        let with_loops = match PSchema::new(schema()).validate(graph()?) {
+            Ok(subset) => subset.height(),
+            Err(error) => return Err(error.to_string()),
+        };
+        let without_loops = match PSchema::new(schema())
+            .without_self_loops()
+            .validate(graph()?)
+        {
+            Ok(subset) => subset.height(),
+            Err(error) => return Err(error.to_string()),
+        };
+        match (with_loops > 0, without_loops) {
+            (true, 0) => Ok(()),
+            _ => Err(String::from("The self-loop should have been excluded")),
+        }
+    }
+
+    #[test]
+    fn vertex_attributes_test() -> Result<(), String> {
+        let mut graph = paper_graph()?;
+        graph.vertices = match graph
+            .vertices
+            .lazy()
+            .with_column(lit("entity").alias("entity_type"))
+            .collect()
+        {
+            Ok(vertices) => vertices,
+            Err(_) => return Err(String::from("Error creating the vertices DataFrame")),
+        };
+        match PSchema::new(simple_schema()).validate(graph) {
+            Ok(actual) if actual.column("entity_type").is_ok() => Ok(()),
+            Ok(_) => Err(String::from("The vertex attributes were not preserved")),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    #[test]
+    fn vertex_attributes_vprog_test() -> Result<(), String> {
+        let mut graph = paper_graph()?;
+        graph.vertices = match graph
+            .vertices
+            .lazy()
+            .with_column(
+                col(VertexId.as_ref())
+                    .eq(lit(TimBernersLee.id()))
+                    .alias("trusted"),
+            )
+            .collect()
+        {
+            Ok(vertices) => vertices,
+            Err(_) => return Err(String::from("Error creating the vertices DataFrame")),
+        };
+        // Only the trusted vertices take the labels sent to them, so Vint Cerf does not conform
+        // even though he is a human too. The self-loops are discarded, so the vertices are rebuilt.
+        let actual = match PSchema::new(simple_schema())
+            .without_self_loops()
+            .with_custom_vprog(|| {
+                when(col("trusted"))
+                    .then(Column::msg(None))
+                    .otherwise(col(Custom("labels").as_ref()))
+            })
+            .validate(graph)
+        {
+            Ok(actual) => actual,
+            Err(error) => return Err(error.to_string()),
+        };
+        let subjects = match actual
+            .column(Subject.as_ref())
+            .and_then(|column| column.unique())
+        {
+            Ok(subjects) => subjects,
+            Err(error) => return Err(error.to_string()),
+        };
+        match (
+            subjects.len(),
+            subjects.u32().ok().and_then(|ids| ids.get(0)),
+        ) {
+            (1, Some(id)) if id == TimBernersLee.id() => Ok(()),
+            _ => Err(String::from("Only Tim Berners-Lee should conform")),
+        }
     }
 
     #[test]
@@ -306,7 +3204,8 @@ mod tests {
 
         match PSchema::new(simple_schema()).validate(graph) {
             Ok(_) => Err(String::from("An error should have occurred")),
-            Err(_) => Ok(()),
+            Err(PSchemaError::MissingColumn(column)) if column == Predicate.as_ref() => Ok(()),
+            Err(error) => Err(format!("Unexpected error: {}", error)),
         }
     }
 
@@ -0,0 +1,204 @@
+use polars::prelude::Literal;
+
+use crate::shape::shex::{
+    Bound, Cardinality, IncomingReference, NodeConstraint, Shape, ShapeAnd, ShapeOr,
+    ShapeReference, TripleConstraint,
+};
+use crate::utils::intern::intern;
+use crate::utils::symbol_table::SymbolTable;
+
+/// The `SchemaBuilder` struct eases the construction of schemas by interning
+/// the labels of the shapes it creates in its own `SymbolTable`, so there is no
+/// need to manage them manually. Inline shapes need no label, as the builder
+/// generates a unique one for them; the generated labels start with `_:`, so
+/// explicit labels should not.
+#[derive(Clone, Debug, Default)]
+pub struct SchemaBuilder {
+    symbols: SymbolTable,
+    anonymous: usize,
+}
+
+impl SchemaBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn triple<T: Literal + Clone>(
+        &mut self,
+        name: &'static str,
+        predicate: T,
+        object: NodeConstraint<T>,
+    ) -> Shape<T> {
+        self.symbols.intern(name);
+        TripleConstraint::new(name, predicate, object).into()
+    }
+
+    pub fn reference<T: Literal + Clone>(
+        &mut self,
+        name: &'static str,
+        predicate: T,
+        reference: Shape<T>,
+    ) -> Shape<T> {
+        self.symbols.intern(name);
+        ShapeReference::new(name, predicate, reference).into()
+    }
+
+    pub fn incoming_reference<T: Literal + Clone>(
+        &mut self,
+        name: &'static str,
+        predicate: T,
+        reference: Shape<T>,
+    ) -> Shape<T> {
+        self.symbols.intern(name);
+        IncomingReference::new(name, predicate, reference).into()
+    }
+
+    pub fn and<T: Literal + Clone>(
+        &mut self,
+        name: &'static str,
+        shapes: Vec<Shape<T>>,
+    ) -> Shape<T> {
+        self.symbols.intern(name);
+        ShapeAnd::new(name, shapes).into()
+    }
+
+    pub fn or<T: Literal + Clone>(
+        &mut self,
+        name: &'static str,
+        shapes: Vec<Shape<T>>,
+    ) -> Shape<T> {
+        self.symbols.intern(name);
+        ShapeOr::new(name, shapes).into()
+    }
+
+    pub fn cardinality<T: Literal + Clone>(
+        &mut self,
+        name: &'static str,
+        shape: Shape<T>,
+        min: Bound,
+        max: Bound,
+    ) -> Shape<T> {
+        self.symbols.intern(name);
+        Cardinality::new(name, shape, min, max).into()
+    }
+
+    /// This function generates a label for an anonymous shape, such as those
+    /// written inline at a reference point, which does not collide with any of
+    /// the labels interned so far. The label is interned right away, so later
+    /// calls yield different ones.
+    pub fn anonymous(&mut self) -> &'static str {
+        loop {
+            let label = format!("_:{}", self.anonymous);
+            self.anonymous += 1;
+            if !self.symbols.contains(&label) {
+                let label = intern(label);
+                self.symbols.intern(label);
+                return label;
+            }
+        }
+    }
+
+    pub fn anonymous_and<T: Literal + Clone>(&mut self, shapes: Vec<Shape<T>>) -> Shape<T> {
+        let name = self.anonymous();
+        ShapeAnd::new(name, shapes).into()
+    }
+
+    pub fn anonymous_or<T: Literal + Clone>(&mut self, shapes: Vec<Shape<T>>) -> Shape<T> {
+        let name = self.anonymous();
+        ShapeOr::new(name, shapes).into()
+    }
+
+    /// This function returns the `SymbolTable` holding the labels interned so far.
+    pub fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pschema::PSchema;
+    use crate::shape::builder::SchemaBuilder;
+    use crate::shape::shex::NodeConstraint;
+    use crate::test_support::assert_conforms;
+    use crate::utils::examples::Value::*;
+    use crate::utils::examples::*;
+    use crate::utils::intern::intern;
+
+    #[test]
+    fn paper_schema_test() {
+        let mut builder = SchemaBuilder::new();
+        let human = builder.triple("Human", InstanceOf.id(), NodeConstraint::Value(Human.id()));
+        let london = builder.triple(
+            "London",
+            BirthPlace.id(),
+            NodeConstraint::Value(London.id()),
+        );
+        let date = builder.triple(
+            "DateTime",
+            BirthDate.id(),
+            NodeConstraint::Value(DateTime.id()),
+        );
+        let schema = builder.and("Researcher", vec![human, london, date]);
+
+        assert_eq!(paper_schema(), schema);
+        let symbols = builder.symbols();
+        let mut ids = ["Human", "London", "DateTime", "Researcher"]
+            .iter()
+            .filter_map(|label| symbols.get(label))
+            .collect::<Vec<_>>();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(4, ids.len())
+    }
+
+    #[test]
+    fn anonymous_shape_test() -> Result<(), String> {
+        let mut builder = SchemaBuilder::new();
+        let award = builder.triple(
+            "AwardReceived",
+            AwardReceived.id(),
+            NodeConstraint::Value(Award.id()),
+        );
+        let inline = builder.anonymous_and(vec![award]);
+        let schema = builder.reference("EmployerAwarded", Employer.id(), inline.clone());
+
+        if ["AwardReceived", "EmployerAwarded"].contains(&inline.get_label()) {
+            return Err(format!("Colliding label: {}", inline.get_label()));
+        }
+        match PSchema::new(schema).validate(paper_graph()?) {
+            Ok(subset) => match subset
+                .column("subject")
+                .and_then(|subjects| subjects.n_unique())
+            {
+                Ok(1) => Ok(()),
+                _ => Err(format!("Unexpected subset: {:?}", subset)),
+            },
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    #[test]
+    fn many_shapes_test() -> Result<(), String> {
+        // More labels than a `u8` can tell apart, so colliding ids would mix the shapes up.
+        let mut builder = SchemaBuilder::new();
+        let mut children = (0..299)
+            .map(|i| {
+                builder.triple(
+                    intern(format!("Shape{}", i)),
+                    InstanceOf.id(),
+                    NodeConstraint::Value(1_000_000 + i),
+                )
+            })
+            .collect::<Vec<_>>();
+        children.push(builder.triple("Human", InstanceOf.id(), NodeConstraint::Value(Human.id())));
+        let schema = builder.or("Many", children);
+        assert_eq!(301, builder.symbols().len());
+
+        let (tim, vint) = (TimBernersLee.id().to_string(), VintCerf.id().to_string());
+        assert_conforms(
+            paper_graph()?,
+            schema,
+            &[(tim.as_str(), &["Many"]), (vint.as_str(), &["Many"])],
+        )
+    }
+}
@@ -0,0 +1,132 @@
+/// The `shape!` macro builds a `Shape` out of a compact description, so schemas
+/// can be written without nesting the constructors and their `.into()` calls.
+/// Each of the shapes is written as follows, where the predicates, values and
+/// bounds are arbitrary expressions:
+///
+/// * `"Label": predicate => value` is a `TripleConstraint` whose object must be
+/// equal to `value`, while `"Label": predicate => *` matches any object.
+/// * `and "Label" { shape, ... }` and `or "Label" { shape, ... }` are the
+/// `ShapeAnd` and `ShapeOr` composites of the shapes between the braces.
+/// * `ref "Label": predicate => { shape }` is a `ShapeReference`, whereas
+/// `incoming "Label": predicate => { shape }` is an `IncomingReference`.
+/// * `card "Label" [min, max] { shape }` is a `Cardinality` of the shape.
+/// * `not "Label" { shape }` is a `ShapeNot` of the shape.
+///
+/// ```
+/// use pschema_rs::shape;
+/// use pschema_rs::shape::shex::{Bound, Shape};
+///
+/// let schema: Shape<&str> = shape!(and "Researcher" {
+///     "Human": "P31" => "Q5",
+///     card "Awards" [Bound::Zero, Bound::Many] { "Award": "P166" => * },
+/// });
+/// ```
+#[macro_export]
+macro_rules! shape {
+    // The shapes of the composites are munched one at a time into the accumulator.
+    (@list [$($acc:expr),*] $(,)?) => {
+        vec![$($acc),*]
+    };
+    (@list [$($acc:expr),*] $kind:ident $label:literal { $($inner:tt)* } $(, $($rest:tt)*)?) => {
+        $crate::shape!(@list [$($acc,)* $crate::shape!($kind $label { $($inner)* })] $($($rest)*)?)
+    };
+    (@list [$($acc:expr),*] $kind:ident $label:literal [$min:expr, $max:expr] { $($inner:tt)* } $(, $($rest:tt)*)?) => {
+        $crate::shape!(@list [$($acc,)* $crate::shape!($kind $label [$min, $max] { $($inner)* })] $($($rest)*)?)
+    };
+    (@list [$($acc:expr),*] $kind:ident $label:literal : $predicate:expr => { $($inner:tt)* } $(, $($rest:tt)*)?) => {
+        $crate::shape!(@list [$($acc,)* $crate::shape!($kind $label: $predicate => { $($inner)* })] $($($rest)*)?)
+    };
+    (@list [$($acc:expr),*] $label:literal : $predicate:expr => * $(, $($rest:tt)*)?) => {
+        $crate::shape!(@list [$($acc,)* $crate::shape!($label: $predicate => *)] $($($rest)*)?)
+    };
+    (@list [$($acc:expr),*] $label:literal : $predicate:expr => $value:expr $(, $($rest:tt)*)?) => {
+        $crate::shape!(@list [$($acc,)* $crate::shape!($label: $predicate => $value)] $($($rest)*)?)
+    };
+    (and $label:literal { $($inner:tt)* }) => {
+        $crate::shape::shex::Shape::from($crate::shape::shex::ShapeAnd::new(
+            $label,
+            $crate::shape!(@list [] $($inner)*),
+        ))
+    };
+    (or $label:literal { $($inner:tt)* }) => {
+        $crate::shape::shex::Shape::from($crate::shape::shex::ShapeOr::new(
+            $label,
+            $crate::shape!(@list [] $($inner)*),
+        ))
+    };
+    (not $label:literal { $($inner:tt)* }) => {
+        $crate::shape::shex::Shape::from($crate::shape::shex::ShapeNot::new(
+            $label,
+            $crate::shape!($($inner)*),
+        ))
+    };
+    (card $label:literal [$min:expr, $max:expr] { $($inner:tt)* }) => {
+        $crate::shape::shex::Shape::from($crate::shape::shex::Cardinality::new(
+            $label,
+            $crate::shape!($($inner)*),
+            $min,
+            $max,
+        ))
+    };
+    (ref $label:literal : $predicate:expr => { $($inner:tt)* }) => {
+        $crate::shape::shex::Shape::from($crate::shape::shex::ShapeReference::new(
+            $label,
+            $predicate,
+            $crate::shape!($($inner)*),
+        ))
+    };
+    (incoming $label:literal : $predicate:expr => { $($inner:tt)* }) => {
+        $crate::shape::shex::Shape::from($crate::shape::shex::IncomingReference::new(
+            $label,
+            $predicate,
+            $crate::shape!($($inner)*),
+        ))
+    };
+    ($label:literal : $predicate:expr => *) => {
+        $crate::shape::shex::Shape::from($crate::shape::shex::TripleConstraint::new(
+            $label,
+            $predicate,
+            $crate::shape::shex::NodeConstraint::Any,
+        ))
+    };
+    ($label:literal : $predicate:expr => $value:expr) => {
+        $crate::shape::shex::Shape::from($crate::shape::shex::TripleConstraint::new(
+            $label,
+            $predicate,
+            $crate::shape::shex::NodeConstraint::Value($value),
+        ))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::shape::shex::{Bound, Shape};
+    use crate::utils::examples::Value::*;
+    use crate::utils::examples::*;
+
+    #[test]
+    fn paper_schema_test() {
+        let schema: Shape<u32> = shape!(and "Researcher" {
+            "Human": InstanceOf.id() => Human.id(),
+            "London": BirthPlace.id() => London.id(),
+            "DateTime": BirthDate.id() => DateTime.id(),
+        });
+        assert_eq!(paper_schema(), schema)
+    }
+
+    #[test]
+    fn nested_schema_test() {
+        let schema: Shape<u32> = shape!(and "Researcher" {
+            "IsHuman": InstanceOf.id() => Human.id(),
+            ref "BirthUnitedKingdom": BirthPlace.id() => {
+                "UnitedKingdom": Country.id() => UnitedKingdom.id()
+            },
+            "DateTime": BirthDate.id() => DateTime.id()
+        });
+        assert_eq!(complex_schema(), schema);
+        let schema: Shape<u32> = shape!(card "Awards" [Bound::Inclusive(1), Bound::Many] {
+            or "Recognized" { "Award": AwardReceived.id() => *, "Human": InstanceOf.id() => * }
+        });
+        assert_eq!("Awards", schema.get_label())
+    }
+}
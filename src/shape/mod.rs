@@ -1,3 +1,9 @@
+/// `pub mod builder;` is declaring a public module named `builder` to ease the
+/// construction of schemas.
+pub mod builder;
+/// `mod macros;` is declaring a private module named `macros` holding the
+/// `shape!` macro, which is exported at the root of the crate.
+mod macros;
 /// `pub mod shape_tree;` is declaring a public module named `shape_tree` to work
 /// with Shape Trees in the context of Knowledge graph validation.
 pub mod shape_tree;
@@ -1,4 +1,4 @@
-use polars::prelude::Literal;
+use polars::prelude::{Literal, PolarsError, PolarsResult};
 
 use crate::shape::shex::Shape;
 use std::collections::VecDeque;
@@ -32,7 +32,8 @@ impl<T: Literal + Clone> ShapeTree<T> {
     ///         4.2.2 If it is a `ShapeReference` => push it to the temporary vector and enqueue its child
     ///         4.2.3 If it is a `ShapeComposite` => push it to the temporary vector and enqueue its children
     ///         4.2.4 If it is a `ShapeLiteral` => push it to the temporary vector for the current iteration
-    ///         4.2.5 If it is a `NumericFacet` => push it to the temporary vector and enqueue its child
+    ///         4.2.5 If it is a `NumericFacet` => push it to the temporary vector for the current iteration
+    ///         4.2.6 If it is a `ShapeNot` => push it to the temporary vector and enqueue its child
     ///     4.3 Push the temporary results into the `shapes` vector
     ///     4.4 Clear the temporary results.
     /// 5. Return the `shapes` vector in reverse order
@@ -49,10 +50,15 @@ impl<T: Literal + Clone> ShapeTree<T> {
                 match nodes.pop_front() {
                     Some(node) => match &node {
                         Shape::TripleConstraint(_) => temp.push(node),
+                        Shape::NumericFacet(_) => temp.push(node),
                         Shape::ShapeReference(shape) => {
                             temp.push(node.clone());
                             nodes.push_back(shape.clone().get_reference());
                         }
+                        Shape::IncomingReference(shape) => {
+                            temp.push(node.clone());
+                            nodes.push_back(shape.clone().get_reference());
+                        }
                         Shape::ShapeAnd(shape) => {
                             temp.push(node.clone());
                             shape
@@ -71,6 +77,10 @@ impl<T: Literal + Clone> ShapeTree<T> {
                             temp.push(node.clone());
                             nodes.push_back(shape.clone().get_shape());
                         }
+                        Shape::ShapeNot(shape) => {
+                            temp.push(node.clone());
+                            nodes.push_back(shape.clone().get_shape());
+                        }
                     },
                     None => continue,
                 }
@@ -94,9 +104,49 @@ impl<T: Literal + Clone> ShapeTree<T> {
     /// iterations required to generate all possible combinations of shapes in the
     /// `self` object. If the `self` object contains an n-ary shape, then the number of
     /// iterations is equal to the number of shapes minus one, otherwise it is equal to
-    /// the number of shapes.
-    pub fn iterations(self) -> u8 {
-        self.into_iter().count() as u8
+    /// the number of shapes. An error is returned for degenerate trees, that is,
+    /// those containing composites with no shapes, for which the Pregel algorithm
+    /// would silently produce no labels, and for those exceeding `u8::MAX` levels.
+    pub fn iterations(self) -> PolarsResult<u8> {
+        let degenerate = self.shapes.iter().flatten().any(|shape| match shape {
+            Shape::ShapeAnd(shape) => shape.get_shapes().is_empty(),
+            Shape::ShapeOr(shape) => shape.get_shapes().is_empty(),
+            _ => false,
+        });
+        if degenerate {
+            return Err(PolarsError::ComputeError(
+                "The schema contains a composite shape with no shapes".into(),
+            ));
+        }
+        match u8::try_from(self.into_iter().count()) {
+            Ok(0) => Err(PolarsError::ComputeError("The schema is empty".into())),
+            Ok(iterations) => Ok(iterations),
+            Err(_) => Err(PolarsError::ComputeError(
+                "The schema exceeds the maximum number of levels".into(),
+            )),
+        }
+    }
+
+    /// The function serializes the tree as JSON, so the validation plan can be
+    /// rendered by other tools. Each level is an array of `{label, variant,
+    /// predicate}` objects, and the levels are sorted in the same order the
+    /// messages are sent during the Pregel supersteps.
+    ///
+    /// Returns:
+    ///
+    /// A `String` containing the JSON array of levels.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String
+    where
+        T: serde::Serialize,
+    {
+        serde_json::Value::Array(
+            self.shapes
+                .iter()
+                .map(|level| serde_json::Value::Array(level.iter().map(Shape::describe).collect()))
+                .collect(),
+        )
+        .to_string()
     }
 }
 
@@ -112,8 +162,20 @@ impl<T: Literal + Clone> IntoIterator for ShapeTree<T> {
 #[cfg(test)]
 pub mod tests {
     use crate::shape::shape_tree::ShapeTree;
+    use crate::shape::shex::{Shape, ShapeAnd};
     use crate::utils::examples::*;
 
+    #[test]
+    fn simple_schema_iterations_test() {
+        assert_eq!(1, ShapeTree::new(simple_schema()).iterations().unwrap())
+    }
+
+    #[test]
+    fn empty_schema_iterations_test() {
+        let schema: Shape<u32> = ShapeAnd::new("Empty", vec![]).into();
+        assert!(ShapeTree::new(schema).iterations().is_err())
+    }
+
     #[test]
     fn simple_schema_test() {
         assert_eq!(1, ShapeTree::new(simple_schema()).into_iter().count())
@@ -154,4 +216,24 @@ pub mod tests {
             ShapeTree::new(and_reference_schema()).into_iter().count()
         )
     }
+
+    #[test]
+    fn cardinality_reference_schema_test() {
+        assert_eq!(
+            3,
+            ShapeTree::new(cardinality_reference_schema())
+                .into_iter()
+                .count()
+        )
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn complex_schema_json_test() {
+        let json = ShapeTree::new(complex_schema()).to_json();
+        match serde_json::from_str::<serde_json::Value>(&json) {
+            Ok(serde_json::Value::Array(levels)) => assert_eq!(3, levels.len()),
+            _ => panic!("Unexpected JSON: {}", json),
+        }
+    }
 }
@@ -1,44 +1,460 @@
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use chrono::NaiveDate;
 use polars::prelude::*;
 use pregel_rs::pregel::Column;
 use pregel_rs::pregel::Column::{Custom, Object, Predicate};
+use regex::Regex;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize};
+use wikidata_rs::dtype::DataType as WikidataType;
+use wikidata_rs::id::Id;
+
+use crate::backends::ntriples::NTriples;
+use crate::utils::hash::StableHasher;
+use crate::utils::intern::intern;
+use crate::utils::symbol_table::{Label, SymbolTable};
 
 /// The above code is defining a trait named `Validate` with a single method
 /// `validate`. This trait can be implemented by any type that wants to provide
 /// validation functionality. The `validate` method takes in a parameter `prev` of
 /// type `Expr` and returns an `Expr`. The implementation of this method will
 /// perform some validation on the input `self` and return a modified `Expr` based
-/// on the validation result. The `pub(crate)` keyword specifies that this trait is
-/// only accessible within the current crate.
+/// on the validation result. The labels are written in the messages as `labels`
+/// tells. The `pub(crate)` keyword specifies that this trait is only accessible
+/// within the current crate.
 pub(crate) trait Validate {
-    fn validate(self, prev: Expr) -> Expr;
+    fn validate(self, prev: Expr, labels: &Labels) -> Expr;
 }
 
-#[derive(Clone, Debug, PartialEq)]
+/// The `Labels` struct tells how the labels of the shapes are written in the
+/// messages sent during the validation. `Categorical` and `String` messages
+/// hold the labels as they are, whereas integer messages hold the codes the
+/// labels are assigned by a `SymbolTable`, which take less memory.
+///
+/// Properties:
+///
+/// * `dtype`: the datatype of the messages.
+/// * `symbols`: the codes of the labels, in case the messages are integers.
+#[derive(Clone, Debug)]
+pub(crate) struct Labels {
+    dtype: DataType,
+    symbols: Option<SymbolTable>,
+}
+
+impl Labels {
+    /// This function creates the labels written as they are, in messages of
+    /// the provided datatype.
+    pub(crate) fn new(dtype: DataType) -> Self {
+        Self {
+            dtype,
+            symbols: None,
+        }
+    }
+
+    /// This function creates the labels written as their codes in the provided
+    /// `SymbolTable`, in messages of the provided integer datatype.
+    pub(crate) fn encoded(dtype: DataType, symbols: SymbolTable) -> Self {
+        Self {
+            dtype,
+            symbols: Some(symbols),
+        }
+    }
+
+    /// This function returns the datatype of the messages.
+    pub(crate) fn dtype(&self) -> &DataType {
+        &self.dtype
+    }
+
+    /// This function builds the literal standing for the provided label in the
+    /// messages. Labels lacking a code result in NULL, so they match nothing.
+    pub(crate) fn lit(&self, label: &'static str) -> Expr {
+        match &self.symbols {
+            None => lit(label),
+            Some(symbols) => match symbols.get(label) {
+                Some(code) => lit(code as u32).cast(self.dtype.clone()),
+                None => lit(NULL).cast(self.dtype.clone()),
+            },
+        }
+    }
+
+    /// This function turns the codes of the lists of labels contained in the
+    /// provided expression back into the labels they stand for, as `String`s.
+    /// Labels written as they are are kept unchanged.
+    pub(crate) fn decode(&self, labels: Expr) -> Expr {
+        let symbols = match &self.symbols {
+            Some(symbols) => symbols.clone(),
+            None => return labels,
+        };
+        labels.list().eval(
+            col("").cast(DataType::UInt32).map(
+                move |column| {
+                    let names = column
+                        .as_materialized_series()
+                        .u32()?
+                        .into_iter()
+                        .map(|code| {
+                            code.and_then(|code| Label::try_from(code).ok())
+                                .and_then(|code| symbols.label(code))
+                        })
+                        .collect::<StringChunked>()
+                        .with_name(column.name().clone());
+                    Ok(Some(names.into_series().into()))
+                },
+                GetOutput::from_type(DataType::String),
+            ),
+            false,
+        )
+    }
+}
+
+/// The `Shape` enum gathers the different shapes a schema can be made of. When
+/// the `serde` feature is enabled, shapes can be serialized and deserialized;
+/// note that deserialization requires `T` to own its data (e.g. `u32`).
+#[derive(Clone, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Shape<T: Literal + Clone> {
     TripleConstraint(TripleConstraint<T>),
     ShapeReference(Box<ShapeReference<T>>),
+    IncomingReference(Box<IncomingReference<T>>),
     ShapeAnd(ShapeAnd<T>),
     ShapeOr(ShapeOr<T>),
     Cardinality(Box<Cardinality<T>>),
+    ShapeNot(Box<ShapeNot<T>>),
+    NumericFacet(NumericFacet<T>),
+}
+
+/// The `ShapeError` enum lists the invariants a shape may violate when built by
+/// means of the fallible constructors, e.g. from untrusted input. Note that
+/// references cannot be cyclic, as each shape owns the shapes it refers to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ShapeError {
+    /// The composite with the given label contains no shapes.
+    EmptyComposite(&'static str),
+    /// The cardinality with the given label has a minimum above its maximum.
+    InvalidBounds(&'static str),
+    /// The schema has the first number of distinct labels, which exceeds the
+    /// second one, the number of labels a `SymbolTable` can hold.
+    TooManyLabels(usize, usize),
+    /// The cardinality with the given label counts the distinct objects of a
+    /// shape other than a `TripleConstraint`.
+    DistinctObjects(&'static str),
+}
+
+impl std::fmt::Display for ShapeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShapeError::EmptyComposite(label) => write!(f, "The composite {} is empty", label),
+            ShapeError::InvalidBounds(label) => {
+                write!(f, "The minimum of {} is greater than its maximum", label)
+            }
+            ShapeError::TooManyLabels(labels, limit) => write!(
+                f,
+                "The schema has {} labels, but no more than {} can be told apart",
+                labels, limit
+            ),
+            ShapeError::DistinctObjects(label) => write!(
+                f,
+                "The cardinality {} can only count the distinct objects of a triple constraint",
+                label
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ShapeError {}
+
+/// Labels are `&'static str`, so the deserialized ones are interned: each
+/// distinct label is leaked once, however many schemas are deserialized.
+#[cfg(feature = "serde")]
+fn deserialize_label<'de, D: Deserializer<'de>>(deserializer: D) -> Result<&'static str, D::Error> {
+    String::deserialize(deserializer).map(intern)
 }
 
 /// The above code is defining an enumeration type `Bound` in Rust. The `Bound` type
 /// has two variants: `Inclusive` and `Exclusive`, each of which takes a single `u8`
 /// value as an argument. The `#[derive(Clone, Debug, PartialEq)]` attribute is used
 /// to automatically generate implementations of the `Clone`, `Debug`, and
-/// `PartialEq` traits for the `Bound` type.
-#[derive(Clone, Debug, PartialEq)]
-pub enum Bound {
-    Inclusive(u8),
-    Exclusive(u8),
+/// `PartialEq` traits for the `Bound` type. The bounds of a `NumericFacet` hold
+/// `f64` values instead, as they are compared against the literals of the graph.
+#[derive(Clone, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Bound<N = u8> {
+    Inclusive(N),
+    Exclusive(N),
     Zero,
     Many,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+/// The `NodeConstraint` enum defines the constraints the object of an edge must
+/// satisfy. `DateBefore` and `DateAfter` compare `xsd:date` and `xsd:dateTime`
+/// literals, written as in N-Triples, against a date; the time and timezone
+/// offset of the `xsd:dateTime` values are ignored, so only their date is taken
+/// into account. Both bounds are exclusive. `ValueSet` is satisfied by the
+/// edges whose object is any of the provided values, so an empty set matches
+/// nothing and a single-element set behaves as `Value` does. `NotValueSet` is
+/// satisfied by the edges whose object is none of the provided values; a node
+/// lacking the predicate altogether has no such edge, so it does not satisfy
+/// it, while a node having the predicate several times satisfies it as long as
+/// any of its objects is not in the set. `AnyLangString` is satisfied by the
+/// language-tagged literals, written as in N-Triples, whatever their language
+/// is; literals whose lexical form contains an `@` are not mistaken for them,
+/// as the tag must follow the closing quote. `QuantityUnit` is satisfied by the
+/// quantities measured in the provided unit, which requires the graph to hold a
+/// `unit` column, as produced by `DuckDB::import_with_units`. `Datatype` is
+/// satisfied by the edges whose object is a literal of the provided datatype,
+/// which requires the graph to hold a `dtype` column, as produced by
+/// `DuckDB::import`, where it holds the id of the Wikidata datatype of each of
+/// the objects, e.g. `u32::from(Id::DataType(DataType::DateTime))`, or by
+/// `NTriples::import_with_options` with `ImportOptions::with_datatypes`, where
+/// it holds their datatype IRI, e.g.
+/// `<http://www.w3.org/2001/XMLSchema#dateTime>`. `IdRange` is satisfied by the
+/// Wikidata entities whose numeric id lies within the provided inclusive
+/// bounds, e.g. `Q515` has the numeric id 515; it is meant for the N-Triples
+/// path, so the objects are assumed to be IRIs ending in the id, such as
+/// `<http://www.wikidata.org/entity/Q515>`. Any other object does not satisfy
+/// it. `Custom` is satisfied by the objects for which the provided `Matcher`
+/// returns `true`, which allows plugging in any other matching logic, such as
+/// semantic equivalence. Note that the closure is run as a UDF over the string
+/// form of each of the objects, so the query optimizer cannot see through it
+/// and it is considerably slower than the native expressions the rest of the
+/// constraints are built on; prefer them whenever possible.
+#[derive(Clone, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum NodeConstraint<T: Literal + Clone> {
     Value(T),
     Any,
+    ValueSet(Vec<T>),
+    NotValueSet(Vec<T>),
+    AnyLangString,
+    DateBefore(NaiveDate),
+    DateAfter(NaiveDate),
+    QuantityUnit(T),
+    Datatype(T),
+    IdRange(u32, u32),
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(Matcher),
+}
+
+/// The `Matcher` struct wraps the user-provided closure of a
+/// `NodeConstraint::Custom`, which receives the string form of the object, e.g.
+/// `<http://example.org/alan>` for the graphs imported from N-Triples or `80`
+/// for those holding integer ids. As closures cannot be compared, two matchers
+/// are equal only if they share the same closure; they cannot be serialized
+/// either, so serializing a schema holding one results in an error. For the
+/// same reason, a matcher is hashed by the address of its closure, so the hash
+/// of a schema holding one changes from run to run, and every matcher has the
+/// same `Debug` output. Hence, such schemas cannot be cached: `Cache` rejects
+/// them instead of computing a key that would never be hit again.
+#[derive(Clone)]
+pub struct Matcher(Arc<dyn Fn(&str) -> bool + Send + Sync>);
+
+impl Matcher {
+    pub fn new(matcher: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+        Self(Arc::new(matcher))
+    }
+}
+
+impl std::fmt::Debug for Matcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Matcher")
+    }
+}
+
+impl PartialEq for Matcher {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Hash for Matcher {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (Arc::as_ptr(&self.0) as *const () as usize).hash(state)
+    }
+}
+
+impl<T: Literal + Clone> NodeConstraint<T> {
+    /// This function creates a `NodeConstraint::Custom` out of a closure deciding
+    /// whether the string form of an object satisfies the constraint.
+    pub fn custom(matcher: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+        NodeConstraint::Custom(Matcher::new(matcher))
+    }
+}
+
+/// The `FromValue` trait parses the values written in the files the value sets
+/// are loaded from into the type of the values of the graph. Wikidata ids, such
+/// as `Q515`, are converted into the integer ids used by the DuckDB backend,
+/// while IRIs are kept as they are written, enclosed in angle brackets, as in
+/// the N-Triples backend.
+pub trait FromValue: Sized {
+    fn from_value(value: &str) -> Result<Self, String>;
+}
+
+impl FromValue for u32 {
+    fn from_value(value: &str) -> Result<Self, String> {
+        let error = || format!("Invalid Wikidata id: {}", value);
+        match value.chars().next() {
+            // The numeric part is checked beforehand, as `Id::from` panics on malformed ids.
+            Some('Q' | 'P' | 'L') => match value[1..].chars().all(|c| c.is_ascii_digit()) {
+                true => match value[1..].parse::<u32>() {
+                    Ok(_) => Ok(u32::from(Id::from(value))),
+                    Err(_) => Err(error()),
+                },
+                false => Err(error()),
+            },
+            _ => value.parse::<u32>().map_err(|_| error()),
+        }
+    }
+}
+
+impl FromValue for &'static str {
+    fn from_value(value: &str) -> Result<Self, String> {
+        Ok(intern(value.to_string()))
+    }
+}
+
+impl<T: Literal + Clone + FromValue> NodeConstraint<T> {
+    /// This function loads a `ValueSet` from a file holding one value per line,
+    /// which is useful for large curated lists of allowed values. Blank lines
+    /// and lines starting with `#` are skipped. In case the file is a CSV or a
+    /// TSV, only its first column is read, so values must not contain commas
+    /// nor tabs, which holds for both Wikidata ids and IRIs.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The path to the file holding the allowed values.
+    ///
+    /// Returns:
+    ///
+    /// A `Result` with the `ValueSet` or an error message in case the file could
+    /// not be read or any of the values could not be parsed.
+    pub fn value_set_from_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self, String> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(error) => return Err(format!("Error reading the value set: {}", error)),
+        };
+        content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| T::from_value(line.split([',', '\t']).next().unwrap_or(line).trim()))
+            .collect::<Result<Vec<_>, _>>()
+            .map(NodeConstraint::ValueSet)
+    }
+}
+
+/// This function parses the date of the `xsd:date` and `xsd:dateTime` literals
+/// contained in the provided expression. Any other value results in NULL.
+fn literal_date(expr: Expr) -> Expr {
+    let lexical = expr.cast(DataType::String);
+    when(
+        lexical
+            .clone()
+            .str()
+            .contains_literal(lit("^^<http://www.w3.org/2001/XMLSchema#date")),
+    )
+    .then(
+        lexical
+            .str()
+            .slice(lit(1), lit(10))
+            .str()
+            .to_date(StrptimeOptions {
+                format: Some("%Y-%m-%d".into()),
+                strict: false,
+                ..Default::default()
+            }),
+    )
+    .otherwise(lit(NULL).cast(DataType::Date))
+}
+
+/// This function extracts the lexical form of the terms contained in the provided
+/// expression, as written in N-Triples: the value of the literals, without the
+/// quotes, the datatype nor the language tag, and the IRIs without the angle
+/// brackets. Any other term, such as a blank node, results in NULL.
+fn lexical_form(expr: Expr) -> Expr {
+    let term = expr.cast(DataType::String);
+    when(term.clone().str().starts_with(lit("\"")))
+        .then(term.clone().str().extract(lit(r#"(?s)^"(.*)""#), 1))
+        .otherwise(term.str().extract(lit(r"^<(.*)>$"), 1))
+}
+
+/// This function checks whether the terms contained in the provided expression
+/// are any of the provided values. The values are concatenated into a single
+/// expression, so large sets do not build deeply nested expressions, as chaining
+/// a comparison per value would. An empty set of values matches nothing.
+pub(crate) fn is_in_values<T: Literal>(expr: Expr, values: Vec<T>) -> Expr {
+    let values = values.into_iter().map(lit).collect::<Vec<_>>();
+    match concat_expr(values, false) {
+        Ok(values) => expr.is_in(values),
+        Err(_) => lit(false),
+    }
+}
+
+/// This function decodes the percent-encoded characters of the IRIs contained in
+/// the provided expression, which is the canonical form used when comparing
+/// IRIs with `TripleConstraint::with_normalized_iris`. Every value is decoded
+/// into a freshly allocated string each time the expression is evaluated.
+fn percent_decoded(expr: Expr) -> Expr {
+    expr.cast(DataType::String).map(
+        |column| {
+            let decoded = column
+                .as_materialized_series()
+                .str()?
+                .into_iter()
+                .map(|value| value.map(percent_decode))
+                .collect::<StringChunked>()
+                .with_name(column.name().clone());
+            Ok(Some(decoded.into_series().into()))
+        },
+        GetOutput::from_type(DataType::String),
+    )
+}
+
+/// This function applies the closure of a `Matcher` to the string form of the
+/// terms contained in the provided expression. NULL terms result in NULL.
+fn matched(expr: Expr, matcher: Matcher) -> Expr {
+    expr.cast(DataType::String).map(
+        move |column| {
+            let matched = column
+                .as_materialized_series()
+                .str()?
+                .into_iter()
+                .map(|value| value.map(|value| (matcher.0)(value)))
+                .collect::<BooleanChunked>()
+                .with_name(column.name().clone());
+            Ok(Some(matched.into_series().into()))
+        },
+        GetOutput::from_type(DataType::Boolean),
+    )
+}
+
+/// This function replaces every `%XX` escape of the provided IRI by the byte it
+/// stands for. In case the result is not valid UTF-8, the IRI is kept as is.
+fn percent_decode(iri: &str) -> String {
+    let bytes = iri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = match (bytes[i], bytes.get(i + 1..i + 3)) {
+            (b'%', Some(hex)) => std::str::from_utf8(hex)
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+        match escape {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).unwrap_or_else(|_| iri.to_string())
 }
 
 /// The above code is implementing a method `get_label` for the `Shape` struct. This
@@ -59,9 +475,210 @@ impl<T: Literal + Clone> Shape<T> {
         match self {
             Shape::TripleConstraint(shape) => shape.label,
             Shape::ShapeReference(shape) => shape.label,
+            Shape::IncomingReference(shape) => shape.label,
             Shape::ShapeAnd(shape) => shape.label,
             Shape::ShapeOr(shape) => shape.label,
             Shape::Cardinality(shape) => shape.label,
+            Shape::ShapeNot(shape) => shape.label,
+            Shape::NumericFacet(shape) => shape.label,
+        }
+    }
+
+    /// This function returns the highest identifier assigned to the labels of
+    /// the shape when they are interned, in depth-first order and starting from
+    /// zero, as a `SymbolTable` does. Hence, it is the number of distinct labels
+    /// minus one, which tells whether the labels fit in the chosen message type.
+    /// `None` is returned in case the labels do not fit in a `Label`.
+    pub fn max_label(&self) -> Option<Label> {
+        let mut labels = Vec::new();
+        self.collect_labels(&mut labels);
+        Label::try_from(labels.len().saturating_sub(1)).ok()
+    }
+
+    /// This function tells whether the provided label is used by the shape or by
+    /// any of the shapes nested in it.
+    pub fn contains_label(&self, label: &str) -> bool {
+        self.labels().contains(label)
+    }
+
+    /// This function returns the labels used by the shape and by all of the
+    /// shapes nested in it.
+    pub fn labels(&self) -> HashSet<&'static str> {
+        let mut labels = Vec::new();
+        self.collect_labels(&mut labels);
+        labels.into_iter().collect()
+    }
+
+    fn collect_labels(&self, labels: &mut Vec<&'static str>) {
+        if !labels.contains(&self.get_label()) {
+            labels.push(self.get_label());
+        }
+        match self {
+            Shape::TripleConstraint(_) => (),
+            Shape::ShapeReference(shape) => shape.reference.collect_labels(labels),
+            Shape::IncomingReference(shape) => shape.reference.collect_labels(labels),
+            Shape::ShapeAnd(shape) => shape.shapes.iter().for_each(|s| s.collect_labels(labels)),
+            Shape::ShapeOr(shape) => shape.shapes.iter().for_each(|s| s.collect_labels(labels)),
+            Shape::Cardinality(shape) => shape.shape.collect_labels(labels),
+            Shape::ShapeNot(shape) => shape.shape.collect_labels(labels),
+            Shape::NumericFacet(_) => (),
+        }
+    }
+
+    /// This function tells whether any of the triple constraints of the shape
+    /// is checked through a `NodeConstraint::Custom`, whose closure cannot be
+    /// compared, so the results of validating the shape cannot be cached.
+    pub(crate) fn has_custom_constraint(&self) -> bool {
+        match self {
+            Shape::TripleConstraint(shape) => matches!(shape.object, NodeConstraint::Custom(_)),
+            Shape::ShapeReference(shape) => shape.reference.has_custom_constraint(),
+            Shape::IncomingReference(shape) => shape.reference.has_custom_constraint(),
+            Shape::ShapeAnd(shape) => shape.shapes.iter().any(Shape::has_custom_constraint),
+            Shape::ShapeOr(shape) => shape.shapes.iter().any(Shape::has_custom_constraint),
+            Shape::Cardinality(shape) => shape.shape.has_custom_constraint(),
+            Shape::ShapeNot(shape) => shape.shape.has_custom_constraint(),
+            Shape::NumericFacet(_) => false,
+        }
+    }
+
+    /// This function returns the first predicate found in the shape, if any,
+    /// traversing it in depth-first order.
+    pub(crate) fn first_predicate(&self) -> Option<T> {
+        match self {
+            Shape::TripleConstraint(shape) => shape.predicates.first().cloned(),
+            Shape::ShapeReference(shape) => Some(shape.predicate.clone()),
+            Shape::IncomingReference(shape) => Some(shape.predicate.clone()),
+            Shape::ShapeAnd(shape) => shape.shapes.iter().find_map(Shape::first_predicate),
+            Shape::ShapeOr(shape) => shape.shapes.iter().find_map(Shape::first_predicate),
+            Shape::Cardinality(shape) => shape.shape.first_predicate(),
+            Shape::ShapeNot(shape) => shape.shape.first_predicate(),
+            Shape::NumericFacet(shape) => Some(shape.predicate.clone()),
+        }
+    }
+
+    /// This function estimates the cost of the Polars expressions the shape
+    /// generates when validated, so expensive schemas can be predicted. Each
+    /// `when/then/otherwise` and each `is_in` or `contains` counts as a node of
+    /// the expression tree, and the cost of the nested shapes is added up.
+    ///
+    /// Returns:
+    ///
+    /// The number of expensive nodes the expressions of the shape are made of.
+    pub fn estimate_expr_cost(&self) -> usize {
+        match self {
+            Shape::TripleConstraint(_) => 1,
+            Shape::ShapeReference(shape) => 2 + shape.reference.estimate_expr_cost(),
+            Shape::IncomingReference(shape) => 2 + shape.reference.estimate_expr_cost(),
+            Shape::ShapeAnd(shape) => shape
+                .shapes
+                .iter()
+                .fold(1, |acc, shape| acc + 1 + shape.estimate_expr_cost()),
+            Shape::ShapeOr(shape) => shape
+                .shapes
+                .iter()
+                .fold(1, |acc, shape| acc + 1 + shape.estimate_expr_cost()),
+            Shape::Cardinality(shape) => 2 + shape.shape.estimate_expr_cost(),
+            Shape::ShapeNot(shape) => 2 + shape.shape.estimate_expr_cost(),
+            Shape::NumericFacet(_) => 2,
+        }
+    }
+
+    /// This function describes the shape as a JSON object holding its label, the
+    /// name of its variant and its predicate, which is `null` for the shapes not
+    /// having any and a list when a triple constraint has several of them.
+    #[cfg(feature = "serde")]
+    pub(crate) fn describe(&self) -> serde_json::Value
+    where
+        T: Serialize,
+    {
+        let (variant, predicate) = match self {
+            Shape::TripleConstraint(shape) => (
+                "TripleConstraint",
+                match shape.predicates.as_slice() {
+                    [predicate] => serde_json::to_value(predicate),
+                    predicates => serde_json::to_value(predicates),
+                }
+                .unwrap_or_default(),
+            ),
+            Shape::ShapeReference(shape) => (
+                "ShapeReference",
+                serde_json::to_value(&shape.predicate).unwrap_or_default(),
+            ),
+            Shape::IncomingReference(shape) => (
+                "IncomingReference",
+                serde_json::to_value(&shape.predicate).unwrap_or_default(),
+            ),
+            Shape::ShapeAnd(_) => ("ShapeAnd", serde_json::Value::Null),
+            Shape::ShapeOr(_) => ("ShapeOr", serde_json::Value::Null),
+            Shape::Cardinality(_) => ("Cardinality", serde_json::Value::Null),
+            Shape::ShapeNot(_) => ("ShapeNot", serde_json::Value::Null),
+            Shape::NumericFacet(shape) => (
+                "NumericFacet",
+                serde_json::to_value(&shape.predicate).unwrap_or_default(),
+            ),
+        };
+        serde_json::json!({
+            "label": self.get_label(),
+            "variant": variant,
+            "predicate": predicate,
+        })
+    }
+
+    /// This function merges the triple constraints of a `ShapeAnd` that are
+    /// written more than once, that is, those equal to each other but for their
+    /// label. The constraints with the same predicates but different values or
+    /// facets are both kept, as a node may have several values for the same
+    /// predicate.
+    /// Nested shapes are simplified recursively.
+    ///
+    /// Returns:
+    ///
+    /// The simplified `Shape`.
+    pub fn simplify(self) -> Shape<T>
+    where
+        T: PartialEq,
+    {
+        match self {
+            Shape::TripleConstraint(_) | Shape::NumericFacet(_) => self,
+            Shape::ShapeReference(shape) => {
+                ShapeReference::new(shape.label, shape.predicate, shape.reference.simplify()).into()
+            }
+            Shape::IncomingReference(shape) => {
+                IncomingReference::new(shape.label, shape.predicate, shape.reference.simplify())
+                    .into()
+            }
+            Shape::ShapeAnd(shape) => {
+                let mut shapes: Vec<Shape<T>> = Vec::with_capacity(shape.shapes.len());
+                for child in shape.shapes {
+                    let child = child.simplify();
+                    if let Shape::TripleConstraint(constraint) = &child {
+                        let duplicate = shapes.iter().any(|shape| match shape {
+                            Shape::TripleConstraint(other) => {
+                                *other
+                                    == TripleConstraint {
+                                        label: other.label,
+                                        ..constraint.clone()
+                                    }
+                            }
+                            _ => false,
+                        });
+                        if duplicate {
+                            continue;
+                        }
+                    }
+                    shapes.push(child);
+                }
+                ShapeAnd::new(shape.label, shapes).into()
+            }
+            Shape::ShapeOr(shape) => ShapeOr::new(
+                shape.label,
+                shape.shapes.into_iter().map(Shape::simplify).collect(),
+            )
+            .into(),
+            Shape::Cardinality(shape) => {
+                Cardinality::new(shape.label, shape.shape.simplify(), shape.min, shape.max).into()
+            }
+            Shape::ShapeNot(shape) => ShapeNot::new(shape.label, shape.shape.simplify()).into(),
         }
     }
 }
@@ -79,11 +696,25 @@ impl<T: Literal + Clone> Shape<T> {
 /// field is used to specify the property that the constraint
 /// * `dst`: `dst` stands for "destination" and is of type `u32`. It likely
 /// represents the ID of the node that the triple constraint is pointing to.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TripleConstraint<T: Literal + Clone> {
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_label"))]
     label: &'static str,
-    predicate: T,
+    predicates: Vec<T>,
     object: NodeConstraint<T>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    distinct: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    normalize_iris: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pattern: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    min_length: Option<u32>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    max_length: Option<u32>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    inverse: bool,
 }
 
 /// The `ShapeReference` struct contains a label, property ID, and a reference to a
@@ -100,8 +731,29 @@ pub struct TripleConstraint<T: Literal + Clone> {
 /// * `reference`: `reference` is a field of type `Shape` that is contained within
 /// the `ShapeReference` struct. It is likely a reference to another instance of the
 /// `Shape` struct.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ShapeReference<T: Literal + Clone> {
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_label"))]
+    label: &'static str,
+    predicate: T,
+    reference: Shape<T>,
+}
+
+/// The `IncomingReference` struct is the counterpart of `ShapeReference`: it
+/// is satisfied by the nodes having an incoming edge, labelled with the given
+/// predicate, from a node conforming to the referenced shape. That is, it
+/// selects the children of the nodes conforming to the referenced shape.
+///
+/// Properties:
+///
+/// * `label`: The label of the shape.
+/// * `predicate`: The predicate of the incoming edge.
+/// * `reference`: The shape the source of the incoming edge must conform to.
+#[derive(Clone, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct IncomingReference<T: Literal + Clone> {
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_label"))]
     label: &'static str,
     predicate: T,
     reference: Shape<T>,
@@ -118,13 +770,17 @@ pub struct ShapeReference<T: Literal + Clone> {
 /// `ShapeComposite`. It can hold any number of `Shape` objects and allows for easy
 /// manipulation of the composite as a whole.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ShapeAnd<T: Literal + Clone> {
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_label"))]
     label: &'static str,
     shapes: Vec<Shape<T>>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ShapeOr<T: Literal + Clone> {
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_label"))]
     label: &'static str,
     shapes: Vec<Shape<T>>,
 }
@@ -143,8 +799,10 @@ pub struct ShapeOr<T: Literal + Clone> {
 /// maximum number of elements that can be contained within the shape defined by the
 /// `shape` property. It is of type `Bound`, which is an enum that can either be
 /// `Finite(usize)` to represent a specific number
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Cardinality<T: Literal + Clone> {
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_label"))]
     label: &'static str,
     shape: Shape<T>,
     min: Bound,
@@ -178,14 +836,254 @@ impl<T: Literal + Clone> TripleConstraint<T> {
     /// The struct is not specified in the code snippet provided, so it is not possible
     /// to determine the exact type being returned.
     pub fn new(label: &'static str, predicate: T, object: NodeConstraint<T>) -> Self {
+        Self::with_predicates(label, vec![predicate], object)
+    }
+
+    /// This is a constructor function that creates a new instance of a triple
+    /// constraint matching any of several equivalent predicates.
+    ///
+    /// Arguments:
+    ///
+    /// * `label`: The label associated with the constraint.
+    /// * `predicates`: The predicates any of which the edge must be labelled with.
+    /// * `object`: The constraint the destination of the edge must satisfy.
+    pub fn with_predicates(
+        label: &'static str,
+        predicates: Vec<T>,
+        object: NodeConstraint<T>,
+    ) -> Self {
         Self {
             label,
-            predicate,
+            predicates,
             object,
+            distinct: false,
+            normalize_iris: false,
+            pattern: None,
+            min_length: None,
+            max_length: None,
+            inverse: false,
+        }
+    }
+
+    /// This function makes a `NodeConstraint::Value` compare the IRIs in their
+    /// canonical form, so an object stored percent-encoded matches a value
+    /// provided unencoded, and vice versa. The canonical form is the fully
+    /// percent-decoded IRI: every `%XX` escape, in either case, is replaced by
+    /// the byte it stands for. IRIs whose decoded bytes are not valid UTF-8 are
+    /// kept as they are. The rest of the node constraints are not affected.
+    ///
+    /// Note that the decoding is not free: the objects are cast to strings and
+    /// decoded by a UDF, allocating a new string per edge, and this happens
+    /// in every superstep the constraint is evaluated, as the decoded objects
+    /// are not kept between them. For large graphs, it is cheaper to store the
+    /// IRIs already decoded when importing them, and compare them as usual.
+    pub fn with_normalized_iris(mut self) -> Self {
+        self.normalize_iris = true;
+        self
+    }
+
+    /// This function constrains the lexical form of the object to match a
+    /// regular expression, as the ShEx `PATTERN` facet does. The lexical form
+    /// of a literal is its value without the quotes, the datatype nor the
+    /// language tag, while that of an IRI is the IRI without the angle brackets.
+    /// The pattern is not anchored, so it may match any part of the lexical form.
+    ///
+    /// Arguments:
+    ///
+    /// * `pattern`: The regular expression the lexical form must match.
+    ///
+    /// Returns:
+    ///
+    /// A `Result` with the constraint or an error message in case the pattern is
+    /// not a valid regular expression, so it does not silently match nothing.
+    pub fn with_pattern(mut self, pattern: &str) -> Result<Self, String> {
+        match Regex::new(pattern) {
+            Ok(_) => {
+                self.pattern = Some(pattern.to_string());
+                Ok(self)
+            }
+            Err(error) => Err(format!("Invalid pattern {}: {}", pattern, error)),
+        }
+    }
+
+    /// This function constrains the number of characters of the lexical form of
+    /// the object, as defined in `with_pattern`, to be at least the provided one,
+    /// as the ShEx `MINLENGTH` facet does.
+    ///
+    /// Arguments:
+    ///
+    /// * `min_length`: The minimum number of characters, inclusive.
+    pub fn with_min_length(mut self, min_length: u32) -> Self {
+        self.min_length = Some(min_length);
+        self
+    }
+
+    /// This function constrains the number of characters of the lexical form of
+    /// the object, as defined in `with_pattern`, to be at most the provided one,
+    /// as the ShEx `MAXLENGTH` facet does.
+    ///
+    /// Arguments:
+    ///
+    /// * `max_length`: The maximum number of characters, inclusive.
+    pub fn with_max_length(mut self, max_length: u32) -> Self {
+        self.max_length = Some(max_length);
+        self
+    }
+
+    /// This function turns the constraint into an inverse one, as the ShEx
+    /// `^predicate` does: the edges are matched from their object, so the node
+    /// constraint and the facets are checked on the subject of the edge and the
+    /// label is assigned to the object. The messages of an inverse constraint
+    /// are thus sent to the objects of the edges, as those of an
+    /// `IncomingReference` are.
+    pub fn with_inverse(mut self) -> Self {
+        self.inverse = true;
+        self
+    }
+
+    /// This function returns whether the constraint is an inverse one.
+    pub fn is_inverse(&self) -> bool {
+        self.inverse
+    }
+
+    /// This function returns the column holding the node the node constraint is
+    /// checked on, which is the object of the edge unless the constraint is an
+    /// inverse one, in which case it is its subject.
+    fn node(&self) -> Expr {
+        match self.inverse {
+            true => Column::subject(Column::VertexId),
+            false => Column::edge(Object),
+        }
+    }
+
+    /// This function builds the expression checking the string facets of the
+    /// constraint, if any, on the lexical form of the node. The nodes with no
+    /// lexical form, such as blank nodes, do not satisfy any facet.
+    fn facets(&self) -> Expr {
+        let lexical = lexical_form(self.node());
+        let mut facets = lit(true);
+        if let Some(pattern) = &self.pattern {
+            facets = facets.and(lexical.clone().str().contains(lit(pattern.as_str()), true));
+        }
+        if let Some(min_length) = self.min_length {
+            facets = facets.and(lexical.clone().str().len_chars().gt_eq(lit(min_length)));
+        }
+        if let Some(max_length) = self.max_length {
+            facets = facets.and(lexical.str().len_chars().lt_eq(lit(max_length)));
+        }
+        facets.fill_null(lit(false))
+    }
+}
+
+impl<T: Literal + Clone> TripleConstraint<T> {
+    /// This function returns the predicates and the value the object must be
+    /// equal to, provided the constraint is a `NodeConstraint::Value` and it is
+    /// not an inverse one.
+    pub(crate) fn value_constraint(&self) -> Option<(&[T], &T)> {
+        match &self.object {
+            NodeConstraint::Value(value) if !self.inverse => Some((&self.predicates, value)),
+            _ => None,
         }
     }
 }
 
+/// `RDF_TYPE` is the IRI of the `rdf:type` predicate, as stored by the N-Triples
+/// backend, which is what the Turtle `a` shortcut stands for.
+pub const RDF_TYPE: &str = "<http://www.w3.org/1999/02/22-rdf-syntax-ns#type>";
+
+/// This block contains the constructors that are specific to the schemas whose
+/// predicates and objects are IRIs, such as those validated against graphs
+/// imported from N-Triples.
+impl TripleConstraint<&'static str> {
+    /// This is a constructor function that normalizes the predicate and the
+    /// object before creating the constraint, so they match the form in which the
+    /// N-Triples backend stores the IRIs: the full IRI, fragment included, wrapped
+    /// in angle brackets. For instance, `rdf:type` is stored as
+    /// `<http://www.w3.org/1999/02/22-rdf-syntax-ns#type>`. The Turtle `a`
+    /// shortcut and the `rdf:` prefix are expanded, and bare IRIs such as
+    /// `http://www.w3.org/1999/02/22-rdf-syntax-ns#type` are wrapped in brackets.
+    ///
+    /// Arguments:
+    ///
+    /// * `label`: The label associated with the constraint.
+    /// * `predicate`: The IRI of the predicate or the `a` shortcut.
+    /// * `object`: The constraint the destination of the edge must satisfy.
+    pub fn iri(
+        label: &'static str,
+        predicate: &'static str,
+        object: NodeConstraint<&'static str>,
+    ) -> Self {
+        let predicate = match predicate {
+            "a" => RDF_TYPE,
+            predicate => normalize_iri(predicate),
+        };
+        let object = match object {
+            NodeConstraint::Value(value) => NodeConstraint::Value(normalize_iri(value)),
+            object => object,
+        };
+        Self::new(label, predicate, object)
+    }
+
+    /// This is a constructor function that creates a constraint on both the
+    /// predicate of an edge and the datatype of its object, as `typed` does for
+    /// the integer ids, e.g. a `schema:birthDate` whose value is an
+    /// `xsd:dateTime`. The datatype is checked against the `dtype` column, so
+    /// the edges must be imported with `ImportOptions::with_datatypes`. The
+    /// predicate is normalized as `iri` does, while the datatype IRI may be
+    /// written in any of the forms accepted by `from_xsd_iri`.
+    ///
+    /// Arguments:
+    ///
+    /// * `label`: The label associated with the constraint.
+    /// * `predicate`: The IRI of the predicate or the `a` shortcut.
+    /// * `datatype`: The IRI of the datatype the object must be a literal of.
+    pub fn typed_iri(label: &'static str, predicate: &'static str, datatype: &str) -> Self {
+        let datatype = datatype
+            .strip_prefix('<')
+            .and_then(|datatype| datatype.strip_suffix('>'))
+            .unwrap_or(datatype);
+        let datatype = intern(format!("<{}>", NTriples::canonical_datatype(datatype)));
+        Self::iri(label, predicate, NodeConstraint::Datatype(datatype))
+    }
+}
+
+/// This block contains the constructors that are specific to the schemas whose
+/// predicates and objects are the integer ids used by the DuckDB backend.
+impl TripleConstraint<u32> {
+    /// This is a constructor function that creates a constraint on both the
+    /// predicate of an edge and the datatype of its object, e.g. a `P569` whose
+    /// value is a `DateTime`. As the DuckDB backend stores the literals as the id
+    /// of their datatype, both conditions are checked in a single expression.
+    ///
+    /// Arguments:
+    ///
+    /// * `label`: The label associated with the constraint.
+    /// * `predicate`: The predicate the edge must be labelled with.
+    /// * `dtype`: The Wikidata `DataType` the object of the edge must be a literal of.
+    pub fn typed(label: &'static str, predicate: u32, dtype: WikidataType) -> Self {
+        Self::new(
+            label,
+            predicate,
+            NodeConstraint::Value(u32::from(Id::DataType(dtype))),
+        )
+    }
+}
+
+/// This function rewrites an IRI in the form stored by the N-Triples backend.
+/// Literals and IRIs already wrapped in angle brackets are left untouched. As
+/// the constraints hold `&'static str` values, the rewritten IRIs are interned,
+/// so each distinct IRI is allocated once, however many constraints use it.
+fn normalize_iri(iri: &'static str) -> &'static str {
+    let expanded = match iri.strip_prefix("rdf:") {
+        Some(name) => format!("<http://www.w3.org/1999/02/22-rdf-syntax-ns#{}>", name),
+        None if iri.starts_with("http://") || iri.starts_with("https://") => {
+            format!("<{}>", iri)
+        }
+        None => return iri,
+    };
+    intern(expanded)
+}
+
 /// The above code is implementing a conversion from a `TripleConstraint` struct to
 /// a `Shape` enum using the `From` trait. It creates a new `Shape` enum variant
 /// called `TripleConstraint` and assigns the value of the `TripleConstraint` struct
@@ -218,16 +1116,59 @@ impl<T: Literal + Clone> Validate for TripleConstraint<T> {
     /// specified in the `when` function is true, then the `then` function will return a
     /// `lit` expression with the value of `self.label`. Otherwise, the `otherwise`
     /// function will return the `prev` expression.
-    fn validate(self, prev: Expr) -> Expr {
+    fn validate(self, prev: Expr, labels: &Labels) -> Expr {
+        let facets = self.facets();
+        let node = self.node();
         when(
-            Column::edge(Predicate)
-                .eq(lit(self.predicate))
+            is_in_values(Column::edge(Predicate), self.predicates)
                 .and(match self.object {
-                    NodeConstraint::Value(value) => Column::edge(Object).eq(lit(value)),
+                    NodeConstraint::Value(value) if self.normalize_iris => {
+                        percent_decoded(node.clone()).eq(percent_decoded(lit(value)))
+                    }
+                    NodeConstraint::Value(value) => node.clone().eq(lit(value)),
                     NodeConstraint::Any => lit(true),
+                    // A single value is compared as `Value` does, so both are equivalent.
+                    NodeConstraint::ValueSet(mut values) if values.len() == 1 => {
+                        node.clone().eq(lit(values.remove(0)))
+                    }
+                    NodeConstraint::ValueSet(values) => is_in_values(node.clone(), values),
+                    NodeConstraint::NotValueSet(values) => is_in_values(node.clone(), values).not(),
+                    NodeConstraint::AnyLangString => node
+                        .clone()
+                        .cast(DataType::String)
+                        .str()
+                        .contains(lit(r#""@[a-zA-Z]+(-[a-zA-Z0-9]+)*$"#), true),
+                    NodeConstraint::DateBefore(date) => literal_date(node.clone()).lt(lit(date)),
+                    NodeConstraint::DateAfter(date) => literal_date(node.clone()).gt(lit(date)),
+                    NodeConstraint::QuantityUnit(unit) => {
+                        Column::edge(Custom("unit")).eq(lit(unit))
+                    }
+                    NodeConstraint::Datatype(dtype) => Column::edge(Custom("dtype")).eq(lit(dtype)),
+                    NodeConstraint::IdRange(min, max) => {
+                        let id = node
+                            .clone()
+                            .cast(DataType::String)
+                            .str()
+                            .extract(lit(r"/[QPL]([0-9]+)>$"), 1)
+                            .cast(DataType::UInt32);
+                        id.clone().gt_eq(lit(min)).and(id.lt_eq(lit(max)))
+                    }
+                    NodeConstraint::Custom(matcher) => matched(node.clone(), matcher),
+                })
+                .and(facets)
+                // Only one of the edges to the same object is taken into account, if requested.
+                .and(match self.distinct {
+                    true => node.is_first_distinct().over([
+                        match self.inverse {
+                            true => Column::object(Column::VertexId),
+                            false => Column::subject(Column::VertexId),
+                        },
+                        Column::edge(Predicate),
+                    ]),
+                    false => lit(true),
                 }),
         )
-        .then(lit(self.label))
+        .then(labels.lit(self.label))
         .otherwise(prev)
     }
 }
@@ -273,6 +1214,10 @@ impl<T: Literal + Clone> ShapeReference<T> {
     pub fn get_reference(self) -> Shape<T> {
         self.reference
     }
+
+    pub(crate) fn get_predicate(&self) -> &T {
+        &self.predicate
+    }
 }
 
 /// The above code is implementing the `From` trait for the `ShapeReference` struct,
@@ -305,18 +1250,163 @@ impl<T: Literal + Clone> Validate for ShapeReference<T> {
     /// specified in the `when` function is true, then the `then` function will return a
     /// `lit` expression with the value of `self.label`. Otherwise, the `otherwise`
     /// function will return the `prev` parameter that was passed
-    fn validate(self, prev: Expr) -> Expr {
+    fn validate(self, prev: Expr, labels: &Labels) -> Expr {
         when(
             Column::object(Custom("labels"))
                 .list()
-                .contains(lit(self.reference.get_label()))
+                .contains(labels.lit(self.reference.get_label()))
+                .and(Column::edge(Predicate).eq(lit(self.predicate))),
+        )
+        .then(labels.lit(self.label))
+        .otherwise(prev)
+    }
+}
+
+impl<T: Literal + Clone> IncomingReference<T> {
+    /// This is a constructor function that creates a new instance of an incoming
+    /// reference.
+    ///
+    /// Arguments:
+    ///
+    /// * `label`: The label of the shape.
+    /// * `predicate`: The predicate of the incoming edge.
+    /// * `reference`: The shape the source of the incoming edge must conform to.
+    pub fn new(label: &'static str, predicate: T, reference: Shape<T>) -> Self {
+        Self {
+            label,
+            predicate,
+            reference,
+        }
+    }
+
+    pub fn get_reference(self) -> Shape<T> {
+        self.reference
+    }
+
+    pub(crate) fn get_predicate(&self) -> &T {
+        &self.predicate
+    }
+}
+
+impl<T: Literal + Clone> From<IncomingReference<T>> for Shape<T> {
+    fn from(value: IncomingReference<T>) -> Self {
+        Shape::IncomingReference(Box::from(value))
+    }
+}
+
+/// The messages of the `IncomingReference` are sent to the object of the edges,
+/// so the subject is the node that must conform to the referenced shape.
+impl<T: Literal + Clone> Validate for IncomingReference<T> {
+    fn validate(self, prev: Expr, labels: &Labels) -> Expr {
+        when(
+            Column::subject(Custom("labels"))
+                .list()
+                .contains(labels.lit(self.reference.get_label()))
                 .and(Column::edge(Predicate).eq(lit(self.predicate))),
         )
-        .then(lit(self.label))
+        .then(labels.lit(self.label))
         .otherwise(prev)
     }
 }
 
+/// The `SubclassConstraint` struct matches the instances of a class or of any of
+/// its subclasses, up to a certain depth. It is not a shape on its own, but it
+/// is expanded into a `ShapeOr` of the direct instances of the root class and
+/// the instances of any class reaching it within `max_depth` subclass hops.
+/// The latter classes are matched by a chain of `ShapeOr`s, one per depth, of
+/// the direct subclasses of the root class and a `ShapeReference` over the
+/// subclass predicate to the classes reaching it within one hop less, so the
+/// transitive closure is computed by the Pregel supersteps. Hence, the shapes
+/// and the labels grow linearly with the depth: the labels of the inner shapes
+/// are derived from the provided one and the depth, e.g. `Instance_subclass_2`,
+/// so no more than `2 * max_depth + 2` are used, far below the number of labels
+/// a `SymbolTable` holds. The derived labels are interned, so expanding the same
+/// constraint again does not allocate them anew.
+///
+/// Properties:
+///
+/// * `label`: The label of the resulting shape.
+/// * `instance_predicate`: The predicate linking an instance to its class.
+/// * `subclass_predicate`: The predicate linking a class to its superclass.
+/// * `root_class`: The class whose instances, direct or not, are matched.
+/// * `max_depth`: The maximum number of subclass hops from the root class.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SubclassConstraint<T: Literal + Clone> {
+    label: &'static str,
+    instance_predicate: T,
+    subclass_predicate: T,
+    root_class: T,
+    max_depth: u8,
+}
+
+impl<T: Literal + Clone> SubclassConstraint<T> {
+    pub fn new(
+        label: &'static str,
+        instance_predicate: T,
+        subclass_predicate: T,
+        root_class: T,
+        max_depth: u8,
+    ) -> Self {
+        Self {
+            label,
+            instance_predicate,
+            subclass_predicate,
+            root_class,
+            max_depth,
+        }
+    }
+
+    /// This function builds the shape matching the classes that reach the root
+    /// class within `depth` subclass hops, which must be at least one.
+    fn subclasses(&self, depth: u8) -> Shape<T> {
+        let direct = TripleConstraint::new(
+            intern(format!("{}_subclass_1", self.label)),
+            self.subclass_predicate.clone(),
+            NodeConstraint::Value(self.root_class.clone()),
+        )
+        .into();
+        match depth {
+            0 | 1 => direct,
+            depth => {
+                let indirect = ShapeReference::new(
+                    intern(format!("{}_superclass_{}", self.label, depth)),
+                    self.subclass_predicate.clone(),
+                    self.subclasses(depth - 1),
+                )
+                .into();
+                ShapeOr::new(
+                    intern(format!("{}_subclass_{}", self.label, depth)),
+                    vec![direct, indirect],
+                )
+                .into()
+            }
+        }
+    }
+}
+
+impl<T: Literal + Clone> From<SubclassConstraint<T>> for Shape<T> {
+    fn from(value: SubclassConstraint<T>) -> Self {
+        let instances = TripleConstraint::new(
+            intern(format!("{}_instance", value.label)),
+            value.instance_predicate.clone(),
+            NodeConstraint::Value(value.root_class.clone()),
+        )
+        .into();
+        let subclasses = match value.max_depth {
+            0 => None,
+            depth => Some(
+                ShapeReference::new(
+                    intern(format!("{}_subclass_instance", value.label)),
+                    value.instance_predicate.clone(),
+                    value.subclasses(depth),
+                )
+                .into(),
+            ),
+        };
+        ShapeOr::from_shapes(value.label, std::iter::once(instances).chain(subclasses)).into()
+    }
+}
+
 /// This is an implementation of the `ShapeComposite` struct, which defines two
 /// methods: `new` and `get_shapes`.
 impl<T: Literal + Clone> ShapeAnd<T> {
@@ -341,6 +1431,35 @@ impl<T: Literal + Clone> ShapeAnd<T> {
         Self { label, shapes }
     }
 
+    /// This is a constructor function that collects the shapes yielded by an
+    /// iterator into a new composite, so it can be built at the end of a pipeline.
+    ///
+    /// Arguments:
+    ///
+    /// * `label`: The label of the composite.
+    /// * `shapes`: Any `IntoIterator` yielding the shapes of the composite.
+    pub fn from_shapes<I: IntoIterator<Item = Shape<T>>>(label: &'static str, shapes: I) -> Self {
+        Self::new(label, shapes.into_iter().collect())
+    }
+
+    /// This is a fallible constructor that makes sure the composite is not
+    /// empty, as such composites would silently produce no labels.
+    ///
+    /// Arguments:
+    ///
+    /// * `label`: The label of the composite.
+    /// * `shapes`: The shapes of the composite.
+    ///
+    /// Returns:
+    ///
+    /// The `Shape` or a `ShapeError::EmptyComposite` if there are no shapes.
+    pub fn try_new(label: &'static str, shapes: Vec<Shape<T>>) -> Result<Shape<T>, ShapeError> {
+        match shapes.is_empty() {
+            true => Err(ShapeError::EmptyComposite(label)),
+            false => Ok(Self::new(label, shapes).into()),
+        }
+    }
+
     /// This function returns a vector of shapes.
     ///
     /// Returns:
@@ -365,6 +1484,19 @@ impl<T: Literal + Clone> From<ShapeAnd<T>> for Shape<T> {
     }
 }
 
+/// The conversion builds a `ShapeAnd` out of its label and its shapes, checking
+/// them as `ShapeAnd::try_new` does.
+impl<T: Literal + Clone> TryFrom<(&'static str, Vec<Shape<T>>)> for ShapeAnd<T> {
+    type Error = ShapeError;
+
+    fn try_from((label, shapes): (&'static str, Vec<Shape<T>>)) -> Result<Self, Self::Error> {
+        match Self::try_new(label, shapes)? {
+            Shape::ShapeAnd(shape) => Ok(shape),
+            _ => unreachable!("ShapeAnd::try_new always builds a ShapeAnd"),
+        }
+    }
+}
+
 /// This is an implementation of the `Validate` trait for the `ShapeComposite`
 /// struct. The `Validate` trait defines a method `validate` that takes an `Expr`
 /// argument and returns an `Expr`. The purpose of this trait is to provide a way to
@@ -388,20 +1520,20 @@ impl<T: Literal + Clone> Validate for ShapeAnd<T> {
     /// checks if all the labels of the shapes in `self.shapes` are in the
     /// `Column::msg(None)` list. If the condition is true, it concatenates `self.label`
     /// and `prev` using the `
-    fn validate(self, prev: Expr) -> Expr {
+    fn validate(self, prev: Expr, labels: &Labels) -> Expr {
         when(
             self.shapes
                 .iter()
                 .fold(lit(true), |acc, shape| {
                     acc.and(
-                        lit(shape.get_label())
-                            .cast(DataType::Categorical(None, CategoricalOrdering::Lexical))
-                            .is_in(Column::subject(Column::Custom("labels"))),
+                        Column::subject(Column::Custom("labels"))
+                            .list()
+                            .contains(labels.lit(shape.get_label())),
                     )
                 })
                 .and(Column::subject(Column::VertexId).is_first_distinct()),
         )
-        .then(lit(self.label))
+        .then(labels.lit(self.label))
         .otherwise(prev)
     }
 }
@@ -413,6 +1545,35 @@ impl<T: Literal + Clone> ShapeOr<T> {
         Self { label, shapes }
     }
 
+    /// This is a constructor function that collects the shapes yielded by an
+    /// iterator into a new composite, so it can be built at the end of a pipeline.
+    ///
+    /// Arguments:
+    ///
+    /// * `label`: The label of the composite.
+    /// * `shapes`: Any `IntoIterator` yielding the shapes of the composite.
+    pub fn from_shapes<I: IntoIterator<Item = Shape<T>>>(label: &'static str, shapes: I) -> Self {
+        Self::new(label, shapes.into_iter().collect())
+    }
+
+    /// This is a fallible constructor that makes sure the composite is not
+    /// empty, as such composites would silently produce no labels.
+    ///
+    /// Arguments:
+    ///
+    /// * `label`: The label of the composite.
+    /// * `shapes`: The shapes of the composite.
+    ///
+    /// Returns:
+    ///
+    /// The `Shape` or a `ShapeError::EmptyComposite` if there are no shapes.
+    pub fn try_new(label: &'static str, shapes: Vec<Shape<T>>) -> Result<Shape<T>, ShapeError> {
+        match shapes.is_empty() {
+            true => Err(ShapeError::EmptyComposite(label)),
+            false => Ok(Self::new(label, shapes).into()),
+        }
+    }
+
     pub fn get_shapes(&self) -> Vec<Shape<T>> {
         self.shapes.to_vec()
     }
@@ -424,19 +1585,32 @@ impl<T: Literal + Clone> From<ShapeOr<T>> for Shape<T> {
     }
 }
 
+/// The conversion builds a `ShapeOr` out of its label and its shapes, checking
+/// them as `ShapeOr::try_new` does.
+impl<T: Literal + Clone> TryFrom<(&'static str, Vec<Shape<T>>)> for ShapeOr<T> {
+    type Error = ShapeError;
+
+    fn try_from((label, shapes): (&'static str, Vec<Shape<T>>)) -> Result<Self, Self::Error> {
+        match Self::try_new(label, shapes)? {
+            Shape::ShapeOr(shape) => Ok(shape),
+            _ => unreachable!("ShapeOr::try_new always builds a ShapeOr"),
+        }
+    }
+}
+
 impl<T: Literal + Clone> Validate for ShapeOr<T> {
-    fn validate(self, prev: Expr) -> Expr {
+    fn validate(self, prev: Expr, labels: &Labels) -> Expr {
         when(
             self.shapes
                 .iter()
                 .fold(lit(false), |acc, shape| {
-                    acc.or(lit(shape.get_label())
-                        .cast(DataType::Categorical(None, CategoricalOrdering::Lexical))
-                        .is_in(Column::subject(Column::Custom("labels"))))
+                    acc.or(Column::subject(Column::Custom("labels"))
+                        .list()
+                        .contains(labels.lit(shape.get_label())))
                 })
                 .and(Column::subject(Column::VertexId).is_first_distinct()),
         )
-        .then(lit(self.label))
+        .then(labels.lit(self.label))
         .otherwise(prev)
     }
 }
@@ -473,6 +1647,63 @@ impl<T: Literal + Clone> Cardinality<T> {
         }
     }
 
+    /// This is a fallible constructor that makes sure the minimum bound is not
+    /// greater than the maximum one, as no node could satisfy the cardinality.
+    /// `Bound::Many` stands for `u8::MAX` as it does when validating.
+    ///
+    /// Arguments:
+    ///
+    /// * `label`: The label of the cardinality.
+    /// * `shape`: The shape whose occurrences are counted.
+    /// * `min`: The minimum bound.
+    /// * `max`: The maximum bound.
+    ///
+    /// Returns:
+    ///
+    /// The `Shape` or a `ShapeError::InvalidBounds` if `min` exceeds `max`.
+    pub fn try_new(
+        label: &'static str,
+        shape: Shape<T>,
+        min: Bound,
+        max: Bound,
+    ) -> Result<Shape<T>, ShapeError> {
+        let lowest = match min {
+            Bound::Inclusive(min) => min as i16,
+            Bound::Exclusive(min) => min as i16 + 1,
+            Bound::Zero => 0,
+            Bound::Many => u8::MAX as i16,
+        };
+        let highest = match max {
+            Bound::Inclusive(max) => max as i16,
+            Bound::Exclusive(max) => max as i16 - 1,
+            Bound::Zero => 0,
+            Bound::Many => u8::MAX as i16,
+        };
+        match lowest > highest {
+            true => Err(ShapeError::InvalidBounds(label)),
+            false => Ok(Self::new(label, shape, min, max).into()),
+        }
+    }
+
+    /// This function makes the cardinality count the distinct objects of the
+    /// node instead of its edges, so several edges with the same predicate and
+    /// the same object count once. Only a `TripleConstraint` can be counted this
+    /// way, as the rest of the shapes are not bound to a single edge.
+    ///
+    /// Returns:
+    ///
+    /// The cardinality counting the distinct objects, or an error in case the
+    /// shape it counts is not a `TripleConstraint`.
+    pub fn with_distinct_objects(mut self) -> Result<Self, ShapeError> {
+        match &mut self.shape {
+            Shape::TripleConstraint(shape) => {
+                shape.distinct = true;
+                Ok(self)
+            }
+            _ => Err(ShapeError::DistinctObjects(self.label)),
+        }
+    }
+
     /// This Rust function returns the shape of an object.
     ///
     /// Returns:
@@ -500,12 +1731,14 @@ impl<T: Literal + Clone> Validate for Cardinality<T> {
     /// Returns:
     ///
     /// The `validate` function is returning an `Expr` object.
-    fn validate(self, prev: Expr) -> Expr {
+    fn validate(self, prev: Expr, labels: &Labels) -> Expr {
         let count = Column::subject(Column::Custom("labels"))
             .list()
-            .eval(col("").eq(lit(self.shape.get_label())), true)
+            .eval(col("").eq(labels.lit(self.shape.get_label())), true)
             .list()
-            .sum();
+            .sum()
+            // A vertex that received no labels matched the shape zero times.
+            .fill_null(lit(0u32));
 
         when(
             match self.min {
@@ -522,7 +1755,7 @@ impl<T: Literal + Clone> Validate for Cardinality<T> {
             })
             .and(Column::subject(Column::VertexId).is_first_distinct()),
         )
-        .then(lit(self.label))
+        .then(labels.lit(self.label))
         .otherwise(prev)
     }
 }
@@ -537,3 +1770,439 @@ impl<T: Literal + Clone> From<Cardinality<T>> for Shape<T> {
         Shape::Cardinality(Box::from(value))
     }
 }
+
+/// The conversion builds a `Cardinality` out of its label, its shape and its
+/// bounds, checking them as `Cardinality::try_new` does.
+impl<T: Literal + Clone> TryFrom<(&'static str, Shape<T>, Bound, Bound)> for Cardinality<T> {
+    type Error = ShapeError;
+
+    fn try_from(
+        (label, shape, min, max): (&'static str, Shape<T>, Bound, Bound),
+    ) -> Result<Self, Self::Error> {
+        match Self::try_new(label, shape, min, max)? {
+            Shape::Cardinality(shape) => Ok(*shape),
+            _ => unreachable!("Cardinality::try_new always builds a Cardinality"),
+        }
+    }
+}
+
+/// The `ShapeNot` struct negates a shape, so it is satisfied by the nodes that
+/// do not conform to the shape it wraps, e.g. the humans who are not fictional
+/// characters when combined with a `ShapeAnd`. As labels are only assigned to
+/// the nodes having outgoing edges, those are the only ones a `ShapeNot` can
+/// be assigned to.
+///
+/// Properties:
+///
+/// * `label`: The label of the negation.
+/// * `shape`: The shape the nodes must not conform to.
+#[derive(Clone, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ShapeNot<T: Literal + Clone> {
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_label"))]
+    label: &'static str,
+    shape: Shape<T>,
+}
+
+impl<T: Literal + Clone> ShapeNot<T> {
+    /// This is a constructor function that creates the negation of a shape.
+    ///
+    /// Arguments:
+    ///
+    /// * `label`: The label of the negation.
+    /// * `shape`: The shape the nodes must not conform to.
+    pub fn new(label: &'static str, shape: Shape<T>) -> Self {
+        Self { label, shape }
+    }
+
+    pub fn get_shape(self) -> Shape<T> {
+        self.shape
+    }
+}
+
+impl<T: Literal + Clone> Validate for ShapeNot<T> {
+    /// The function assigns the label of the negation to the nodes whose labels,
+    /// computed during the previous superstep, lack that of the negated shape.
+    fn validate(self, prev: Expr, labels: &Labels) -> Expr {
+        when(
+            Column::subject(Column::Custom("labels"))
+                .list()
+                .contains(labels.lit(self.shape.get_label()))
+                .fill_null(lit(false))
+                .not()
+                .and(Column::subject(Column::VertexId).is_first_distinct()),
+        )
+        .then(labels.lit(self.label))
+        .otherwise(prev)
+    }
+}
+
+impl<T: Literal + Clone> From<ShapeNot<T>> for Shape<T> {
+    fn from(value: ShapeNot<T>) -> Self {
+        Shape::ShapeNot(Box::from(value))
+    }
+}
+
+/// The `NumericFacet` struct constrains the literals pointed to by the edges
+/// labelled with a predicate to lie within a range, as the ShEx `MININCLUSIVE`,
+/// `MINEXCLUSIVE`, `MAXINCLUSIVE` and `MAXEXCLUSIVE` facets do, e.g. the cities
+/// whose population is above a million. Only the literals of the numeric XSD
+/// datatypes, such as `xsd:integer`, `xsd:decimal` or `xsd:double`, written in
+/// full as in N-Triples, are taken into account, so a plain string such as
+/// `"123"` does not satisfy it, nor does an IRI. Their lexical form is parsed
+/// as a number, so an optional leading `+`, as in the Wikidata quantities, is
+/// accepted. A missing bound leaves that end of the range unbounded, whereas
+/// `Bound::Zero` and `Bound::Many` stand for zero and infinity, respectively.
+///
+/// Properties:
+///
+/// * `label`: The label of the facet.
+/// * `predicate`: The predicate of the edges pointing to the literals.
+/// * `min`: The lower bound of the range, if any.
+/// * `max`: The upper bound of the range, if any.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NumericFacet<T: Literal + Clone> {
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_label"))]
+    label: &'static str,
+    predicate: T,
+    min: Option<Bound<f64>>,
+    max: Option<Bound<f64>>,
+}
+
+impl<T: Literal + Clone> NumericFacet<T> {
+    /// This is a constructor function that creates a new numeric facet.
+    ///
+    /// Arguments:
+    ///
+    /// * `label`: The label of the facet.
+    /// * `predicate`: The predicate of the edges pointing to the literals.
+    /// * `min`: The lower bound of the range, or `None` for no lower bound.
+    /// * `max`: The upper bound of the range, or `None` for no upper bound.
+    pub fn new(
+        label: &'static str,
+        predicate: T,
+        min: Option<Bound<f64>>,
+        max: Option<Bound<f64>>,
+    ) -> Self {
+        Self {
+            label,
+            predicate,
+            min,
+            max,
+        }
+    }
+}
+
+/// This function parses the literals of the numeric XSD datatypes contained in
+/// the provided expression, written as in N-Triples, into `f64` values. Any
+/// other value, including the numbers written as plain strings, results in NULL.
+fn literal_number(expr: Expr) -> Expr {
+    expr.cast(DataType::String)
+        .str()
+        .extract(
+            lit(concat!(
+                r#"^"\+?(-?[0-9]*\.?[0-9]+([eE][+-]?[0-9]+)?)"\^\^"#,
+                r"<http://www\.w3\.org/2001/XMLSchema#(integer|decimal|double|float|long|int|",
+                r"short|byte|nonNegativeInteger|positiveInteger|nonPositiveInteger|",
+                r"negativeInteger|unsignedLong|unsignedInt|unsignedShort|unsignedByte)>$",
+            )),
+            1,
+        )
+        .cast(DataType::Float64)
+}
+
+impl<T: Literal + Clone> Validate for NumericFacet<T> {
+    /// The function assigns the label of the facet to the subjects of the edges
+    /// labelled with its predicate whose object is a number within the range.
+    fn validate(self, prev: Expr, labels: &Labels) -> Expr {
+        let number = literal_number(Column::edge(Object));
+        let min = match self.min {
+            Some(Bound::Inclusive(min)) => number.clone().gt_eq(lit(min)),
+            Some(Bound::Exclusive(min)) => number.clone().gt(lit(min)),
+            Some(Bound::Zero) => number.clone().gt_eq(lit(0f64)),
+            Some(Bound::Many) => number.clone().gt_eq(lit(f64::INFINITY)),
+            None => number.clone().is_not_null(),
+        };
+        let max = match self.max {
+            Some(Bound::Inclusive(max)) => number.clone().lt_eq(lit(max)),
+            Some(Bound::Exclusive(max)) => number.clone().lt(lit(max)),
+            Some(Bound::Zero) => number.clone().lt_eq(lit(0f64)),
+            Some(Bound::Many) => number.lt_eq(lit(f64::INFINITY)),
+            None => number.is_not_null(),
+        };
+        when(
+            Column::edge(Predicate)
+                .eq(lit(self.predicate))
+                .and(min)
+                .and(max)
+                .fill_null(lit(false)),
+        )
+        .then(labels.lit(self.label))
+        .otherwise(prev)
+    }
+}
+
+impl<T: Literal + Clone> From<NumericFacet<T>> for Shape<T> {
+    fn from(value: NumericFacet<T>) -> Self {
+        Shape::NumericFacet(value)
+    }
+}
+
+/// The bounds of a `NumericFacet` hold `f64` values, which do not implement
+/// `Hash`, so their bits are hashed instead.
+impl<T: Literal + Clone + Hash> Hash for NumericFacet<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.label.hash(state);
+        self.predicate.hash(state);
+        for bound in [&self.min, &self.max] {
+            bound.as_ref().map(std::mem::discriminant).hash(state);
+            if let Some(Bound::Inclusive(value) | Bound::Exclusive(value)) = bound {
+                value.to_bits().hash(state);
+            }
+        }
+    }
+}
+
+/// The shapes of the composites are hashed in the order of their labels, so the
+/// composites holding the same shapes in a different order hash the same, as
+/// the order does not affect the validation. Shapes sharing the same label are
+/// ordered by their own hash, so the result does not depend on the order either.
+/// Such hashes are computed with a `StableHasher`, so they do not change across
+/// Rust releases, whatever the hasher of the composite is.
+fn hash_unordered<T: Literal + Clone + Hash, H: Hasher>(shapes: &[Shape<T>], state: &mut H) {
+    let mut keys = shapes
+        .iter()
+        .map(|shape| {
+            let mut hasher = StableHasher::new();
+            shape.hash(&mut hasher);
+            (shape.get_label(), hasher.finish())
+        })
+        .collect::<Vec<_>>();
+    keys.sort();
+    keys.hash(state);
+}
+
+impl<T: Literal + Clone + Hash> Hash for ShapeAnd<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.label.hash(state);
+        hash_unordered(&self.shapes, state);
+    }
+}
+
+impl<T: Literal + Clone + Hash> Hash for ShapeOr<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.label.hash(state);
+        hash_unordered(&self.shapes, state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::examples::Value::*;
+    use crate::utils::examples::*;
+
+    #[test]
+    fn expr_cost_test() {
+        assert!(complex_schema().estimate_expr_cost() > simple_schema().estimate_expr_cost())
+    }
+
+    #[test]
+    fn max_label_test() {
+        assert_eq!(Some(4), complex_schema().max_label())
+    }
+
+    #[test]
+    fn from_value_test() {
+        assert_eq!(Ok(u32::from(Id::from("Q515"))), u32::from_value("Q515"));
+        assert_eq!(Ok(80), u32::from_value("80"));
+        for value in ["Q", "P", "Q12a", "Q-1", "Q+1", "Q99999999999", "X1", ""] {
+            assert!(
+                u32::from_value(value).is_err(),
+                "{} should be rejected",
+                value
+            )
+        }
+    }
+
+    #[test]
+    fn subclass_labels_test() {
+        let subclass =
+            || -> Shape<u32> { SubclassConstraint::new("Instance", 31, 279, 12, 10).into() };
+        let (first, second) = (subclass(), subclass());
+        // The labels grow linearly with the depth and are shared by every expansion.
+        assert_eq!(2 * 10 + 2, first.labels().len());
+        assert_eq!(first.max_label(), second.max_label());
+        for label in first.labels() {
+            let shared = second.labels().into_iter().find(|other| *other == label);
+            assert_eq!(Some(label.as_ptr()), shared.map(str::as_ptr))
+        }
+    }
+
+    #[test]
+    fn contains_label_test() {
+        let schema = complex_schema();
+        assert!(schema.contains_label("UnitedKingdom") && !schema.contains_label("London"));
+        assert_eq!(5, schema.labels().len())
+    }
+
+    #[test]
+    fn hash_test() {
+        let hash = |shape: &Shape<u32>| {
+            let mut hasher = StableHasher::new();
+            shape.hash(&mut hasher);
+            hasher.finish()
+        };
+        let human = || {
+            TripleConstraint::new("Human", InstanceOf.id(), NodeConstraint::Value(Human.id()))
+                .into()
+        };
+        let birth_date =
+            || TripleConstraint::new("BirthDate", BirthDate.id(), NodeConstraint::Any).into();
+        let schema: Shape<u32> = ShapeAnd::new("Researcher", vec![human(), birth_date()]).into();
+        let reordered: Shape<u32> = ShapeAnd::new("Researcher", vec![birth_date(), human()]).into();
+        let modified: Shape<u32> = ShapeAnd::new(
+            "Researcher",
+            vec![
+                human(),
+                TripleConstraint::new(
+                    "BirthDate",
+                    BirthDate.id(),
+                    NodeConstraint::Value(DateTime.id()),
+                )
+                .into(),
+            ],
+        )
+        .into();
+        assert_eq!(hash(&complex_schema()), hash(&complex_schema()));
+        assert_eq!(hash(&schema), hash(&reordered));
+        assert_ne!(hash(&schema), hash(&modified))
+    }
+
+    #[test]
+    fn invalid_bounds_test() {
+        let shape: Shape<u32> =
+            TripleConstraint::new("BirthPlace", BirthPlace.id(), NodeConstraint::Any).into();
+        assert_eq!(
+            Err(ShapeError::InvalidBounds("cardinality")),
+            Cardinality::try_new(
+                "cardinality",
+                shape,
+                Bound::Inclusive(2),
+                Bound::Inclusive(1)
+            )
+        )
+    }
+
+    #[test]
+    fn empty_composite_test() {
+        assert_eq!(
+            Err(ShapeError::EmptyComposite("grouping")),
+            ShapeAnd::<u32>::try_new("grouping", vec![])
+        )
+    }
+
+    #[test]
+    fn try_from_test() {
+        let shape: Shape<u32> =
+            TripleConstraint::new("BirthPlace", BirthPlace.id(), NodeConstraint::Any).into();
+        assert_eq!(
+            Err(ShapeError::InvalidBounds("cardinality")),
+            Cardinality::try_from((
+                "cardinality",
+                shape.clone(),
+                Bound::Inclusive(2),
+                Bound::Inclusive(1)
+            ))
+        );
+        assert_eq!(
+            Err(ShapeError::EmptyComposite("grouping")),
+            ShapeOr::<u32>::try_from(("grouping", vec![]))
+        );
+        assert_eq!(
+            Ok(ShapeAnd::new("grouping", vec![shape.clone()])),
+            ShapeAnd::try_from(("grouping", vec![shape]))
+        )
+    }
+
+    #[test]
+    fn distinct_objects_test() {
+        let shape: Shape<u32> =
+            TripleConstraint::new("BirthPlace", BirthPlace.id(), NodeConstraint::Any).into();
+        let reference: Shape<u32> =
+            ShapeReference::new("Born", BirthPlace.id(), shape.clone()).into();
+        assert!(
+            Cardinality::new("cardinality", shape, Bound::Inclusive(2), Bound::Many)
+                .with_distinct_objects()
+                .is_ok()
+        );
+        assert_eq!(
+            Err(ShapeError::DistinctObjects("cardinality")),
+            Cardinality::new("cardinality", reference, Bound::Inclusive(2), Bound::Many)
+                .with_distinct_objects()
+        )
+    }
+
+    #[test]
+    fn simplify_test() -> Result<(), String> {
+        let schema: Shape<u32> = ShapeAnd::new(
+            "Researcher",
+            vec![
+                TripleConstraint::new("Human", InstanceOf.id(), NodeConstraint::Value(Human.id()))
+                    .into(),
+                TripleConstraint::new(
+                    "IsHuman",
+                    InstanceOf.id(),
+                    NodeConstraint::Value(Human.id()),
+                )
+                .into(),
+                TripleConstraint::new("BirthPlace", BirthPlace.id(), NodeConstraint::Any).into(),
+            ],
+        )
+        .into();
+        match schema.simplify() {
+            Shape::ShapeAnd(shape) if shape.get_shapes().len() == 2 => Ok(()),
+            shape => Err(format!("Unexpected simplified shape: {:?}", shape)),
+        }
+    }
+
+    #[test]
+    fn distinct_values_simplify_test() -> Result<(), String> {
+        let schema: Shape<u32> = ShapeAnd::new(
+            "Researcher",
+            vec![
+                TripleConstraint::new("Human", InstanceOf.id(), NodeConstraint::Value(Human.id()))
+                    .into(),
+                TripleConstraint::new("Award", InstanceOf.id(), NodeConstraint::Value(Award.id()))
+                    .into(),
+            ],
+        )
+        .into();
+        // A node may be an instance of both classes, so none of the constraints is discarded.
+        match schema.simplify() {
+            Shape::ShapeAnd(shape) if shape.get_shapes().len() == 2 => Ok(()),
+            shape => Err(format!("Unexpected simplified shape: {:?}", shape)),
+        }
+    }
+
+    #[test]
+    fn facets_simplify_test() -> Result<(), String> {
+        let employer = || TripleConstraint::new("Employer", Employer.id(), NodeConstraint::Any);
+        let schema: Shape<u32> = ShapeAnd::new(
+            "Employed",
+            vec![
+                employer().into(),
+                employer().with_min_length(3).into(),
+                employer().with_inverse().into(),
+                employer().into(),
+            ],
+        )
+        .into();
+        // Only the last constraint is the same as another one, as the rest differ in their facets.
+        match schema.simplify() {
+            Shape::ShapeAnd(shape) if shape.get_shapes().len() == 3 => Ok(()),
+            shape => Err(format!("Unexpected simplified shape: {:?}", shape)),
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::BTreeMap;
+
+use polars::prelude::*;
+use pregel_rs::graph_frame::GraphFrame;
+use pregel_rs::pregel::Column;
+
+use crate::pschema::PSchema;
+use crate::shape::shex::Shape;
+
+/// The function validates a graph and checks that exactly the expected subjects
+/// matched exactly the expected shapes, which is stricter than comparing the
+/// number of labels of each subject. The subjects are compared by their string
+/// representation, e.g. `<http://example.org/alan>` for the graphs imported
+/// from N-Triples or `80` for those holding integer ids, and the order of the
+/// subjects and of their labels does not matter.
+///
+/// Arguments:
+///
+/// * `graph`: The `GraphFrame` to be validated.
+/// * `schema`: The starting `Shape` of the schema.
+/// * `expected`: The subjects expected to match, each of them together with the
+/// labels of the shapes it is expected to match.
+///
+/// Returns:
+///
+/// a `Result<(), String>`, where the `String` describes the differences found
+/// between the expected and the actual results, if any.
+pub fn assert_conforms<T: Literal + Clone>(
+    graph: GraphFrame,
+    schema: Shape<T>,
+    expected: &[(&str, &[&str])],
+) -> Result<(), String> {
+    let subset = match PSchema::new(schema).validate(graph) {
+        Ok(subset) => subset,
+        Err(error) => return Err(error.to_string()),
+    };
+    let labels = Column::Custom("labels");
+    let matches = match subset
+        .lazy()
+        .group_by([col(Column::Subject.as_ref())])
+        .agg([col(labels.as_ref()).first()])
+        .select([
+            col(Column::Subject.as_ref()).cast(DataType::String),
+            col(labels.as_ref()).cast(DataType::List(Box::new(DataType::String))),
+        ])
+        .collect()
+    {
+        Ok(matches) => matches,
+        Err(error) => return Err(error.to_string()),
+    };
+
+    let mut actual = BTreeMap::new();
+    for i in 0..matches.height() {
+        let row = match matches.get_row(i) {
+            Ok(row) => row.0,
+            Err(_) => return Err(format!("Error retrieving the {}th row", i)),
+        };
+        let subject = row[0].get_str().unwrap_or_default().to_string();
+        let mut labels = match &row[1] {
+            AnyValue::List(labels) => match labels.str() {
+                Ok(labels) => labels
+                    .into_iter()
+                    .flatten()
+                    .map(String::from)
+                    .collect::<Vec<_>>(),
+                Err(_) => return Err(format!("The labels of {} are not strings", subject)),
+            },
+            _ => Vec::new(),
+        };
+        labels.sort();
+        actual.insert(subject, labels);
+    }
+
+    let expected = expected
+        .iter()
+        .map(|(subject, labels)| {
+            let mut labels = labels
+                .iter()
+                .map(|label| label.to_string())
+                .collect::<Vec<_>>();
+            labels.sort();
+            (subject.to_string(), labels)
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    match actual == expected {
+        true => Ok(()),
+        false => Err(format!(
+            "The subjects did not match the expected shapes\n expected: {:?}\n actual: {:?}",
+            expected, actual
+        )),
+    }
+}
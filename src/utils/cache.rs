@@ -0,0 +1,112 @@
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use polars::prelude::*;
+use pregel_rs::pregel::Column;
+
+use crate::shape::shex::Shape;
+use crate::utils::hash::StableHasher;
+
+/// The `Cache` struct stores the results of previous validations on disk, so
+/// running the same schema against the same graph does not recompute them.
+///
+/// Properties:
+///
+/// * `dir`: the directory where the cached subsets are stored as Parquet files.
+/// * `hits`: the number of times a validation has been read from the cache. It
+/// is shared among the clones of the `Cache`.
+#[derive(Clone, Debug)]
+pub struct Cache {
+    dir: PathBuf,
+    hits: Arc<AtomicUsize>,
+}
+
+impl Cache {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            hits: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// This function returns the number of validations read from the cache.
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::SeqCst)
+    }
+
+    /// The function computes the key of a validation by hashing the structure of
+    /// the schema, through the `Hash` of the `Shape`, together with the options
+    /// of the validation and the edges of the graph. Thus, any change in any of
+    /// them results in a different key, invalidating the previous entry, while
+    /// composites holding the same shapes in a different order share it. The key
+    /// is computed with a `StableHasher`, so the entries remain valid across Rust
+    /// releases.
+    ///
+    /// Arguments:
+    ///
+    /// * `shape`: the starting shape of the schema.
+    /// * `options`: a description of the options affecting the result.
+    /// * `edges`: the edges of the graph to be validated.
+    ///
+    /// Returns:
+    ///
+    /// a `Result<u64, PolarsError>` with the key of the validation. An error is
+    /// returned in case the schema holds any `NodeConstraint::Custom`, as their
+    /// closures cannot be told apart, so their results cannot be cached.
+    pub(crate) fn key<T: Literal + Clone + Hash>(
+        shape: &Shape<T>,
+        options: &str,
+        edges: &DataFrame,
+    ) -> PolarsResult<u64> {
+        if shape.has_custom_constraint() {
+            return Err(PolarsError::InvalidOperation(
+                "Schemas holding custom node constraints cannot be cached".into(),
+            ));
+        }
+        let mut hasher = StableHasher::new();
+        shape.hash(&mut hasher);
+        options.hash(&mut hasher);
+        for column in [Column::Subject, Column::Predicate, Column::Object] {
+            let series = edges
+                .column(column.as_ref())?
+                .as_materialized_series()
+                .cast(&DataType::String)?;
+            series
+                .str()?
+                .into_iter()
+                .for_each(|value| value.hash(&mut hasher));
+        }
+        Ok(hasher.finish())
+    }
+
+    /// The function computes a key identifying the structure of the schema on
+    /// its own, so runs of the same schema can be told apart from the rest.
+    pub(crate) fn schema_key<T: Literal + Clone + Hash>(shape: &Shape<T>) -> u64 {
+        let mut hasher = StableHasher::new();
+        shape.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn path(&self, key: u64) -> PathBuf {
+        self.dir.join(format!("{:016x}.parquet", key))
+    }
+
+    /// The function retrieves the subset stored for the provided key, if any.
+    pub(crate) fn get(&self, key: u64) -> Option<DataFrame> {
+        let file = File::open(self.path(key)).ok()?;
+        let subset = ParquetReader::new(file).finish().ok()?;
+        self.hits.fetch_add(1, Ordering::SeqCst);
+        Some(subset)
+    }
+
+    /// The function stores the subset resulting from a validation under the
+    /// provided key.
+    pub(crate) fn put(&self, key: u64, subset: &mut DataFrame) -> PolarsResult<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        ParquetWriter::new(File::create(self.path(key))?).finish(subset)?;
+        Ok(())
+    }
+}
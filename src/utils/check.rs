@@ -1,7 +1,13 @@
+#[cfg(feature = "auto-string-cache")]
+use polars::enable_string_cache;
 use polars::error::PolarsError;
 use polars::frame::DataFrame;
+use polars::prelude::{DataType, Expr, Literal};
+use polars::using_string_cache;
 use pregel_rs::pregel::Column;
 
+use crate::shape::shex::Shape;
+
 pub(crate) fn check_field(edges: &DataFrame, column: Column) -> Result<(), PolarsError> {
     if edges.schema().get(column.as_ref()).is_none() {
         return Err(PolarsError::SchemaFieldNotFound(
@@ -12,3 +18,46 @@ pub(crate) fn check_field(edges: &DataFrame, column: Column) -> Result<(), Polar
     }
     Ok(())
 }
+
+/// The function makes sure the global string cache of Polars is enabled, as the
+/// categorical columns of the graph and the labels of the shapes are compared
+/// with each other. With the `auto-string-cache` feature, which is enabled by
+/// default, the cache is enabled here. Otherwise, the global state of Polars is
+/// left untouched, so the caller must enable it beforehand, for instance by
+/// holding a `StringCacheHolder`, and an error is returned if they did not.
+pub(crate) fn check_string_cache() -> Result<(), PolarsError> {
+    #[cfg(feature = "auto-string-cache")]
+    enable_string_cache();
+    match using_string_cache() {
+        true => Ok(()),
+        false => Err(PolarsError::InvalidOperation(
+            "The global string cache must be enabled by the caller".into(),
+        )),
+    }
+}
+
+/// The function checks that the predicates of the schema can be compared with
+/// those of the graph. Otherwise, a schema whose predicates are IRIs validated
+/// against a graph whose predicates are integers, such as those imported from
+/// DuckDB, would silently match nothing.
+pub(crate) fn check_predicate_dtype<T: Literal + Clone>(
+    edges: &DataFrame,
+    shape: &Shape<T>,
+) -> Result<(), PolarsError> {
+    let expected = match shape.first_predicate().map(Literal::lit) {
+        Some(Expr::Literal(value)) => value.get_datatype(),
+        _ => return Ok(()),
+    };
+    let actual = edges.column(Column::Predicate.as_ref())?.dtype().clone();
+    let is_text =
+        |dtype: &DataType| matches!(dtype, DataType::String | DataType::Categorical(_, _));
+    match (is_text(&expected), is_text(&actual)) {
+        (true, false) if actual.is_integer() => Err(PolarsError::SchemaMismatch(
+            "The schema uses IRI predicates but the graph has integer predicates; convert them via Id".into(),
+        )),
+        (false, true) if expected.is_integer() => Err(PolarsError::SchemaMismatch(
+            "The schema uses integer predicates but the graph has IRI predicates; convert them via Id".into(),
+        )),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+use polars::prelude::*;
+use pregel_rs::pregel::Column;
+
+/// The function computes the weakly connected components of a graph by means
+/// of a union-find over its edges. The direction of the edges is ignored.
+///
+/// Arguments:
+///
+/// * `edges`: The `DataFrame` containing the `subject` and `object` columns.
+///
+/// Returns:
+///
+/// a `Result<Vec<u32>, PolarsError>` containing the component each of the
+/// edges belongs to, in the same order as the rows of the `DataFrame`.
+pub(crate) fn components(edges: &DataFrame) -> PolarsResult<Vec<u32>> {
+    let as_strings = |column: Column| -> PolarsResult<Vec<Option<String>>> {
+        Ok(edges
+            .column(column.as_ref())?
+            .as_materialized_series()
+            .cast(&DataType::String)?
+            .str()?
+            .into_iter()
+            .map(|value| value.map(String::from))
+            .collect())
+    };
+    let subjects = as_strings(Column::Subject)?;
+    let objects = as_strings(Column::Object)?;
+
+    let mut ids = HashMap::<String, usize>::new();
+    let mut parents = Vec::<usize>::new();
+    let mut id = |vertex: Option<String>, parents: &mut Vec<usize>| -> usize {
+        let next = ids.len();
+        let id = *ids.entry(vertex.unwrap_or_default()).or_insert(next);
+        if id == parents.len() {
+            parents.push(id);
+        }
+        id
+    };
+
+    let edges = subjects
+        .into_iter()
+        .zip(objects)
+        .map(|(subject, object)| {
+            let subject = id(subject, &mut parents);
+            let object = id(object, &mut parents);
+            union(&mut parents, subject, object);
+            subject
+        })
+        .collect::<Vec<_>>();
+
+    Ok(edges
+        .into_iter()
+        .map(|subject| find(&mut parents, subject) as u32)
+        .collect())
+}
+
+fn find(parents: &mut [usize], vertex: usize) -> usize {
+    let mut root = vertex;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    // We compress the path so further lookups are faster
+    let mut vertex = vertex;
+    while parents[vertex] != root {
+        let next = parents[vertex];
+        parents[vertex] = root;
+        vertex = next;
+    }
+    root
+}
+
+fn union(parents: &mut [usize], a: usize, b: usize) {
+    let a = find(parents, a);
+    let b = find(parents, b);
+    if a != b {
+        parents[b] = a;
+    }
+}
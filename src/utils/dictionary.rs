@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+
+use polars::prelude::*;
+use pregel_rs::pregel::Column;
+
+/// The `Dictionary` struct maps the terms of a graph to compact `u32` codes, so
+/// graphs imported from textual formats can be validated with integer schemas,
+/// as those imported from DuckDB are. Codes are assigned in insertion order
+/// starting from zero.
+///
+/// Properties:
+///
+/// * `terms`: the terms of the graph, indexed by their code.
+/// * `codes`: the code assigned to each of the terms.
+#[derive(Clone, Debug, Default)]
+pub struct Dictionary {
+    terms: Vec<String>,
+    codes: HashMap<String, u32>,
+}
+
+impl Dictionary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// This function returns the code of a term, assigning it the next one in
+    /// case it is not in the dictionary yet.
+    ///
+    /// Arguments:
+    ///
+    /// * `term`: The term to be interned.
+    ///
+    /// Returns:
+    ///
+    /// A `Result` with the code of the term or an error message in case there
+    /// are no codes left.
+    pub fn intern(&mut self, term: &str) -> Result<u32, String> {
+        if let Some(code) = self.codes.get(term) {
+            return Ok(*code);
+        }
+        match u32::try_from(self.terms.len()) {
+            Ok(code) => {
+                self.terms.push(term.to_string());
+                self.codes.insert(term.to_string(), code);
+                Ok(code)
+            }
+            Err(_) => Err(format!("Cannot intern {}: too many terms", term)),
+        }
+    }
+
+    /// This function returns the code of a term, if any.
+    pub fn code(&self, term: &str) -> Option<u32> {
+        self.codes.get(term).copied()
+    }
+
+    /// This function returns the term a code was assigned to, if any.
+    pub fn term(&self, code: u32) -> Option<&str> {
+        self.terms.get(code as usize).map(String::as_str)
+    }
+
+    /// This function returns the number of terms in the dictionary.
+    pub fn len(&self) -> usize {
+        self.terms.len()
+    }
+
+    /// This function returns whether the dictionary holds no terms.
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// This function turns the codes of the `subject`, `predicate` and `object`
+    /// columns back into their terms, so the results of a validation run on an
+    /// interned graph can be exported as usual. The rest of the columns, such
+    /// as the labels, are kept as they are.
+    ///
+    /// Arguments:
+    ///
+    /// * `df`: The `DataFrame` whose columns hold the codes.
+    ///
+    /// Returns:
+    ///
+    /// a `PolarsResult<DataFrame>` with the columns holding `String` terms. The
+    /// codes not in the dictionary are turned into NULL values.
+    pub fn decode(&self, df: &DataFrame) -> PolarsResult<DataFrame> {
+        let mut df = df.clone();
+        for column in [Column::Subject, Column::Predicate, Column::Object] {
+            let codes = match df.column(column.as_ref()) {
+                Ok(codes) => codes.as_materialized_series().cast(&DataType::UInt32)?,
+                Err(_) => continue,
+            };
+            let terms = codes
+                .u32()?
+                .into_iter()
+                .map(|code| code.and_then(|code| self.term(code)))
+                .collect::<Vec<_>>();
+            df.with_column(Series::new(column.as_ptr(), terms))?;
+        }
+        Ok(df)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use polars::df;
+    use pregel_rs::pregel::Column;
+
+    use crate::utils::dictionary::Dictionary;
+
+    #[test]
+    fn intern_test() -> Result<(), String> {
+        let mut dictionary = Dictionary::new();
+        let codes = ["<a>", "<b>", "<a>"]
+            .iter()
+            .map(|term| dictionary.intern(term))
+            .collect::<Result<Vec<_>, _>>()?;
+        match (codes, dictionary.len(), dictionary.term(1)) {
+            (codes, 2, Some("<b>")) if codes == vec![0, 1, 0] => Ok(()),
+            (codes, _, _) => Err(format!("Unexpected codes: {:?}", codes)),
+        }
+    }
+
+    #[test]
+    fn decode_test() -> Result<(), String> {
+        let mut dictionary = Dictionary::new();
+        for term in ["<a>", "<knows>", "<b>"] {
+            dictionary.intern(term)?;
+        }
+        let edges = match df![
+            Column::Subject.as_ref() => [0u32, 2u32],
+            Column::Predicate.as_ref() => [1u32, 1u32],
+            Column::Object.as_ref() => [2u32, 7u32],
+        ] {
+            Ok(edges) => edges,
+            Err(_) => return Err(String::from("Error creating the edges DataFrame")),
+        };
+        let decoded = match dictionary.decode(&edges) {
+            Ok(decoded) => decoded,
+            Err(error) => return Err(error.to_string()),
+        };
+        let objects = match decoded.column(Column::Object.as_ref()) {
+            Ok(objects) => objects.str().unwrap().into_iter().collect::<Vec<_>>(),
+            Err(error) => return Err(error.to_string()),
+        };
+        match objects == vec![Some("<b>"), None] {
+            true => Ok(()),
+            false => Err(format!("Unexpected objects: {:?}", objects)),
+        }
+    }
+}
@@ -28,6 +28,9 @@ pub enum Value {
     UnitedKingdom,
     ScienceAward,
     DateTime,
+    SameAs,
+    City,
+    Town,
 }
 
 /// The `impl Value` block defines a method called `id` for the `Value` enum. This
@@ -55,6 +58,9 @@ impl Value {
             UnitedKingdom => Id::from("Q145"),
             ScienceAward => Id::from("Q11448906"),
             DateTime => Id::from("@DateTime"),
+            SameAs => Id::from("P460"),
+            City => Id::from("Q515"),
+            Town => Id::from("Q3957"),
         };
         u32::from(id)
     }
@@ -355,16 +361,73 @@ pub fn and_reference_schema() -> Shape<u32> {
             ShapeReference::new(
                 "a",
                 BirthPlace.id(),
-                TripleConstraint::new("child_a", InstanceOf.id(), NodeConstraint::Any).into(),
+                TripleConstraint::new("child_a", InstanceOf.id(), NodeConstraint::Value(City.id()))
+                    .into(),
             )
             .into(),
             ShapeReference::new(
                 "b",
-                BirthPlace.id(),
-                TripleConstraint::new("child_b", InstanceOf.id(), NodeConstraint::Any).into(),
+                Employer.id(),
+                TripleConstraint::new("child_b", AwardReceived.id(), NodeConstraint::Any).into(),
             )
             .into(),
         ],
     )
     .into()
 }
+
+pub fn cardinality_reference_schema() -> Shape<u32> {
+    Cardinality::new(
+        "cardinality",
+        ShapeReference::new(
+            "BirthUnitedKingdom",
+            BirthPlace.id(),
+            TripleConstraint::new(
+                "UnitedKingdom",
+                Country.id(),
+                NodeConstraint::Value(UnitedKingdom.id()),
+            )
+            .into(),
+        )
+        .into(),
+        Bound::Inclusive(1),
+        Bound::Inclusive(2),
+    )
+    .into()
+}
+
+pub fn predicates_schema() -> Shape<u32> {
+    TripleConstraint::with_predicates(
+        "EmployerOrBirthPlace",
+        vec![Employer.id(), BirthPlace.id()],
+        NodeConstraint::Value(London.id()),
+    )
+    .into()
+}
+
+pub fn not_value_set_schema() -> Shape<u32> {
+    TripleConstraint::new(
+        "NotHumanNorCountry",
+        InstanceOf.id(),
+        NodeConstraint::NotValueSet(vec![Human.id(), Country.id()]),
+    )
+    .into()
+}
+
+pub fn value_set_schema() -> Shape<u32> {
+    TripleConstraint::new(
+        "Settlement",
+        InstanceOf.id(),
+        NodeConstraint::ValueSet(vec![City.id(), Town.id()]),
+    )
+    .into()
+}
+
+pub fn incoming_reference_schema() -> Shape<u32> {
+    IncomingReference::new(
+        "EmployerOfHuman",
+        Employer.id(),
+        TripleConstraint::new("Human", InstanceOf.id(), NodeConstraint::Value(Human.id())).into(),
+    )
+    .into()
+}
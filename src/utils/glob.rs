@@ -0,0 +1,60 @@
+/// The function checks whether a file name matches a glob pattern, where `*`
+/// matches any sequence of characters and `?` matches any single character.
+/// The rest of the characters of the pattern must appear as they are, so no
+/// character classes nor path separators are supported.
+///
+/// Arguments:
+///
+/// * `pattern`: The glob pattern, e.g. `*.nt`.
+/// * `name`: The name of the file to be checked.
+///
+/// Returns:
+///
+/// `true` if the name matches the pattern, or `false` otherwise.
+pub(crate) fn matches(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    // The position of the last `*` seen in the pattern, together with the
+    // position of the name it was matched up to, so we can backtrack to it.
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    backtrack = Some((star, matched + 1));
+                    p = star + 1;
+                    n = matched + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::matches;
+
+    #[test]
+    fn glob_test() -> Result<(), String> {
+        match (
+            matches("*.nt", "entry.nt"),
+            matches("entry-??.nt", "entry-01.nt"),
+            matches("*.nt", "entry.nt.gz"),
+            matches("entry*", "other.nt"),
+        ) {
+            (true, true, false, false) => Ok(()),
+            result => Err(format!("Unexpected matches: {:?}", result)),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::hash::Hasher;
+
+const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const PRIME: u64 = 0x100000001b3;
+
+/// The `StableHasher` struct implements the 64-bit FNV-1a hash function. As
+/// opposed to the `DefaultHasher` of the standard library, whose algorithm may
+/// change between Rust releases, the same input always yields the same hash,
+/// so it can be used for keys that are persisted, such as those of the on-disk
+/// cache or the manifests. Integers are hashed in little-endian order, so the
+/// hashes do not depend on the platform either.
+///
+/// Properties:
+///
+/// * `state`: the hash of the bytes written so far.
+#[derive(Clone, Debug)]
+pub struct StableHasher {
+    state: u64,
+}
+
+impl StableHasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self {
+            state: OFFSET_BASIS,
+        }
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.state
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.state ^= *byte as u64;
+            self.state = self.state.wrapping_mul(PRIME);
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64)
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_i64(i as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hash::{Hash, Hasher};
+
+    use crate::utils::hash::StableHasher;
+
+    #[test]
+    fn fnv_test() {
+        let mut hasher = StableHasher::new();
+        hasher.write(b"a");
+        assert_eq!(0xaf63dc4c8601ec8c, hasher.finish())
+    }
+
+    #[test]
+    fn platform_independent_test() {
+        let mut integer = StableHasher::new();
+        1usize.hash(&mut integer);
+        let mut bytes = StableHasher::new();
+        bytes.write(&[1, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(bytes.finish(), integer.finish())
+    }
+}
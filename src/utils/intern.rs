@@ -0,0 +1,44 @@
+use std::collections::HashSet;
+use std::sync::{Mutex, OnceLock};
+
+/// The set of strings interned so far. Each of them is leaked only once, so
+/// building the same labels or IRIs over and over does not grow the memory.
+static INTERNED: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+
+/// This function turns a `String` into a `&'static str`, so it can be used
+/// where the labels of the shapes or the values of the constraints are
+/// expected. Equal strings share the same leaked allocation.
+///
+/// Arguments:
+///
+/// * `value`: The string to be interned.
+///
+/// Returns:
+///
+/// The `&'static str` standing for the provided string.
+pub(crate) fn intern(value: String) -> &'static str {
+    let mut interned = INTERNED
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    match interned.get(value.as_str()) {
+        Some(interned) => interned,
+        None => {
+            let leaked: &'static str = Box::leak(value.into_boxed_str());
+            interned.insert(leaked);
+            leaked
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::intern::intern;
+
+    #[test]
+    fn intern_test() {
+        let first = intern(String::from("interned"));
+        let second = intern(String::from("interned"));
+        assert_eq!(first.as_ptr(), second.as_ptr())
+    }
+}
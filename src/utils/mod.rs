@@ -1,5 +1,25 @@
+/// `pub mod cache;` is creating a public module named `cache`. This module
+/// contains the on-disk cache of validation results.
+pub mod cache;
+/// `pub mod dictionary;` is creating a public module named `dictionary`. This
+/// module contains the mapping between the terms of a graph and their codes.
+pub mod dictionary;
 /// `pub mod examples;` is creating a public module named `examples`. This module
 /// contains scenarios for us to play with schemas a Knowledge graphs.
 pub mod examples;
+/// `pub mod hash;` is creating a public module named `hash`. This module
+/// contains the hash function used for the keys that are persisted.
+pub mod hash;
+/// `pub mod symbol_table;` is creating a public module named `symbol_table`.
+/// This module contains the mapping between the labels and their identifiers.
+pub mod symbol_table;
+/// `pub mod xsd;` is creating a public module named `xsd`. This module contains
+/// the mapping between the XSD datatypes and the Wikidata ones.
+pub mod xsd;
 
 pub(crate) mod check;
+pub(crate) mod components;
+pub(crate) mod glob;
+pub(crate) mod intern;
+pub(crate) mod reification;
+pub(crate) mod same_as;
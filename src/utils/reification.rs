@@ -0,0 +1,79 @@
+use polars::prelude::*;
+use pregel_rs::pregel::Column;
+
+use crate::error::PSchemaError;
+
+const CLAIM: &str = "<http://www.wikidata.org/prop/P";
+const STATEMENT: &str = "<http://www.wikidata.org/prop/statement/P";
+const DIRECT: &str = "<http://www.wikidata.org/prop/direct/P";
+
+/// The function flattens the reified statements of a Wikibase graph into direct
+/// edges. That is, for every pair of edges `src p:Pn statement` and `statement
+/// ps:Pn value`, a new `src wdt:Pn value` edge is appended to the graph, so the
+/// statements can be validated as if the truthy form of the data was loaded.
+/// The columns keep their datatypes, and the rest of the columns of the new
+/// edges, such as `dtype` or `object_kind`, are taken from the `ps:Pn` edge, as
+/// it is the one holding the value.
+///
+/// Arguments:
+///
+/// * `edges`: The `DataFrame` containing the edges of the graph.
+///
+/// Returns:
+///
+/// a `Result<DataFrame, PSchemaError>` containing the original edges together
+/// with the flattened ones. An error is returned in case the predicates are
+/// not IRIs, e.g. integer ids, as the statements cannot be told apart then.
+pub(crate) fn flatten_statements(edges: DataFrame) -> Result<DataFrame, PSchemaError> {
+    let dtype = edges.column(Column::Predicate.as_ref())?.dtype().clone();
+    if !matches!(dtype, DataType::String | DataType::Categorical(_, _)) {
+        return Err(PSchemaError::Graph(format!(
+            "The reified statements can only be flattened when the predicates are IRIs, not {}",
+            dtype
+        )));
+    }
+    let names = edges
+        .get_column_names()
+        .into_iter()
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>();
+    let edges = edges.lazy();
+
+    let hop = |prefix: &str| {
+        let predicate = col(Column::Predicate.as_ref()).cast(DataType::String);
+        edges
+            .clone()
+            .filter(predicate.clone().str().starts_with(lit(prefix.to_string())))
+            .with_column(
+                predicate
+                    .str()
+                    .strip_prefix(lit(prefix.to_string()))
+                    .alias("property"),
+            )
+    };
+
+    // The subject of the new edges comes from the `p:Pn` edge, while the rest of the columns come
+    // from the `ps:Pn` one, whose columns are suffixed when joining both of them.
+    let columns = names
+        .iter()
+        .map(|name| match name.as_str() {
+            name if name == Column::Subject.as_ref() => col(name),
+            name if name == Column::Predicate.as_ref() => {
+                concat_str([lit(DIRECT), col("property")], "", false)
+                    .cast(dtype.clone())
+                    .alias(name)
+            }
+            name => col(&format!("{}_statement", name)).alias(name),
+        })
+        .collect::<Vec<_>>();
+    let flattened = hop(CLAIM)
+        .join(
+            hop(STATEMENT),
+            [col(Column::Object.as_ref()), col("property")],
+            [col(Column::Subject.as_ref()), col("property")],
+            JoinArgs::new(JoinType::Inner).with_suffix(Some("_statement".into())),
+        )
+        .select(columns);
+
+    Ok(concat([edges, flattened], UnionArgs::default())?.collect()?)
+}
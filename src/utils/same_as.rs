@@ -0,0 +1,74 @@
+use polars::prelude::*;
+use pregel_rs::pregel::Column;
+
+use crate::utils::components::components;
+
+/// The function merges the entities linked by a `sameAs`-like predicate. The
+/// equivalence classes are the connected components of the `sameAs` edges, and
+/// every subject and object of the graph belonging to one of them is rewritten
+/// to its representative, which is the first member found. The `sameAs` edges
+/// are discarded afterwards, as they would become self-loops. The rest of the
+/// columns of the edges are kept.
+///
+/// Arguments:
+///
+/// * `edges`: The `DataFrame` containing the edges of the graph.
+/// * `predicate`: The predicate linking the aliases of the same entity.
+///
+/// Returns:
+///
+/// a `Result<DataFrame, PolarsError>` containing the edges of the merged graph.
+pub(crate) fn merge_same_as<T: Literal>(edges: DataFrame, predicate: T) -> PolarsResult<DataFrame> {
+    let is_same_as = col(Column::Predicate.as_ref()).eq(lit(predicate));
+
+    let links = edges
+        .clone()
+        .lazy()
+        .filter(is_same_as.clone())
+        .select([col(Column::Subject.as_ref()), col(Column::Object.as_ref())])
+        .collect()?;
+    let component = components(&links)?;
+
+    let members = |column: Column| {
+        links
+            .clone()
+            .lazy()
+            .select([col(column.as_ref()).alias("alias")])
+            .with_column(lit(Series::new("component".into(), component.clone())))
+    };
+    let aliases = concat(
+        [members(Column::Subject), members(Column::Object)],
+        UnionArgs::default(),
+    )?;
+    let mapping = aliases
+        .clone()
+        .join(
+            aliases
+                .group_by_stable([col("component")])
+                .agg([col("alias").first().alias("canonical")]),
+            [col("component")],
+            [col("component")],
+            JoinArgs::new(JoinType::Inner),
+        )
+        .select([col("alias"), col("canonical")])
+        .unique(None, UniqueKeepStrategy::Any);
+
+    let canonical = |column: Column| {
+        when(col("canonical").is_null())
+            .then(col(column.as_ref()))
+            .otherwise(col("canonical"))
+            .alias(column.as_ref())
+    };
+
+    // The rest of the columns, such as `dtype` or `_order`, are kept as they are.
+    edges
+        .lazy()
+        .filter(is_same_as.not())
+        .left_join(mapping.clone(), col(Column::Subject.as_ref()), col("alias"))
+        .with_column(canonical(Column::Subject))
+        .drop(["canonical"])
+        .left_join(mapping, col(Column::Object.as_ref()), col("alias"))
+        .with_column(canonical(Column::Object))
+        .drop(["canonical"])
+        .collect()
+}
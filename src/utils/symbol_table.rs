@@ -0,0 +1,178 @@
+use std::hash::Hash;
+
+use bimap::BiMap;
+
+/// `Label` is the type of the identifiers assigned to the symbols.
+pub type Label = u16;
+
+/// The `SymbolTable` struct assigns a unique numeric identifier to each of the
+/// labels of a schema, so they can be referred to compactly. Identifiers are
+/// assigned in insertion order starting from zero, so up to 65536 labels fit
+/// with the default `Label` identifiers. Narrower identifiers can be used too,
+/// which is mostly useful for exercising a full table cheaply.
+///
+/// Properties:
+///
+/// * `symbols`: the bidirectional mapping between the labels and their ids.
+#[derive(Clone, Debug, Default)]
+pub struct SymbolTable<L: Eq + Hash = Label> {
+    symbols: BiMap<&'static str, L>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<L: Copy + Eq + Hash + TryFrom<usize>> SymbolTable<L> {
+    /// This function inserts a label into the table, assigning it the next
+    /// identifier. Inserting a label that is already in the table returns its
+    /// identifier instead of allocating a new one.
+    ///
+    /// Arguments:
+    ///
+    /// * `label`: The label to be inserted.
+    ///
+    /// Returns:
+    ///
+    /// A `Result` with the identifier of the label or an error message in case
+    /// the table is full.
+    pub fn try_insert(&mut self, label: &'static str) -> Result<L, String> {
+        if let Some(id) = self.get(label) {
+            return Ok(id);
+        }
+        match L::try_from(self.symbols.len()) {
+            Ok(id) => {
+                self.symbols.insert(label, id);
+                Ok(id)
+            }
+            Err(_) => Err(format!(
+                "The symbol table cannot hold more than {} labels, so {} cannot be inserted",
+                self.symbols.len(),
+                label
+            )),
+        }
+    }
+
+    /// This function inserts a label into the table as `try_insert` does.
+    ///
+    /// Arguments:
+    ///
+    /// * `label`: The label to be inserted.
+    ///
+    /// Returns:
+    ///
+    /// The identifier of the label.
+    ///
+    /// Panics:
+    ///
+    /// In case the table is full, e.g. it already holds 65536 other labels.
+    pub fn insert(&mut self, label: &'static str) -> L {
+        match self.try_insert(label) {
+            Ok(id) => id,
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// This function returns the identifier of a label, interning it in case
+    /// it is not in the table yet. Thus, the same label always yields the same
+    /// identifier. It is equivalent to `insert`.
+    ///
+    /// Arguments:
+    ///
+    /// * `label`: The label to be interned.
+    ///
+    /// Returns:
+    ///
+    /// The identifier of the label.
+    pub fn intern(&mut self, label: &'static str) -> L {
+        self.insert(label)
+    }
+
+    /// This function returns the identifier of a label, if any.
+    pub fn get(&self, label: &'static str) -> Option<L> {
+        self.symbols.get_by_left(label).copied()
+    }
+
+    /// This function checks whether a label is in the table.
+    pub fn contains(&self, label: &str) -> bool {
+        self.symbols.contains_left(label)
+    }
+
+    /// This function returns the label associated with an identifier, if any.
+    pub fn label(&self, id: L) -> Option<&'static str> {
+        self.symbols.get_by_right(&id).copied()
+    }
+
+    /// This function removes all the labels from the table, so the identifiers
+    /// are assigned from scratch again.
+    pub fn clear(&mut self) {
+        self.symbols.clear();
+    }
+
+    /// This function returns the number of labels in the table.
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// This function returns whether the table holds no labels.
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::intern::intern;
+    use crate::utils::symbol_table::SymbolTable;
+
+    #[test]
+    fn clear_test() {
+        let mut symbols = SymbolTable::new();
+        symbols.insert("Human");
+        symbols.insert("London");
+        assert_eq!(2, symbols.len());
+
+        symbols.clear();
+        assert!(symbols.is_empty());
+        assert_eq!(0, symbols.insert("Researcher"));
+        assert_eq!(None, symbols.get("Human"))
+    }
+
+    #[test]
+    fn idempotent_insert_test() {
+        let mut symbols = SymbolTable::new();
+        assert_eq!(0, symbols.insert("Human"));
+        assert_eq!(1, symbols.insert("London"));
+        assert_eq!(0, symbols.insert("Human"));
+        assert_eq!(2, symbols.len())
+    }
+
+    #[test]
+    fn many_labels_test() {
+        let mut symbols = SymbolTable::new();
+        let labels = (0..300)
+            .map(|i| intern(format!("Shape{}", i)))
+            .collect::<Vec<_>>();
+        for label in &labels {
+            symbols.insert(label);
+        }
+        assert_eq!(300, symbols.len());
+        for (id, label) in labels.iter().enumerate() {
+            assert_eq!(Some(id as u16), symbols.get(label));
+            assert_eq!(Some(*label), symbols.label(id as u16))
+        }
+    }
+
+    #[test]
+    fn overflow_test() {
+        // A table with narrower identifiers overflows the same way, without filling 65536 labels.
+        let mut symbols = SymbolTable::<u8>::default();
+        for i in 0..=u8::MAX as usize {
+            assert_eq!(Ok(i as u8), symbols.try_insert(intern(i.to_string())));
+        }
+        assert!(symbols.try_insert("Overflow").is_err());
+        assert_eq!(Ok(0), symbols.try_insert("0"))
+    }
+}
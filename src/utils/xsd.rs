@@ -0,0 +1,111 @@
+use wikidata_rs::dtype::DataType;
+
+use crate::backends::ntriples::NTriples;
+
+/// The `Datatype` enum represents the datatype of a literal as found in RDF
+/// dumps. Wikidata has a single datatype for numbers and none for booleans, so
+/// the XSD datatypes lacking a Wikidata counterpart of their own are kept apart
+/// instead of being collapsed into it, while the rest wrap the Wikidata one.
+#[derive(Clone, Debug)]
+pub enum Datatype {
+    /// A datatype stored as the provided Wikidata `DataType`.
+    Wikidata(DataType),
+    /// `xsd:integer` and the rest of the integer datatypes derived from it.
+    Integer,
+    /// `xsd:decimal`, `xsd:double` and `xsd:float`.
+    Decimal,
+    /// `xsd:boolean`.
+    Boolean,
+}
+
+impl Datatype {
+    /// This function returns the Wikidata datatype the literals of this
+    /// datatype are stored as, so it can be used with the DuckDB backend.
+    ///
+    /// Returns:
+    ///
+    /// The Wikidata `DataType`, if any. Both `Integer` and `Decimal` are stored
+    /// as a `Quantity`, whereas `Boolean` has no Wikidata counterpart.
+    pub fn wikidata(&self) -> Option<DataType> {
+        match self {
+            Datatype::Wikidata(dtype) => Some(dtype.clone()),
+            Datatype::Integer | Datatype::Decimal => Some(DataType::Quantity),
+            Datatype::Boolean => None,
+        }
+    }
+}
+
+/// This function maps the IRI of an XSD datatype onto the datatype its literals
+/// are stored as, so datatype constraints can be built straight from the IRIs
+/// found in RDF dumps. The IRI may be written in full, enclosed in angle
+/// brackets or as an `xsd:` prefixed name, as in the N-Triples backend. The
+/// integer datatypes, such as `xsd:long` or `xsd:nonNegativeInteger`, are all
+/// mapped to `Integer`, while `xsd:double` and `xsd:float` are mapped to
+/// `Decimal`.
+///
+/// Arguments:
+///
+/// * `iri`: The IRI of the XSD datatype.
+///
+/// Returns:
+///
+/// The corresponding `Datatype`, if any.
+pub fn from_xsd_iri(iri: &str) -> Option<Datatype> {
+    let iri = iri
+        .strip_prefix('<')
+        .and_then(|iri| iri.strip_suffix('>'))
+        .unwrap_or(iri);
+    let canonical = NTriples::canonical_datatype(iri);
+    match canonical.strip_prefix("http://www.w3.org/2001/XMLSchema#")? {
+        "string" => Some(Datatype::Wikidata(DataType::String)),
+        "date" | "dateTime" | "dateTimeStamp" | "gYear" | "gYearMonth" => {
+            Some(Datatype::Wikidata(DataType::DateTime))
+        }
+        "integer" | "long" | "int" | "short" | "byte" | "nonNegativeInteger"
+        | "positiveInteger" | "nonPositiveInteger" | "negativeInteger" => Some(Datatype::Integer),
+        "decimal" | "double" | "float" => Some(Datatype::Decimal),
+        "boolean" => Some(Datatype::Boolean),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wikidata_rs::dtype::DataType;
+
+    use crate::utils::xsd::{from_xsd_iri, Datatype};
+
+    #[test]
+    fn from_xsd_iri_test() -> Result<(), String> {
+        match (
+            from_xsd_iri("http://www.w3.org/2001/XMLSchema#integer"),
+            from_xsd_iri("<http://www.w3.org/2001/XMLSchema#dateTime>"),
+            from_xsd_iri("xsd:string"),
+            from_xsd_iri("xsd:decimal"),
+            from_xsd_iri("xsd:boolean"),
+            from_xsd_iri("http://example.org/integer"),
+        ) {
+            (
+                Some(Datatype::Integer),
+                Some(Datatype::Wikidata(DataType::DateTime)),
+                Some(Datatype::Wikidata(DataType::String)),
+                Some(Datatype::Decimal),
+                Some(Datatype::Boolean),
+                None,
+            ) => Ok(()),
+            _ => Err(String::from("Unexpected datatypes")),
+        }
+    }
+
+    #[test]
+    fn wikidata_test() -> Result<(), String> {
+        match (
+            Datatype::Integer.wikidata(),
+            Datatype::Decimal.wikidata(),
+            Datatype::Boolean.wikidata(),
+        ) {
+            (Some(DataType::Quantity), Some(DataType::Quantity), None) => Ok(()),
+            _ => Err(String::from("Unexpected Wikidata datatypes")),
+        }
+    }
+}
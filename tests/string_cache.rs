@@ -0,0 +1,27 @@
+//! The global string cache of Polars is shared by the whole process, so these
+//! tests live in their own binary, where no other test can enable it behind
+//! their back.
+#![cfg(not(feature = "auto-string-cache"))]
+
+use polars::prelude::*;
+use polars::StringCacheHolder;
+use pschema_rs::error::PSchemaError;
+use pschema_rs::pschema::PSchema;
+use pschema_rs::utils::examples::{paper_graph, simple_schema};
+
+#[test]
+fn caller_managed_string_cache_test() -> Result<(), String> {
+    // Without the `auto-string-cache` feature, the crate does not enable the cache by itself.
+    match PSchema::new(simple_schema()).validate(paper_graph()?) {
+        Err(PSchemaError::Polars(PolarsError::InvalidOperation(_))) => (),
+        Err(error) => return Err(format!("Unexpected error: {}", error)),
+        Ok(_) => return Err(String::from("The string cache should have been required")),
+    }
+    // Once the caller holds the cache, the very same validation succeeds.
+    let _holder = StringCacheHolder::hold();
+    match PSchema::new(simple_schema()).validate(paper_graph()?) {
+        Ok(subset) if subset.height() > 0 => Ok(()),
+        Ok(_) => Err(String::from("Some subjects should have conformed")),
+        Err(error) => Err(error.to_string()),
+    }
+}